@@ -0,0 +1,96 @@
+#![feature(test)]
+
+extern crate arrow;
+extern crate libc;
+extern crate test;
+
+use std::cmp;
+use std::sync::Arc;
+
+use arrow::array::Array;
+use arrow::buffer::PoolBuffer;
+use arrow::builder::{Append, ArrayBuilder};
+use arrow::common::status::ArrowError;
+use arrow::common::ty::Ty;
+use arrow::memory_pool::{DefaultMemoryPool, MemoryPool};
+
+use test::Bencher;
+
+const NUM_VALUES: i64 = 1 << 20;
+// values of small arrays fit in heap pages, which are smaller than 128 KiB
+const NUM_SMALL_VALUES: i64 = 1 << 13;
+
+/// A pool which always copies values into a new page on reallocation, which is how
+/// DefaultMemoryPool used to reallocate pages.
+struct CopyingMemoryPool {
+  parent: DefaultMemoryPool
+}
+
+impl MemoryPool for CopyingMemoryPool {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    self.parent.allocate(size)
+  }
+
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    match self.parent.allocate(new_size) {
+      Ok(new_page) => {
+        if old_size > 0 {
+          unsafe {
+            libc::memcpy(new_page as *mut libc::c_void, page as *const libc::c_void, cmp::min(old_size, new_size) as usize);
+          }
+          self.parent.free(page, old_size);
+        }
+        Ok(new_page)
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn free(&self, page: *const u8, size: i64) {
+    self.parent.free(page, size)
+  }
+
+  fn bytes_allocated(&self) -> i64 {
+    self.parent.bytes_allocated()
+  }
+
+  fn max_memory(&self) -> i64 {
+    self.parent.max_memory()
+  }
+}
+
+fn append_values(pool: Arc<MemoryPool>, num_values: i64) -> Array {
+  let mut builder = ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool)).unwrap();
+  for i in 0..num_values {
+    builder.append(i).unwrap();
+  }
+  Array::from(builder)
+}
+
+#[bench]
+fn bench_append_with_copying_reallocation(b: &mut Bencher) {
+  let pool: Arc<MemoryPool> = Arc::new(CopyingMemoryPool { parent: DefaultMemoryPool::new() });
+  b.bytes = (NUM_VALUES * 8) as u64;
+  b.iter(|| append_values(pool.clone(), NUM_VALUES));
+}
+
+#[bench]
+fn bench_append_with_in_place_reallocation(b: &mut Bencher) {
+  let pool: Arc<MemoryPool> = Arc::new(DefaultMemoryPool::new());
+  b.bytes = (NUM_VALUES * 8) as u64;
+  b.iter(|| append_values(pool.clone(), NUM_VALUES));
+}
+
+#[bench]
+fn bench_append_small_with_copying_reallocation(b: &mut Bencher) {
+  let pool: Arc<MemoryPool> = Arc::new(CopyingMemoryPool { parent: DefaultMemoryPool::new() });
+  b.bytes = (NUM_SMALL_VALUES * 8) as u64;
+  b.iter(|| append_values(pool.clone(), NUM_SMALL_VALUES));
+}
+
+#[bench]
+fn bench_append_small_with_in_place_reallocation(b: &mut Bencher) {
+  let pool: Arc<MemoryPool> = Arc::new(DefaultMemoryPool::new());
+  b.bytes = (NUM_SMALL_VALUES * 8) as u64;
+  b.iter(|| append_values(pool.clone(), NUM_SMALL_VALUES));
+}
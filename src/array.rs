@@ -1,139 +1,219 @@
 use common::status::ArrowError;
 use common::bit_util;
-use common::ty;
-use common::ty::Ty;
+use common::ty::{Ty, UnionMode, BufferType, BufferDesc, IntervalUnit};
+use common::half::f16;
+use common::decimal::Decimal128;
 use memory_pool::MemoryPool;
-use buffer::{Buffer, PoolBuffer};
-use builder::{ArrayBuilder, BuilderData, Size};
+use buffer::{Buffer, PoolBuffer, SharedBuffer};
+use builder::{ArrayBuilder, Size, Append};
 
-use std::ptr;
 use std::mem;
 use std::slice;
+use std::str;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::marker::PhantomData;
 
 use std::fmt::{Debug, Formatter, Error};
 
-#[derive(Eq, PartialEq)]
-pub struct Array<'a> {
-//  ty: Ty,
-//  length: i64,
-//  offset: i64,
-//  null_count: i64,
-//  null_bitmap: Option<PoolBuffer>,
-//  data: ArrayData
-
-  builder: ArrayBuilder<'a>,
-  data: ArrayData<'a>
-}
-
-#[derive(Eq, PartialEq)]
-pub enum ArrayData<'a> {
-  Null,
-  Bool,
-
-  UInt8 {
-    values: &'a [u8]
-  },
-  Int8 {
-    values: &'a [i8]
-  },
-  UInt16 {
-    values: &'a [u16]
-  },
-  Int16 {
-    values: &'a [i16]
-  },
-  UInt32 {
-    values: &'a [u32]
-  },
-  Int32 {
-    values: &'a [i32]
-  },
-  UInt64 {
-    values: &'a [u64]
-  },
-  Int64 {
-    values: &'a [i64]
-  },
-
-  HalfFloat {
-    values: *const u16
-  },
-  Float {
-    values: *const f32
-  },
-  Double {
-    values: *const f64
-  },
-
-  Binary {
-    lengths_and_values: *const u8,
-    total_len: isize
-//    offsets: Vec<i32>,
-  },
-  String {
-    value_offsets: *const i32,
-    values: *const u8
-  },
-  FixedSizeBinary {
-    values: *const u8
-  },
-
-  Date64 {
-    values: *const i64
-  },
-  Date32 {
-    values: *const i32
-  },
-  Timestamp {
-    values: *const i64
-  },
-  Time32 {
-    values: *const i32
-  },
-  Time64 {
-    values: *const i64
-  },
-  Interval {
-    values: *const i64
-  },
-
-  Decimal {
-    values: *const u8
-  },
-
-  List {
-    value_offsets: *const i32,
-    value_array: Box<Array<'a>>
-  },
-  Struct {
-    fields: Vec<Box<Array<'a>>>
-  },
-  Union {
-    fields: Vec<Box<Array<'a>>>,
-    value_offsets: *const i32
-  },
-
-  Dictionary {
-    indices: Box<Array<'a>>
-  }
-}
-
-//impl PartialEq for ArrayData {
-//  fn eq(&self, other: &Self) -> bool {
-//    // TODO
-//    unimplemented!()
-//  }
-//}
-//
-//impl Eq for ArrayData {
-//
-//}
+/// The null count of array data whose null count is computed when it's requested for the first
+/// time.
+pub const UNKNOWN_NULL_COUNT: i64 = -1;
+
+/// The immutable data of an array. `buffers` follow the layout of `Ty::get_buffer_layout()`, and
+/// a missing validity buffer means that the array doesn't have nulls. Nested arrays keep the data
+/// of their children in `child_data`, while the dictionary of a dictionary array is a part of its
+/// type.
+///
+/// Values start from `offset` in the buffers, so that slices can share the buffers with the
+/// array they are sliced from.
+pub struct ArrayData {
+  ty: Ty,
+  length: i64,
+  offset: i64,
+  null_count: AtomicI64,
+  buffers: Vec<Option<SharedBuffer>>,
+  child_data: Vec<Arc<ArrayData>>
+}
+
+impl ArrayData {
+  /// Creates array data and validates it against its type. This fails with `StatusCode::Invalid`
+  /// if the buffers or the child data don't match the layout of the type, they are too small to
+  /// hold `offset + length` values, or the buffers aren't aligned to the width of their values.
+  /// Values that are read without checks are validated as well: value offsets must be increasing,
+  /// strings must be valid UTF-8, and union slots and dictionary indices must be in range.
+  pub fn new(ty: Ty, length: i64, offset: i64, null_count: i64, buffers: Vec<Option<SharedBuffer>>, child_data: Vec<Arc<ArrayData>>) -> Result<ArrayData, ArrowError> {
+    let data = unsafe { ArrayData::new_unchecked(ty, length, offset, null_count, buffers, child_data) };
+    match data.validate() {
+      Ok(_) => Ok(data),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Creates array data without validating it.
+  ///
+  /// This is unsafe because values are read from the buffers and the child data without bounds
+  /// checks, so they must be valid for `offset + length` values of `ty`.
+  pub unsafe fn new_unchecked(ty: Ty, length: i64, offset: i64, null_count: i64, buffers: Vec<Option<SharedBuffer>>, child_data: Vec<Arc<ArrayData>>) -> ArrayData {
+    let null_count = match ty {
+      Ty::NA => length,
+      _ => null_count
+    };
+    ArrayData {
+      ty,
+      length,
+      offset,
+      null_count: AtomicI64::new(null_count),
+      buffers,
+      child_data
+    }
+  }
+
+  /// Validates the data as `new()` does, and fails with `StatusCode::Invalid` if the buffers or the
+  /// child data can't be read safely.
+  pub fn validate(&self) -> Result<(), ArrowError> {
+    let end = match self.offset.checked_add(self.length) {
+      Some(end) if self.offset >= 0 && self.length >= 0 => end,
+      _ => return Err(ArrowError::invalid(format!("offset [{}] and length [{}] are out of range", self.offset, self.length)))
+    };
+    let null_count = self.null_count.load(Ordering::Relaxed);
+    if null_count < UNKNOWN_NULL_COUNT || null_count > self.length {
+      return Err(ArrowError::invalid(format!("null count [{}] is out of range for length [{}]", null_count, self.length)));
+    }
+
+    let layout = self.ty.get_buffer_layout();
+    if self.buffers.len() != layout.len() {
+      return Err(ArrowError::invalid(
+        format!("{:?} needs [{}] buffers, but [{}] buffers are given", self.ty, layout.len(), self.buffers.len())
+      ));
+    }
+    // nulls of a union are the nulls of its fields
+    if let Ty::Union { .. } = self.ty {
+      if self.buffers[0].is_some() || null_count > 0 {
+        return Err(ArrowError::invalid(format!("{:?} can't have a validity bitmap or nulls", self.ty)));
+      }
+    }
+    // the range of the values of variable-width and list arrays, which is known after the offsets
+    // are validated
+    let mut value_range = (0, 0);
+    for (i, (desc, buffer)) in layout.iter().zip(self.buffers.iter()).enumerate() {
+      let buffer = match (buffer, desc.buffer_type()) {
+        (&Some(ref buffer), _) => buffer,
+        (&None, &BufferType::Validity) if null_count <= 0 => continue,
+        (&None, _) => return Err(ArrowError::invalid(format!("buffer [{}] of {:?} is missing", i, self.ty)))
+      };
+      // a dense union has an offset per slot rather than the bounds of values
+      let value_offsets = match (desc.buffer_type(), &self.ty) {
+        (&BufferType::Offset, &Ty::Union { .. }) => false,
+        (&BufferType::Offset, _) => true,
+        _ => false
+      };
+      let num_values = match (desc.buffer_type(), &self.ty) {
+        // value offsets of empty arrays may be empty
+        (&BufferType::Offset, _) if value_offsets && self.length == 0 && buffer.size() == 0 => 0,
+        (&BufferType::Offset, _) if value_offsets => end + 1,
+        (&BufferType::Data, &Ty::Binary) | (&BufferType::Data, &Ty::String) => value_range.1,
+        _ => end
+      };
+      let size = match num_values.checked_mul(desc.bit_width() as i64) {
+        Some(bits) => bits / 8 + if bits % 8 == 0 { 0 } else { 1 },
+        None => return Err(ArrowError::invalid(format!("[{}] values of {:?} are too large", num_values, self.ty)))
+      };
+      if buffer.size() < size {
+        return Err(ArrowError::invalid(
+          format!("buffer [{}] of {:?} has [{}] bytes, but [{}] bytes are needed", i, self.ty, buffer.size(), size)
+        ));
+      }
+      let alignment = value_alignment(&self.ty, desc);
+      if buffer.data() as usize % alignment != 0 {
+        return Err(ArrowError::invalid(format!("buffer [{}] of {:?} is not aligned to [{}] bytes", i, self.ty, alignment)));
+      }
+
+      if value_offsets && num_values > 0 {
+        let offsets = &raw_value_offsets(buffer, self.offset, self.length)[..];
+        match check_value_offsets(offsets) {
+          Ok(range) => value_range = range,
+          Err(e) => return Err(e)
+        }
+      }
+    }
+
+    if self.ty == Ty::String && value_range.1 > value_range.0 {
+      let values = unsafe {
+        slice::from_raw_parts(self.buffer(2).data().offset(value_range.0 as isize), (value_range.1 - value_range.0) as usize)
+      };
+      match check_utf8(values, raw_value_offsets(self.buffer(1), self.offset, self.length)) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+
+    let child_types = match self.ty {
+      Ty::List { ref value_type } => vec![value_type.as_ref()],
+      Ty::Struct { ref fields } | Ty::Union { ref fields, .. } => fields.iter().map(|field| field.data_type()).collect(),
+      _ => Vec::new()
+    };
+    if self.child_data.len() != child_types.len() {
+      return Err(ArrowError::invalid(
+        format!("{:?} needs [{}] child data, but [{}] child data are given", self.ty, child_types.len(), self.child_data.len())
+      ));
+    }
+    for (i, (child, child_type)) in self.child_data.iter().zip(child_types.into_iter()).enumerate() {
+      let child_len = match self.ty {
+        Ty::List { .. } => value_range.1,
+        // slots of a dense union are checked against the lengths of the children below
+        Ty::Union { mode: UnionMode::DENSE, .. } => 0,
+        _ => end
+      };
+      if child.ty() != child_type || child.len() < child_len {
+        return Err(ArrowError::invalid(
+          format!("child data [{}] of {:?} must be {:?} of at least [{}] values", i, self.ty, child_type, child_len)
+        ));
+      }
+    }
+
+    match self.ty {
+      Ty::Union { .. } | Ty::Dictionary { .. } if self.length > 0 => {
+        let array = Array::new(Arc::new(self.clone()));
+        match self.ty {
+          Ty::Union { .. } => check_union_slots(&array),
+          _ => check_dictionary_indices(&array)
+        }
+      },
+      _ => Ok(())
+    }
+  }
 
-impl <'a> Array<'a> {
   #[inline]
-  fn compute_null_count(null_bitmap: &Option<PoolBuffer>, offset: i64, length: i64) -> i64 {
+  pub fn ty(&self) -> &Ty {
+    &self.ty
+  }
+
+  #[inline]
+  pub fn len(&self) -> i64 {
+    self.length
+  }
+
+  #[inline]
+  pub fn offset(&self) -> i64 {
+    self.offset
+  }
+
+  pub fn null_count(&self) -> i64 {
+    let null_count = self.null_count.load(Ordering::Relaxed);
+    if null_count == UNKNOWN_NULL_COUNT {
+      let null_count = ArrayData::compute_null_count(self.null_bitmap(), self.offset, self.length);
+      self.null_count.store(null_count, Ordering::Relaxed);
+      null_count
+    } else {
+      null_count
+    }
+  }
+
+  #[inline]
+  fn compute_null_count(null_bitmap: Option<&SharedBuffer>, offset: i64, length: i64) -> i64 {
     match null_bitmap {
-      &Some(ref buffer) => {
+      Some(buffer) => {
         let null_bitmap_data = buffer.data();
         if !null_bitmap_data.is_null() {
           length - bit_util::count_set_bits(null_bitmap_data, offset, length)
@@ -141,70 +221,124 @@ impl <'a> Array<'a> {
           0
         }
       },
-      &None => 0
+      None => 0
     }
   }
 
-  pub fn from(builder: ArrayBuilder<'a>) -> Array<'a> {
-    let data = match builder.data() {
-      &BuilderData::Null => ArrayData::Null,
-      &BuilderData::Bool { ref null_bitmap, ref data } => ArrayData::Bool,
-      &BuilderData::UInt8 { ref null_bitmap, ref data } => {
-        ArrayData::UInt8 {
-          values : unsafe { slice::from_raw_parts(data.data(), builder.len() as usize) }
-        }
-      },
-      &BuilderData::Int8 { ref null_bitmap, ref data } => {
-        ArrayData::Int8 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const i8>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::UInt16 { ref null_bitmap, ref data } => {
-        ArrayData::UInt16 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const u16>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::Int16 { ref null_bitmap, ref data } => {
-        ArrayData::Int16 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const i16>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::UInt32 { ref null_bitmap, ref data } => {
-        ArrayData::UInt32 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const u32>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::Int32 { ref null_bitmap, ref data } => {
-        ArrayData::Int32 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const i32>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::UInt64 { ref null_bitmap, ref data } => {
-        ArrayData::UInt64 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const u64>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::Int64 { ref null_bitmap, ref data } => {
-        ArrayData::Int64 {
-          values : unsafe { slice::from_raw_parts(mem::transmute::<*const u8, *const i64>(data.data()), builder.len() as usize) }
-        }
-      },
-      &BuilderData::Binary { ref null_bitmap, ref lengths_and_data, cur_offset } => {
-        ArrayData::Binary {
-          lengths_and_values: lengths_and_data.data(),
-          total_len: cur_offset
-        }
-      },
-      _ => panic!()
+  #[inline]
+  pub fn buffers(&self) -> &Vec<Option<SharedBuffer>> {
+    &self.buffers
+  }
+
+  #[inline]
+  pub fn child_data(&self) -> &Vec<Arc<ArrayData>> {
+    &self.child_data
+  }
+
+  #[inline]
+  pub fn null_bitmap(&self) -> Option<&SharedBuffer> {
+    match self.buffers.first() {
+      Some(&Some(ref null_bitmap)) => Some(null_bitmap),
+      _ => None
+    }
+  }
+
+  /// Returns the i-th buffer. Panics if the buffer doesn't exist.
+  #[inline]
+  pub fn buffer(&self, i: usize) -> &SharedBuffer {
+    match self.buffers.get(i) {
+      Some(&Some(ref buffer)) => buffer,
+      _ => panic!("buffer [{}] of {:?} doesn't exist", i, self.ty)
+    }
+  }
+
+  /// Returns the data of `length` values starting from `offset`. The buffers and the child data
+  /// are shared with this data.
+  pub fn slice(&self, offset: i64, length: i64) -> ArrayData {
+    if offset < 0 || length < 0 || offset + length > self.length {
+      panic!("slice [{}, {}) is out of the array of length [{}]", offset, offset + length, self.length)
+    }
+
+    let null_count = match self.null_count.load(Ordering::Relaxed) {
+      // slices of an array without nulls don't have nulls either
+      0 => 0,
+      _ => UNKNOWN_NULL_COUNT
     };
+    // a slice of valid data is valid because it is in the range of the data
+    unsafe {
+      ArrayData::new_unchecked(self.ty.clone(), length, self.offset + offset, null_count, self.buffers.clone(), self.child_data.clone())
+    }
+  }
+}
+
+impl Clone for ArrayData {
+  fn clone(&self) -> ArrayData {
+    ArrayData {
+      ty: self.ty.clone(),
+      length: self.length,
+      offset: self.offset,
+      null_count: AtomicI64::new(self.null_count.load(Ordering::Relaxed)),
+      buffers: self.buffers.clone(),
+      child_data: self.child_data.clone()
+    }
+  }
+}
 
+/// Array data are equal if they have the same type and the same values, regardless of their
+/// offsets and the bytes of null slots. Floating-point values are compared by their bits, so NaNs
+/// with the same bits are equal and `Eq` holds.
+impl PartialEq for ArrayData {
+  fn eq(&self, other: &ArrayData) -> bool {
+    if self.ty != other.ty || self.length != other.length || self.null_count() != other.null_count() {
+      return false;
+    }
+    let left = Array::new(Arc::new(self.clone()));
+    let right = Array::new(Arc::new(other.clone()));
+    (0..self.length).all(|i| value_equals(&left, i, &right, i))
+  }
+}
+
+impl Eq for ArrayData {}
+
+impl Debug for ArrayData {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    f.debug_struct("ArrayData")
+      .field("ty", &self.ty)
+      .field("offset", &self.offset)
+      .field("length", &self.length)
+      .field("null_count", &self.null_count())
+      .field("num_buffers", &self.buffers.len())
+      .field("child_data", &self.child_data)
+      .finish()
+  }
+}
+
+/// An array is a handle of shared `ArrayData`, so cloning or slicing an array doesn't copy any
+/// value.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Array {
+  data: Arc<ArrayData>
+}
+
+impl Array {
+  pub fn new(data: Arc<ArrayData>) -> Array {
     Array {
-      builder,
       data
     }
   }
 
-  pub fn data(&self) -> &ArrayData {
+  pub fn from(builder: ArrayBuilder) -> Array {
+    Array::new(Arc::new(builder.finish()))
+  }
+
+  /// Returns a zero-copy slice of `length` values starting from `offset`. The slice shares the
+  /// underlying buffers with this array, and its null count is computed lazily.
+  pub fn slice(&self, offset: i64, length: i64) -> Array {
+    Array::new(Arc::new(self.data.slice(offset, length)))
+  }
+
+  #[inline]
+  pub fn data(&self) -> &Arc<ArrayData> {
     &self.data
   }
 
@@ -213,7 +347,7 @@ impl <'a> Array<'a> {
       &Ty::NA => true,
       _ => match self.null_bitmap_buffer() {
         Some(ref null_bitmap) => bit_util::bit_not_set(null_bitmap.data(), i + self.offset()),
-        None => panic!()
+        None => false
       }
     }
   }
@@ -223,183 +357,272 @@ impl <'a> Array<'a> {
       &Ty::NA => false,
       _ => match self.null_bitmap_buffer() {
         Some(ref null_bitmap) => bit_util::get_bit(null_bitmap.data(), i + self.offset()),
-        None => panic!()
+        None => true
       }
     }
   }
 
   #[inline]
   pub fn len(&self) -> i64 {
-    self.builder.len()
+    self.data.len()
   }
 
   #[inline]
   pub fn offset(&self) -> i64 {
-//    self.offset
-    unimplemented!()
+    self.data.offset()
   }
 
   #[inline]
   pub fn null_count(&self) -> i64 {
-    self.builder.null_count()
+    self.data.null_count()
   }
 
   #[inline]
   pub fn ty(&self) -> &Ty {
-    self.builder.ty()
+    self.data.ty()
   }
 
   #[inline]
-  pub fn null_bitmap_buffer(&self) -> Option<&PoolBuffer> {
-    self.builder.null_bitmap()
+  pub fn null_bitmap_buffer(&self) -> Option<&SharedBuffer> {
+    self.data.null_bitmap()
   }
 
-//  #[inline]
-//  pub fn data(&self) -> &ArrayData {
-//    &self.data
-//  }
+  /// Returns the values of this array in the i-th buffer.
+  fn typed_values<T>(&self, i: usize) -> &[T] {
+    if self.len() == 0 {
+      &[]
+    } else {
+      unsafe {
+        let values = mem::transmute::<*const u8, *const T>(self.data.buffer(i).data());
+        slice::from_raw_parts(values.offset(self.offset() as isize), self.len() as usize)
+      }
+    }
+  }
+
+  /// Returns the array of the i-th child data.
+  #[inline]
+  fn child(&self, i: usize) -> Array {
+    Array::new(self.data.child_data()[i].clone())
+  }
 }
 
-impl <'a> Debug for Box<Array<'a>> {
+impl Debug for Array {
   fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-    unimplemented!()
+    f.debug_struct("Array")
+      .field("ty", self.ty())
+      .field("offset", &self.offset())
+      .field("length", &self.len())
+      .field("null_count", &self.null_count())
+      .finish()
   }
 }
 
-impl <'a> Clone for Box<Array<'a>> {
-  fn clone(&self) -> Self {
-    unimplemented!()
+static EMPTY_VALUE_OFFSETS: [i32; 1] = [0];
+
+/// Returns the `len + 1` offsets of a variable-width array starting from `offset`. A builder
+/// without any value might not have allocated its offset buffer yet.
+fn raw_value_offsets(offsets: &SharedBuffer, offset: i64, len: i64) -> &[i32] {
+  if offsets.size() == 0 {
+    &EMPTY_VALUE_OFFSETS
+  } else {
+    unsafe {
+      let value_offsets = mem::transmute::<*const u8, *const i32>(offsets.data());
+      slice::from_raw_parts(value_offsets.offset(offset as isize), (len + 1) as usize)
+    }
   }
 }
 
-#[inline]
-fn raw_value<T: Copy>(values: *const T, i: i64) -> T {
-  unsafe { *values.offset(i as isize) }
+// the alignment of the values in a buffer of `ty`, which are read as slices of their native type
+fn value_alignment(ty: &Ty, desc: &BufferDesc) -> usize {
+  match (desc.buffer_type(), ty) {
+    (&BufferType::Data, &Ty::Decimal { .. }) => mem::align_of::<Decimal128>(),
+    // bits and bytes don't need to be aligned
+    (&BufferType::Validity, _) | (&BufferType::Type, _) => 1,
+    (&BufferType::Data, &Ty::Bool) | (&BufferType::Data, &Ty::Binary) | (&BufferType::Data, &Ty::String) => 1,
+    (&BufferType::Data, &Ty::FixedSizeBinary { .. }) => 1,
+    _ => (desc.bit_width() / 8) as usize
+  }
 }
 
-fn raw_values<T>(value_buffer: &Option<PoolBuffer>, offset: i64) -> *const T {
-  match value_buffer {
-    &Some(ref buffer) => {
-      unsafe {
-        use std::ptr;
-        mem::transmute::<*const u8, *const T>(buffer.data().offset(offset as isize))
-      }
-    },
-    &None => panic!("value buffer doesn't exist")
+// checks that value offsets are non-negative and increasing, and returns the range of the values
+// that they point to
+fn check_value_offsets(value_offsets: &[i32]) -> Result<(i64, i64), ArrowError> {
+  let (first, last) = match (value_offsets.first(), value_offsets.last()) {
+    (Some(&first), Some(&last)) => (first, last),
+    _ => return Ok((0, 0))
+  };
+  if first < 0 {
+    return Err(ArrowError::invalid(format!("value offset [{}] is negative", first)));
+  }
+  for i in 1..value_offsets.len() {
+    if value_offsets[i] < value_offsets[i - 1] {
+      return Err(ArrowError::invalid(format!("value offsets [{}] and [{}] are decreasing", value_offsets[i - 1], value_offsets[i])));
+    }
   }
+  Ok((first as i64, last as i64))
 }
 
-// TODO: maybe need cast?
+// checks that the strings in `values`, which start from the first of `value_offsets`, are valid
+// UTF-8. The offsets must have been checked.
+fn check_utf8(values: &[u8], value_offsets: &[i32]) -> Result<(), ArrowError> {
+  let values = match str::from_utf8(values) {
+    Ok(values) => values,
+    Err(e) => return Err(ArrowError::invalid(format!("strings are not valid UTF-8: {}", e)))
+  };
+  // every string is valid if every offset is at a character boundary of the valid values
+  let first = value_offsets[0];
+  match value_offsets.iter().position(|offset| !values.is_char_boundary((offset - first) as usize)) {
+    Some(i) => Err(ArrowError::invalid(format!("value offset [{}] splits a UTF-8 character", value_offsets[i]))),
+    None => Ok(())
+  }
+}
 
-pub trait ArrowSlice<T> {
-  fn value(&self, i: i64) -> T;
-  fn values(&self) -> &[T];
+// checks that every value of a union has a known type id and a slot in its field
+fn check_union_slots(array: &Array) -> Result<(), ArrowError> {
+  let (type_codes, mode) = match array.ty() {
+    &Ty::Union { ref type_codes, ref mode, .. } => (type_codes, mode),
+    ty => panic!("{:?} is not a union type", ty)
+  };
+  let type_ids = array.type_ids();
+  for i in 0..array.len() {
+    let child_id = match type_codes.iter().position(|code| *code == type_ids[i as usize]) {
+      Some(child_id) => child_id,
+      None => return Err(ArrowError::invalid(format!("[{}] is not a type id of {:?}", type_ids[i as usize], array.ty())))
+    };
+    let child_len = array.data.child_data()[child_id].len();
+    let slot = match mode {
+      &UnionMode::DENSE => array.typed_values::<i32>(2)[i as usize] as i64,
+      &UnionMode::SPARSE => array.offset() + i
+    };
+    if slot < 0 || slot >= child_len {
+      return Err(ArrowError::invalid(format!("value [{}] of the union is out of field [{}] of length [{}]", i, child_id, child_len)));
+    }
+  }
+  Ok(())
 }
 
-impl <'a> ArrowSlice<bool> for Array<'a> {
-  fn value(&self, i: i64) -> bool {
-    match self.builder.data() {
-      &BuilderData::Bool { ref null_bitmap, ref data } => bit_util::get_bit(data.data(), i),
-      _ => panic!("{:?} is not a boolean array", self.ty())
+// checks that every valid index of a dictionary array is in the dictionary
+fn check_dictionary_indices(array: &Array) -> Result<(), ArrowError> {
+  if !array.ty().dictionary_index_type().is_integer() {
+    return Err(ArrowError::invalid(format!("{:?} doesn't have integer indices", array.ty())));
+  }
+  let dictionary_len = array.dictionary().len();
+  for i in 0..array.len() {
+    if array.is_valid(i) {
+      let index = array.index(i);
+      if index < 0 || index >= dictionary_len {
+        return Err(ArrowError::invalid(format!("index [{}] is out of the dictionary of length [{}]", index, dictionary_len)));
+      }
     }
   }
+  Ok(())
+}
 
-  fn values(&self) -> &[bool] {
-    unimplemented!()
+/// Returns true if the i-th value of `left` equals the j-th value of `right`. Both arrays must be
+/// of the same type. Fixed-width values are compared by their bytes.
+fn value_equals(left: &Array, i: i64, right: &Array, j: i64) -> bool {
+  match (left.is_valid(i), right.is_valid(j)) {
+    (false, false) => return true,
+    (true, true) => {},
+    _ => return false
+  }
+  match left.ty() {
+    &Ty::Bool => ArrowValue::<bool>::value(left, i) == ArrowValue::<bool>::value(right, j),
+    &Ty::Binary | &Ty::String => {
+      let value = |array: &Array, i: i64| {
+        Blob::new(unsafe { array.value_data().offset(array.value_offset(i) as isize) }, array.value_len(i))
+      };
+      value(left, i) == value(right, j)
+    },
+    &Ty::List { .. } => {
+      let left: Array = left.value(i);
+      let right: Array = right.value(j);
+      left.len() == right.len() && (0..left.len()).all(|k| value_equals(&left, k, &right, k))
+    },
+    &Ty::Struct { .. } => {
+      (0..left.num_fields()).all(|k| value_equals(&left.field(k), i, &right.field(k), j))
+    },
+    &Ty::Union { .. } => {
+      let (left_child, left_slot) = left.child_slot(i);
+      let (right_child, right_slot) = right.child_slot(j);
+      left_child == right_child &&
+        value_equals(&left.union_field(left_child), left_slot, &right.union_field(right_child), right_slot)
+    },
+    // fixed-width values and dictionary indices
+    ty => {
+      let byte_width = (ty.bit_width() / 8) as i64;
+      let value = |array: &Array, i: i64| unsafe {
+        let pos = (array.offset() + i) * byte_width;
+        slice::from_raw_parts(array.data.buffer(1).data().offset(pos as isize), byte_width as usize)
+      };
+      value(left, i) == value(right, j)
+    }
   }
 }
 
-macro_rules! impl_arrow_slice {
-    ($ty: path, $prim_ty: ident) => {
-      impl <'a > ArrowSlice<$prim_ty> for Array<'a> {
-        fn value(&self, i: i64) -> $prim_ty {
-          match self.data {
-            $ty { ref values } => values[i as usize],
-            _ => panic!("{:?} is not a boolean array", self.ty())
-          }
-        }
+#[inline]
+fn raw_value<T: Copy>(values: *const T, i: i64) -> T {
+  unsafe { *values.offset(i as isize) }
+}
 
-        fn values(&self) -> &[$prim_ty] {
-          match self.data {
-            $ty { ref values } => *values,
-            _ => panic!("{:?} is not a boolean array", self.ty())
-          }
-        }
-      }
-    };
+// TODO: maybe need cast?
 
-    ($ty1: path, $ty2: path, $prim_ty: ident) => {
-      impl <'a > PrimitiveArray<$prim_ty> for Array<'a > {
-        fn prim_value(&self, i: i64) -> $prim_ty {
-          match self.builder.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } => values[i as usize],
-              &$ty1 { ref data } | &$ty2 { ref data } => unsafe { *data.data().offset(i as isize) },
-            _ => panic!("{:?} is not a boolean array", self.ty())
-          }
-        }
+pub trait ArrowValue<'a, T> {
+  fn value(&'a self, i: i64) -> T;
+}
 
-//        fn prim_values(&self) -> &[$prim_ty] {
-//          match self.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } => values.as_slice(),
-//            _ => panic!("{:?} is not a boolean array", self.ty())
-//          }
-//        }
-      }
-    };
+/// Arrays whose values are stored as one contiguous slice of `T`.
+pub trait ArrowSlice<'a, T>: ArrowValue<'a, T> {
+  fn values(&'a self) -> &'a [T];
+}
 
-    ($ty1: path, $ty2: path, $ty3: path, $prim_ty: ident) => {
-      impl <'a > PrimitiveArray<$prim_ty> for Array<'a > {
-        fn prim_value(&self, i: i64) -> $prim_ty {
-          match self.builder.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } | &$ty3 { ref values } => values[i as usize],
-              &$ty1 { ref data } | &$ty2 { ref data } | &$ty3 { ref data } => unsafe { *data.data().offset(i as isize) },
-            _ => panic!("{:?} is not a boolean array", self.ty())
-          }
+impl <'a> ArrowValue<'a, bool> for Array {
+  fn value(&'a self, i: i64) -> bool {
+    match self.ty() {
+      &Ty::Bool => {
+        // bits past the length may be out of the buffer
+        if i < 0 || i >= self.len() {
+          panic!("index [{}] is out of the array of length [{}]", i, self.len())
         }
+        bit_util::get_bit(self.data.buffer(1).data(), i + self.offset())
+      },
+      _ => panic!("{:?} is not a boolean array", self.ty())
+    }
+  }
+}
 
-//        fn prim_values(&self) -> &[$prim_ty] {
-//          match self.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } | &$ty3 { ref values } => values.as_slice(),
-//            _ => panic!("{:?} is not a boolean array", self.ty())
-//          }
-//        }
+// temporal arrays are read as their storage type
+macro_rules! impl_arrow_slice {
+    ($prim_ty: ident, $($ty: pat),+) => {
+      impl <'a> ArrowValue<'a, $prim_ty> for Array {
+        fn value(&'a self, i: i64) -> $prim_ty {
+          self.values()[i as usize]
+        }
       }
-    };
 
-    ($ty1: path, $ty2: path, $ty3: path, $ty4: path, $ty5: path, $prim_ty: ident) => {
-      impl <'a > PrimitiveArray<$prim_ty> for Array<'a > {
-        fn prim_value(&self, i: i64) -> $prim_ty {
-          match self.builder.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } | &$ty3 { ref values } | &$ty4 { ref values } | &$ty5 { ref values } => values[i as usize],
-            &$ty1 { ref data } | &$ty2 { ref data } | &$ty3 { ref data } | &$ty4 { ref data } | &$ty5 { ref data } => unsafe { *data.data().offset(i as isize) },
-            _ => panic!("{:?} is not a boolean array", self.ty())
+      impl <'a> ArrowSlice<'a, $prim_ty> for Array {
+        fn values(&'a self) -> &'a [$prim_ty] {
+          match self.ty() {
+            $(&$ty)|+ => self.typed_values(1),
+            _ => panic!("{:?} is not an array of {}", self.ty(), stringify!($prim_ty))
           }
         }
-
-//        fn prim_values(&self) -> &[$prim_ty] {
-//          match self.data() {
-//            &$ty1 { ref values } | &$ty2 { ref values } | &$ty3 { ref values } | &$ty4 { ref values } | &$ty5 { ref values } => values.as_slice(),
-//            _ => panic!("{:?} is not a boolean array", self.ty())
-//          }
-//        }
       }
     };
 }
 
-impl_arrow_slice!(ArrayData::Int8, i8);
-impl_arrow_slice!(ArrayData::Int16, i16);
-impl_arrow_slice!(ArrayData::Int32, i32);
-impl_arrow_slice!(ArrayData::Int64, i64);
-//impl_arrow_slice!(ArrayData::Int32, ArrayData::Date32, ArrayData::Time32, i32);
-//impl_arrow_slice!(ArrayData::Int64, ArrayData::Date64, ArrayData::Time64, ArrayData::Timestamp, ArrayData::Interval, i64);
-impl_arrow_slice!(ArrayData::UInt8, u8);
-impl_arrow_slice!(ArrayData::UInt16, u16);
-//impl_arrow_slice!(ArrayData::UInt16, ArrayData::HalfFloat, u16);
-impl_arrow_slice!(ArrayData::UInt32, u32);
-impl_arrow_slice!(ArrayData::UInt64, u64);
-
-//impl_primitive_array!(ArrayData::Float, f32);
-//impl_primitive_array!(ArrayData::Double, f64);
+impl_arrow_slice!(i8, Ty::Int8);
+impl_arrow_slice!(i16, Ty::Int16);
+impl_arrow_slice!(i32, Ty::Int32, Ty::Date32 { .. }, Ty::Time32 { .. }, Ty::Interval { unit: IntervalUnit::YearMonth });
+impl_arrow_slice!(i64, Ty::Int64, Ty::Date64 { .. }, Ty::Time64 { .. }, Ty::Timestamp { .. }, Ty::Interval { unit: IntervalUnit::DayTime });
+impl_arrow_slice!(u8, Ty::UInt8);
+impl_arrow_slice!(u16, Ty::UInt16);
+impl_arrow_slice!(u32, Ty::UInt32);
+impl_arrow_slice!(u64, Ty::UInt64);
+impl_arrow_slice!(f16, Ty::HalfFloat);
+impl_arrow_slice!(f32, Ty::Float);
+impl_arrow_slice!(f64, Ty::Double);
+impl_arrow_slice!(Decimal128, Ty::Decimal { .. });
 
 #[derive(Copy, Clone, Debug)]
 pub struct Blob {
@@ -451,151 +674,114 @@ impl PartialEq for Blob {
 
 impl Eq for Blob {}
 
-//impl <'a> ArrowSlice<Blob> for Array<'a> {
-//  fn value(&self, i: i64) -> Blob {
-//    match self.data {
-//      ArrayData::Binary { ref lengths_and_data } => {
-//        unimplemented!()
-//      },
-//      _ => panic!()
-//    }
-//  }
-//
-//  fn values(&self) -> &[Blob] {
-//    match self.data {
-//      ArrayData::Binary { ref lengths_and_data } => {
-//        unimplemented!()
-//      },
-//      _ => panic!()
-//    }
-//  }
-//}
+impl <'a> ArrowValue<'a, Blob> for Array {
+  fn value(&'a self, i: i64) -> Blob {
+    match self.ty() {
+      &Ty::Binary => {
+        let value_offsets = self.value_offsets();
+        let pos = value_offsets[i as usize];
+        let len = value_offsets[i as usize + 1] - pos;
+        Blob::new(unsafe { self.value_data().offset(pos as isize) }, len)
+      },
+      _ => panic!("{:?} is not a binary array", self.ty())
+    }
+  }
+}
 
-pub struct ArrayIterator<'a> {
-  array: Array<'a>,
-  next: isize
+impl <'a> ArrowValue<'a, &'a str> for Array {
+  fn value(&'a self, i: i64) -> &'a str {
+    match self.ty() {
+      &Ty::String => {
+        let value_offsets = self.value_offsets();
+        let pos = value_offsets[i as usize];
+        let len = value_offsets[i as usize + 1] - pos;
+        unsafe {
+          // strings are validated when they are appended to the builder or array data are created
+          str::from_utf8_unchecked(slice::from_raw_parts(self.value_data().offset(pos as isize), len as usize))
+        }
+      },
+      _ => panic!("{:?} is not a string array", self.ty())
+    }
+  }
+}
+
+pub struct ArrayIterator<'a, T> {
+  array: &'a Array,
+  next: i64,
+  phantom: PhantomData<T>
 }
 
-impl <'a> ArrayIterator<'a> {
-  pub fn new(array: Array<'a>) -> ArrayIterator<'a> {
+impl <'a, T> ArrayIterator<'a, T> {
+  pub fn new(array: &'a Array) -> ArrayIterator<'a, T> {
     ArrayIterator {
       array,
-      next: 0
+      next: 0,
+      phantom: PhantomData
     }
   }
 }
 
-impl <'a> Iterator for ArrayIterator<'a> {
-  type Item = Blob;
+impl <'a, T> Iterator for ArrayIterator<'a, T> where Array: ArrowValue<'a, T> {
+  type Item = T;
 
   fn next(&mut self) -> Option<Self::Item> {
-    match self.array.data() {
-      &ArrayData::Binary { ref lengths_and_values, total_len } => {
-        if self.next < total_len {
-          unsafe {
-            let p = lengths_and_values.offset(self.next);
-            let len = *(mem::transmute::<*const u8, *const i32>(p));
-            let p = p.offset(mem::size_of::<i32>() as isize);
-            self.next = self.next + len as isize + mem::size_of::<i32>() as isize;
-            Some(Blob::new(p, len))
-          }
-        } else {
-          None
-        }
-      },
-      _ => panic!()
+    if self.next < self.array.len() {
+      let val = self.array.value(self.next);
+      self.next = self.next + 1;
+      Some(val)
+    } else {
+      None
     }
   }
 }
 
-//pub trait VariableWidthArray {
-//  fn value(&self, i: i64) -> VariableWidthElem;
-//
-//  fn value_offset(&self, i: i64) -> i32;
-//
-//  fn value_len(&self, i: i64) -> i32;
-//}
-//
-//fn value_offset(value_offsets: &*const i32, i: i64) -> i32 {
-//  unsafe { *value_offsets.offset(i as isize) }
-//}
-//
-//fn value_len(value_offsets: &*const i32, i: i64) -> i32 {
-//  unsafe {
-//    let i_as_isize = i as isize;
-//    let pos = *value_offsets.offset(i_as_isize);
-//    *value_offsets.offset(i_as_isize + 1) - pos
-//  }
-//}
-//
-//impl <'a> VariableWidthArray for Array<'a> {
-//  fn value(&self, i: i64) -> VariableWidthElem {
-//    match self.data {
-//      ArrayData::Binary { ref value_offsets, ref values } | ArrayData::String { ref value_offsets, ref values } => {
-//        let offset = i + self.offset();
-//        unsafe {
-//          let pos = *value_offsets.offset(i as isize);
-//          let value_len = *value_offsets.offset((offset + 1) as isize) - pos;
-//          VariableWidthElem {
-//            p: values.offset(pos as isize),
-//            len: value_len
-//          }
-//        }
-//      },
-//      ArrayData::List { ref value_offsets, ref value_array } => {
-//        unimplemented!()
-//      }
-//      _ => panic!()
-//    }
-//  }
-//
-//  fn value_offset(&self, i: i64) -> i32 {
-//    match self.data {
-//      ArrayData::Binary { ref value_offsets, ref values } | ArrayData::String { ref value_offsets, ref values } => {
-//        value_offset(value_offsets, i)
-//      },
-//      ArrayData::List { ref value_offsets, ref value_array } => {
-//        value_offset(value_offsets, i)
-//      },
-//      _ => panic!()
-//    }
-//  }
-//
-//  fn value_len(&self, i: i64) -> i32 {
-//    match self.data {
-//      ArrayData::Binary { ref value_offsets, ref values } | ArrayData::String { ref value_offsets, ref values } => {
-//        value_len(value_offsets, i)
-//      },
-//      ArrayData::List { ref value_offsets, ref value_array } => {
-//        value_len(value_offsets, i)
-//      },
-//      _ => panic!()
-//    }
-//  }
-//}
+/// Variable-width arrays store `len + 1` int32 offsets, and the i-th value occupies the range of
+/// `[value_offsets[i], value_offsets[i + 1])` in the value buffer.
+pub trait VariableWidthArray {
+  fn value_offset(&self, i: i64) -> i32;
 
-//pub trait StringArray {
-//  fn string(&self, i: i64) -> String;
-//}
-//
-//impl <T> StringArray for T where T: VariableWidthArray {
-//  fn string(&self, i: i64) -> String {
-//    let elem = self.value(i);
-//    unsafe { String::from_raw_parts(mem::transmute::<*const u8, *mut u8>(elem.p), elem.len as usize, elem.len as usize) }
-//  }
-//}
+  fn value_len(&self, i: i64) -> i32;
+
+  fn value_offsets(&self) -> &[i32];
+
+  fn value_data(&self) -> *const u8;
+}
 
-type FixedSizedBlob = *const u8;
+impl VariableWidthArray for Array {
+  fn value_offset(&self, i: i64) -> i32 {
+    self.value_offsets()[i as usize]
+  }
+
+  fn value_len(&self, i: i64) -> i32 {
+    let value_offsets = self.value_offsets();
+    value_offsets[i as usize + 1] - value_offsets[i as usize]
+  }
+
+  fn value_offsets(&self) -> &[i32] {
+    match self.ty() {
+      &Ty::Binary | &Ty::String | &Ty::List { .. } => raw_value_offsets(self.data.buffer(1), self.offset(), self.len()),
+      _ => panic!("{:?} is not a variable-width array", self.ty())
+    }
+  }
+
+  fn value_data(&self) -> *const u8 {
+    match self.ty() {
+      &Ty::Binary | &Ty::String => self.data.buffer(2).data(),
+      _ => panic!("{:?} is not a variable-width array", self.ty())
+    }
+  }
+}
 
 pub trait FixedSizeBinaryArray {
   fn byte_width(&self) -> i32;
 
-  fn fixed_size_value(&self, i: i64) -> *const u8;
+  /// Returns the i-th value, which is exactly `byte_width` bytes.
+  fn fixed_size_value(&self, i: i64) -> &[u8];
 
-  fn fixed_size_values(&self) -> *const u8;
+  fn fixed_size_values(&self) -> &[u8];
 }
 
-impl <'a> FixedSizeBinaryArray for Array<'a> {
+impl FixedSizeBinaryArray for Array {
   fn byte_width(&self) -> i32 {
     match self.ty() {
       &Ty::FixedSizeBinary { byte_width } => byte_width,
@@ -604,40 +790,270 @@ impl <'a> FixedSizeBinaryArray for Array<'a> {
     }
   }
 
-  fn fixed_size_value(&self, i: i64) -> *const u8 {
-    match self.data {
-      ArrayData::FixedSizeBinary { ref values } => unsafe { values.offset(((self.offset() + i) * self.byte_width() as i64) as isize) },
-      _ => panic!()
+  fn fixed_size_value(&self, i: i64) -> &[u8] {
+    let byte_width = self.byte_width() as usize;
+    let begin = i as usize * byte_width;
+    &self.fixed_size_values()[begin..begin + byte_width]
+  }
+
+  fn fixed_size_values(&self) -> &[u8] {
+    let byte_width = self.byte_width() as i64;
+    // the buffer of zero-width values may not be allocated
+    if self.len() == 0 || byte_width == 0 {
+      &[]
+    } else {
+      unsafe {
+        let values = self.data.buffer(1).data().offset((self.offset() * byte_width) as isize);
+        slice::from_raw_parts(values, (self.len() * byte_width) as usize)
+      }
     }
   }
+}
+
+impl <'a> ArrowValue<'a, &'a [u8]> for Array {
+  fn value(&'a self, i: i64) -> &'a [u8] {
+    self.fixed_size_value(i)
+  }
+}
 
-  fn fixed_size_values(&self) -> *const u8 {
-    match self.data {
-      ArrayData::FixedSizeBinary { ref values } => unsafe { values.offset((self.offset() * self.byte_width() as i64) as isize) },
-      _ => panic!()
+impl <'a> ArrowValue<'a, Array> for Array {
+  fn value(&'a self, i: i64) -> Array {
+    match self.ty() {
+      &Ty::List { .. } => {
+        let value_offsets = self.value_offsets();
+        let pos = value_offsets[i as usize];
+        let len = value_offsets[i as usize + 1] - pos;
+        self.list_values().slice(pos as i64, len as i64)
+      },
+      _ => panic!("{:?} is not a list array", self.ty())
     }
   }
 }
 
-pub trait ListArray<'a> {
-  fn list_values(&self) -> &Box<Array<'a>>;
+pub trait ListArray {
+  /// Returns the values of all lists. Value offsets are positions in this array.
+  fn list_values(&self) -> Array;
 
   fn value_type(&self) -> &Ty;
 }
 
-impl <'a> ListArray<'a> for Array<'a> {
-  fn list_values(&self) -> &Box<Array<'a>> {
-    match self.data {
-      ArrayData::List { ref value_offsets, ref value_array } => value_array,
-      _ => panic!()
+impl ListArray for Array {
+  fn list_values(&self) -> Array {
+    match self.ty() {
+      &Ty::List { .. } => self.child(0),
+      _ => panic!("{:?} is not a list array", self.ty())
     }
   }
 
   fn value_type(&self) -> &Ty {
     match self.ty() {
       &Ty::List { ref value_type } => value_type,
-      _ => panic!()
+      _ => panic!("{:?} is not a list array", self.ty())
+    }
+  }
+}
+
+pub trait StructArray {
+  fn num_fields(&self) -> usize;
+
+  fn field(&self, i: usize) -> Array;
+
+  fn field_by_name(&self, name: &str) -> Option<Array>;
+}
+
+impl StructArray for Array {
+  fn num_fields(&self) -> usize {
+    match self.ty() {
+      &Ty::Struct { .. } => self.data.child_data().len(),
+      _ => panic!("{:?} is not a struct array", self.ty())
+    }
+  }
+
+  fn field(&self, i: usize) -> Array {
+    match self.ty() {
+      // fields of a struct are aligned with the struct
+      &Ty::Struct { .. } => self.child(i).slice(self.offset(), self.len()),
+      _ => panic!("{:?} is not a struct array", self.ty())
+    }
+  }
+
+  fn field_by_name(&self, name: &str) -> Option<Array> {
+    match self.ty() {
+      &Ty::Struct { ref fields } => {
+        fields.iter()
+          .position(|field| field.name() == name)
+          .map(|i| self.field(i))
+      },
+      _ => panic!("{:?} is not a struct array", self.ty())
+    }
+  }
+}
+
+pub trait UnionArray {
+  fn type_id(&self, i: i64) -> i8;
+
+  fn type_ids(&self) -> &[i8];
+
+  fn union_field(&self, child_id: usize) -> Array;
+
+  /// Returns the index of the field and the position in the field of the i-th value.
+  fn child_slot(&self, i: i64) -> (usize, i64);
+}
+
+impl UnionArray for Array {
+  fn type_id(&self, i: i64) -> i8 {
+    self.type_ids()[i as usize]
+  }
+
+  fn type_ids(&self) -> &[i8] {
+    match self.ty() {
+      &Ty::Union { .. } => self.typed_values(1),
+      _ => panic!("{:?} is not a union array", self.ty())
+    }
+  }
+
+  fn union_field(&self, child_id: usize) -> Array {
+    match self.ty() {
+      &Ty::Union { ref mode, .. } => {
+        match mode {
+          // fields of sparse unions are aligned with the union
+          &UnionMode::SPARSE => self.child(child_id).slice(self.offset(), self.len()),
+          // value offsets of dense unions are absolute positions in fields
+          &UnionMode::DENSE => self.child(child_id)
+        }
+      },
+      _ => panic!("{:?} is not a union array", self.ty())
+    }
+  }
+
+  fn child_slot(&self, i: i64) -> (usize, i64) {
+    match self.ty() {
+      &Ty::Union { ref type_codes, ref mode, .. } => {
+        let type_id = self.type_id(i);
+        let child_id = match type_codes.iter().position(|code| *code == type_id) {
+          Some(child_id) => child_id,
+          None => panic!("unknown type code [{}] for {:?}", type_id, self.ty())
+        };
+        match mode {
+          &UnionMode::DENSE => (child_id, self.typed_values::<i32>(2)[i as usize] as i64),
+          &UnionMode::SPARSE => (child_id, i)
+        }
+      },
+      _ => panic!("{:?} is not a union array", self.ty())
+    }
+  }
+}
+
+pub trait DictionaryArray {
+  fn indices(&self) -> Array;
+
+  fn dictionary(&self) -> &Array;
+
+  /// Returns the position of the i-th value in the dictionary.
+  fn index(&self, i: i64) -> i64;
+
+  /// Returns a new array which has the dictionary values in place of the indices.
+  fn decode(&self, pool: Arc<MemoryPool>) -> Result<Array, ArrowError>;
+}
+
+impl DictionaryArray for Array {
+  fn indices(&self) -> Array {
+    match self.ty() {
+      &Ty::Dictionary { ref index_type, .. } => {
+        // indices share the buffers of the dictionary array, which follow the layout of the indices
+        let data = unsafe {
+          ArrayData::new_unchecked(
+            index_type.as_ref().clone(),
+            self.len(),
+            self.offset(),
+            self.null_count(),
+            self.data.buffers().clone(),
+            Vec::new()
+          )
+        };
+        Array::new(Arc::new(data))
+      },
+      _ => panic!("{:?} is not a dictionary array", self.ty())
+    }
+  }
+
+  fn dictionary(&self) -> &Array {
+    self.ty().get_dictionary()
+  }
+
+  fn index(&self, i: i64) -> i64 {
+    // indices are read without bounds checks
+    if i < 0 || i >= self.len() {
+      panic!("index [{}] is out of the array of length [{}]", i, self.len())
+    }
+    let indices = self.data.buffer(1).data();
+    let pos = i + self.offset();
+    unsafe {
+      match self.ty().dictionary_index_type().as_ref() {
+        &Ty::Int8 => raw_value(mem::transmute::<*const u8, *const i8>(indices), pos) as i64,
+        &Ty::UInt8 => raw_value(indices, pos) as i64,
+        &Ty::Int16 => raw_value(mem::transmute::<*const u8, *const i16>(indices), pos) as i64,
+        &Ty::UInt16 => raw_value(mem::transmute::<*const u8, *const u16>(indices), pos) as i64,
+        &Ty::Int32 => raw_value(mem::transmute::<*const u8, *const i32>(indices), pos) as i64,
+        &Ty::UInt32 => raw_value(mem::transmute::<*const u8, *const u32>(indices), pos) as i64,
+        &Ty::Int64 => raw_value(mem::transmute::<*const u8, *const i64>(indices), pos),
+        &Ty::UInt64 => raw_value(mem::transmute::<*const u8, *const u64>(indices), pos) as i64,
+        index_type => panic!("{:?} is not an integer type", index_type)
+      }
+    }
+  }
+
+  fn decode(&self, pool: Arc<MemoryPool>) -> Result<Array, ArrowError> {
+    let dictionary = self.dictionary();
+    let mut builder = match dictionary.ty() {
+      &Ty::Binary => ArrayBuilder::binary(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool)),
+      &Ty::String => ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool)),
+      ty => match ArrayBuilder::new_fixed_width(ty.clone(), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool)) {
+        Ok(builder) => builder,
+        Err(e) => return Err(e)
+      }
+    };
+
+    for i in 0..self.len() {
+      let result = if self.is_null(i) {
+        builder.append_null()
+      } else {
+        append_value_of(&mut builder, dictionary, self.index(i))
+      };
+      match result {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
     }
+    Ok(Array::from(builder))
+  }
+}
+
+fn append_value_of(builder: &mut ArrayBuilder, array: &Array, i: i64) -> Result<(), ArrowError> {
+  match array.ty() {
+    &Ty::Int8 => builder.append(ArrowValue::<i8>::value(array, i)),
+    &Ty::UInt8 => builder.append(ArrowValue::<u8>::value(array, i)),
+    &Ty::Int16 => builder.append(ArrowValue::<i16>::value(array, i)),
+    &Ty::UInt16 => builder.append(ArrowValue::<u16>::value(array, i)),
+    &Ty::Int32 => builder.append(ArrowValue::<i32>::value(array, i)),
+    &Ty::UInt32 => builder.append(ArrowValue::<u32>::value(array, i)),
+    &Ty::Int64 => builder.append(ArrowValue::<i64>::value(array, i)),
+    &Ty::UInt64 => builder.append(ArrowValue::<u64>::value(array, i)),
+    &Ty::Bool => builder.append(ArrowValue::<bool>::value(array, i)),
+    &Ty::HalfFloat => builder.append(ArrowValue::<f16>::value(array, i)),
+    &Ty::Float => builder.append(ArrowValue::<f32>::value(array, i)),
+    &Ty::Double => builder.append(ArrowValue::<f64>::value(array, i)),
+    &Ty::Date32 { .. } | &Ty::Time32 { .. } | &Ty::Interval { unit: IntervalUnit::YearMonth } => {
+      builder.append(ArrowValue::<i32>::value(array, i))
+    },
+    &Ty::Date64 { .. } | &Ty::Time64 { .. } | &Ty::Timestamp { .. } | &Ty::Interval { unit: IntervalUnit::DayTime } => {
+      builder.append(ArrowValue::<i64>::value(array, i))
+    },
+    &Ty::Decimal { .. } => builder.append(ArrowValue::<Decimal128>::value(array, i)),
+    &Ty::FixedSizeBinary { .. } => builder.append(ArrowValue::<&[u8]>::value(array, i)),
+    &Ty::Binary => builder.append(ArrowValue::<Blob>::value(array, i)),
+    &Ty::String => builder.append(ArrowValue::<&str>::value(array, i)),
+    ty => Err(ArrowError::not_implemented(format!("{:?} is not supported for dictionary values", ty)))
   }
 }
 
@@ -646,11 +1062,11 @@ pub trait Cast {
 //    unimplemented!("Cannot cast to null")
 //  }
 
-  fn as_bool(&self) -> &ArrowSlice<bool> {
+  fn as_bool<'a>(&'a self) -> &'a ArrowValue<'a, bool> {
     unimplemented!("Cannot cast to boolean")
   }
 
-  fn as_int8(&self) -> &ArrowSlice<i8> {
+  fn as_int8<'a>(&'a self) -> &'a ArrowSlice<'a, i8> {
     unimplemented!("Cannot cast to int8")
   }
 
@@ -698,21 +1114,25 @@ pub trait Cast {
 #[cfg(test)]
 mod tests {
   use memory_pool::{MemoryPool, DefaultMemoryPool};
-  use array::Blob;
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use common::ty::Ty;
+  use array::*;
+  use std::sync::Arc;
 
   #[test]
   fn test_blob_eq() {
-    let mut pool = DefaultMemoryPool::new();
+    let pool = DefaultMemoryPool::new();
     let p1 = pool.allocate(20).unwrap();
     let p2 = pool.allocate(20).unwrap();
     let val = 100;
     unsafe {
-      use std::intrinsics;
+      use std::ptr;
       use std::mem;
-      intrinsics::write_bytes(mem::transmute::<*const u8, *mut u8>(p1), 0, 20);
-      intrinsics::write_bytes(mem::transmute::<*const u8, *mut u8>(p2), 0, 20);
-      intrinsics::copy(&val, mem::transmute::<*const u8, *mut u8>(p1), 1);
-      intrinsics::copy(&val, mem::transmute::<*const u8, *mut u8>(p2), 1);
+      ptr::write_bytes(mem::transmute::<*const u8, *mut u8>(p1), 0, 20);
+      ptr::write_bytes(mem::transmute::<*const u8, *mut u8>(p2), 0, 20);
+      ptr::copy(&val, mem::transmute::<*const u8, *mut u8>(p1), 1);
+      ptr::copy(&val, mem::transmute::<*const u8, *mut u8>(p2), 1);
     }
 
     let b1 = Blob::new(p1, 20);
@@ -720,4 +1140,363 @@ mod tests {
 
     assert_eq!(b1, b2);
   }
-}
\ No newline at end of file
+
+  fn pool() -> Arc<DefaultMemoryPool> {
+    Arc::new(DefaultMemoryPool::new())
+  }
+
+  #[test]
+  fn test_slice_primitive_array() {
+    let pool = pool();
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for i in 0..100 {
+      if i % 10 == 0 {
+        builder.append_null().unwrap();
+      } else {
+        builder.append(i as i32).unwrap();
+      }
+    }
+    let array = Array::from(builder);
+    assert_eq!(10, array.null_count());
+
+    let slice = array.slice(15, 30);
+    assert_eq!(15, slice.offset());
+    assert_eq!(30, slice.len());
+    assert_eq!(3, slice.null_count());
+    assert!(slice.is_null(5));
+    assert!(slice.is_valid(6));
+    assert_eq!(16, ArrowValue::<i32>::value(&slice, 1));
+    assert_eq!(&ArrowSlice::<i32>::values(&array)[15..45], ArrowSlice::<i32>::values(&slice));
+
+    let iterated: Vec<i32> = ArrayIterator::new(&slice).collect();
+    assert_eq!(ArrowSlice::<i32>::values(&slice), &iterated[..]);
+
+    // slice of a slice
+    let slice = slice.slice(6, 4);
+    assert_eq!(21, slice.offset());
+    assert_eq!(0, slice.null_count());
+    assert_eq!(vec![21, 22, 23, 24], ArrayIterator::<i32>::new(&slice).collect::<Vec<i32>>());
+
+    let empty = array.slice(100, 0);
+    assert_eq!(0, empty.len());
+    assert_eq!(0, empty.null_count());
+  }
+
+  #[test]
+  fn test_slice_bool_array() {
+    let pool = pool();
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Bool, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for i in 0..20 {
+      builder.append(i % 3 == 0).unwrap();
+    }
+    let array = Array::from(builder);
+
+    let slice = array.slice(7, 10);
+    assert_eq!(0, slice.null_count());
+    for i in 0..10 {
+      assert_eq!((i + 7) % 3 == 0, ArrowValue::<bool>::value(&slice, i));
+    }
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_bool_value_out_of_range() {
+    let pool = pool();
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Bool, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for i in 0..20 {
+      builder.append(i % 3 == 0).unwrap();
+    }
+    // the slice ends in the middle of the parent
+    let slice = Array::from(builder).slice(7, 10);
+    ArrowValue::<bool>::value(&slice, 10);
+  }
+
+  #[test]
+  fn test_slice_null_array() {
+    let array = Array::from(ArrayBuilder::null(10));
+    let slice = array.slice(2, 5);
+    assert_eq!(5, slice.null_count());
+    assert!(slice.is_null(0));
+  }
+
+  #[test]
+  fn test_slice_string_array() {
+    let pool = pool();
+    let mut builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let vals = vec!["iron", "arrow", "", "columnar", "memory", "format"];
+    for (i, val) in vals.iter().enumerate() {
+      if i == 2 {
+        builder.append_null().unwrap();
+      } else {
+        builder.append(*val).unwrap();
+      }
+    }
+    let array = Array::from(builder);
+
+    let slice = array.slice(1, 4);
+    assert_eq!(1, slice.null_count());
+    assert!(slice.is_null(1));
+    assert_eq!("arrow", ArrowValue::<&str>::value(&slice, 0));
+    assert_eq!("columnar", ArrowValue::<&str>::value(&slice, 2));
+    assert_eq!("memory", ArrowValue::<&str>::value(&slice, 3));
+    assert_eq!(8, slice.value_len(2));
+    assert_eq!(5, slice.value_offsets().len());
+  }
+
+  #[test]
+  fn test_slice_list_array() {
+    let pool = pool();
+    let value_builder = ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut builder = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), value_builder);
+    for i in 0..10 {
+      for j in 0..i {
+        builder.value_builder_mut().append(j as i64).unwrap();
+      }
+      builder.append_list().unwrap();
+    }
+    let array = Array::from(builder);
+
+    let slice = array.slice(3, 4);
+    for i in 0..4 {
+      let list: Array = slice.value(i);
+      assert_eq!(i + 3, list.len());
+      let expected: Vec<i64> = (0..i + 3).collect();
+      assert_eq!(&expected[..], ArrowSlice::<i64>::values(&list));
+    }
+  }
+
+  #[test]
+  fn test_slice_struct_array() {
+    use common::field::Field;
+
+    let pool = pool();
+    let fields = vec![
+      Field::new(String::from("f1"), Ty::Int8),
+      Field::new(String::from("f2"), Ty::Double)
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::new_fixed_width(Ty::Double, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+    ];
+    let mut builder = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, field_builders).unwrap();
+    for i in 0..10 {
+      builder.field_builder_mut(0).append(i as i8).unwrap();
+      if i % 2 == 0 {
+        builder.field_builder_mut(1).append_null().unwrap();
+      } else {
+        builder.field_builder_mut(1).append(i as f64).unwrap();
+      }
+      builder.append_struct().unwrap();
+    }
+    let array = Array::from(builder);
+
+    let slice = array.slice(5, 3);
+    assert_eq!(0, slice.null_count());
+    assert_eq!(5, slice.field(0).offset());
+    assert_eq!(&[5, 6, 7], ArrowSlice::<i8>::values(&slice.field(0)));
+    assert_eq!(1, slice.field(1).null_count());
+    assert!(slice.field(1).is_null(1));
+    assert_eq!(7.0, ArrowValue::<f64>::value(&slice.field_by_name("f2").unwrap(), 2));
+  }
+
+  #[test]
+  fn test_slice_fixed_size_binary_array() {
+    let pool = pool();
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::fixed_sized_binary(4), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for i in 0..10 {
+      builder.append(&[i as u8; 4][..]).unwrap();
+    }
+    let array = Array::from(builder);
+
+    let slice = array.slice(8, 2);
+    assert_eq!(&[8, 8, 8, 8], slice.fixed_size_value(0));
+    assert_eq!(&[8, 8, 8, 8, 9, 9, 9, 9], slice.fixed_size_values());
+  }
+
+  #[test]
+  fn test_fixed_size_values() {
+    use common::decimal::Decimal128;
+
+    let pool = pool();
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::decimal(5, 2), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    builder.append(Decimal128::new(1)).unwrap();
+    builder.append(Decimal128::new(2)).unwrap();
+    let array = Array::from(builder).slice(1, 1);
+    assert_eq!(16, array.fixed_size_values().len());
+    // decimals are 128-bit little-endian integers
+    assert_eq!(&2i128.to_le_bytes()[..], array.fixed_size_value(0));
+
+    // zero-width values don't have any byte
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::fixed_sized_binary(0), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for _ in 0..3 {
+      builder.append(&[][..]).unwrap();
+    }
+    let array = Array::from(builder);
+    assert_eq!(3, array.len());
+    assert!(array.fixed_size_values().is_empty());
+    assert!(array.fixed_size_value(2).is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_slice_out_of_range() {
+    let array = Array::from(ArrayBuilder::null(10));
+    array.slice(5, 6);
+  }
+
+  #[test]
+  fn test_array_data_validation() {
+    use buffer::{ForeignBuffer, SharedBuffer};
+    use common::field::Field;
+    use common::status::StatusCode;
+
+    static VALUES: [i32; 3] = [0; 3];
+    static OFFSETS: [i32; 3] = [0, 2, 5];
+    static DECREASING_OFFSETS: [i32; 3] = [0, 3, 2];
+    static BYTES: [u8; 13] = [0; 13];
+    static STRINGS: [u8; 5] = [b'i', b'r', 0xc3, 0xa9, b'n'];
+    let bytes = |bytes: &'static [u8]| Some(SharedBuffer::from(ForeignBuffer::from_static(bytes)));
+    let ints = |values: &'static [i32]| unsafe {
+      let bytes = slice::from_raw_parts(values.as_ptr() as *const u8, values.len() * 4);
+      Some(SharedBuffer::from(ForeignBuffer::from_static(bytes)))
+    };
+
+    assert!(ArrayData::new(Ty::Int32, 3, 0, 0, vec![None, ints(&VALUES)], Vec::new()).is_ok());
+    assert!(ArrayData::new(Ty::Int32, 2, 1, 0, vec![None, ints(&VALUES)], Vec::new()).is_ok());
+    let invalid = vec![
+      // too small buffers
+      ArrayData::new(Ty::Int32, 4, 0, 0, vec![None, ints(&VALUES)], Vec::new()),
+      ArrayData::new(Ty::Int32, 3, 1, 0, vec![None, ints(&VALUES)], Vec::new()),
+      ArrayData::new(Ty::Int32, 3, -1, 0, vec![None, ints(&VALUES)], Vec::new()),
+      // missing buffers
+      ArrayData::new(Ty::Int32, 3, 0, 0, vec![None], Vec::new()),
+      ArrayData::new(Ty::Int32, 3, 0, 0, vec![None, None], Vec::new()),
+      ArrayData::new(Ty::Int32, 3, 0, 1, vec![None, ints(&VALUES)], Vec::new()),
+      // values which are not aligned to their width
+      ArrayData::new(Ty::Int32, 3, 0, 0, vec![None, bytes(&BYTES[1..])], Vec::new()),
+      // values out of the data buffer
+      ArrayData::new(Ty::String, 2, 0, 0, vec![None, ints(&OFFSETS), bytes(&BYTES[..4])], Vec::new()),
+      // decreasing offsets
+      ArrayData::new(Ty::Binary, 2, 0, 0, vec![None, ints(&DECREASING_OFFSETS), bytes(&BYTES)], Vec::new()),
+      // invalid UTF-8, and an offset in the middle of a character
+      ArrayData::new(Ty::String, 1, 0, 0, vec![None, ints(&OFFSETS[..2]), bytes(&[0xff, 0xff])], Vec::new()),
+      ArrayData::new(Ty::String, 1, 0, 0, vec![None, ints(&[0, 3]), bytes(&STRINGS)], Vec::new()),
+      // missing child data
+      ArrayData::new(Ty::list(Box::new(Ty::Int8)), 2, 0, 0, vec![None, ints(&OFFSETS)], Vec::new())
+    ];
+    for result in invalid {
+      assert_eq!(&StatusCode::Invalid, result.unwrap_err().code());
+    }
+
+    let strings = ArrayData::new(Ty::String, 2, 0, 0, vec![None, ints(&OFFSETS), bytes(&STRINGS)], Vec::new()).unwrap();
+    assert_eq!("ir", ArrowValue::<&str>::value(&Array::new(Arc::new(strings)), 0));
+    let int8s = Arc::new(ArrayData::new(Ty::Int8, 5, 0, 0, vec![None, bytes(&BYTES)], Vec::new()).unwrap());
+    let list = ArrayData::new(Ty::list(Box::new(Ty::Int8)), 2, 0, 0, vec![None, ints(&OFFSETS)], vec![int8s.clone()]);
+    assert!(list.is_ok());
+    let list = ArrayData::new(Ty::list(Box::new(Ty::Int16)), 2, 0, 0, vec![None, ints(&OFFSETS)], vec![int8s.clone()]);
+    assert_eq!(&StatusCode::Invalid, list.unwrap_err().code());
+
+    // slots of a dense union must be in its fields
+    static TYPE_IDS: [u8; 2] = [0, 1];
+    static UNKNOWN_TYPE_IDS: [u8; 2] = [0, 2];
+    static SLOTS: [i32; 2] = [4, 0];
+    static SLOTS_OUT_OF_FIELD: [i32; 2] = [5, 0];
+    let union_type = Ty::union_with_mode(
+      vec![Field::new(String::from("a"), Ty::Int8), Field::new(String::from("b"), Ty::Int8)], vec![0, 1], UnionMode::DENSE
+    );
+    let union = |type_ids: &'static [u8], slots: &'static [i32]| {
+      ArrayData::new(union_type.clone(), 2, 0, 0, vec![None, bytes(type_ids), ints(slots)], vec![int8s.clone(), int8s.clone()])
+    };
+    assert!(union(&TYPE_IDS, &SLOTS).is_ok());
+    assert_eq!(&StatusCode::Invalid, union(&UNKNOWN_TYPE_IDS, &SLOTS).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, union(&TYPE_IDS, &SLOTS_OUT_OF_FIELD).unwrap_err().code());
+
+    // indices of a dictionary array must be in the dictionary
+    static INDICES: [u8; 3] = [0, 1, 5];
+    let dictionary_type = Ty::dictionary(Box::new(Ty::UInt8), Box::new(Array::new(int8s)));
+    assert!(ArrayData::new(dictionary_type.clone(), 2, 0, 0, vec![None, bytes(&INDICES)], Vec::new()).is_ok());
+    let out_of_dictionary = ArrayData::new(dictionary_type, 3, 0, 0, vec![None, bytes(&INDICES)], Vec::new());
+    assert_eq!(&StatusCode::Invalid, out_of_dictionary.unwrap_err().code());
+  }
+
+  #[test]
+  fn test_array_data_eq() {
+    use buffer::{ForeignBuffer, SharedBuffer};
+
+    static VALIDITY: [u8; 1] = [0b101];
+    static VALUES: [i32; 4] = [1, 2, 3, 1];
+    static OTHER_VALUES: [i32; 3] = [1, 9, 3];
+    let buffer = |values: &'static [i32]| unsafe {
+      let bytes = slice::from_raw_parts(values.as_ptr() as *const u8, values.len() * 4);
+      Some(SharedBuffer::from(ForeignBuffer::from_static(bytes)))
+    };
+    let validity = Some(SharedBuffer::from(ForeignBuffer::from_static(&VALIDITY)));
+
+    // null slots are not compared
+    let data = ArrayData::new(Ty::Int32, 3, 0, 1, vec![validity.clone(), buffer(&VALUES)], Vec::new()).unwrap();
+    let other = ArrayData::new(Ty::Int32, 3, 0, 1, vec![validity.clone(), buffer(&OTHER_VALUES)], Vec::new()).unwrap();
+    assert_eq!(data, other);
+
+    // slices are compared by their values rather than their offsets
+    let values = ArrayData::new(Ty::Int32, 4, 0, 0, vec![None, buffer(&VALUES)], Vec::new()).unwrap();
+    assert_eq!(values.slice(0, 1), values.slice(3, 1));
+    assert!(values.slice(0, 2) != values.slice(2, 2));
+    assert!(data != values.slice(0, 3));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_dictionary_index_out_of_range() {
+    use buffer::{ForeignBuffer, SharedBuffer};
+
+    static INDICES: [u8; 3] = [0, 1, 0];
+    let pool = pool();
+    let mut values = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    values.append(7i32).unwrap();
+    values.append(9i32).unwrap();
+    let dictionary_type = Ty::dictionary(Box::new(Ty::UInt8), Box::new(Array::from(values)));
+    let buffers = vec![None, Some(SharedBuffer::from(ForeignBuffer::from_static(&INDICES)))];
+    let array = Array::new(Arc::new(ArrayData::new(dictionary_type, 3, 0, 0, buffers, Vec::new()).unwrap()));
+    assert_eq!(1, array.index(1));
+    array.index(1_000_000);
+  }
+
+  #[test]
+  fn test_decode_fixed_width_dictionary() {
+    use buffer::{ForeignBuffer, SharedBuffer};
+
+    let pool = pool();
+    let mut values = ArrayBuilder::new_fixed_width(Ty::Float, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    values.append(1.5f32).unwrap();
+    values.append(-2.25f32).unwrap();
+    let dictionary_type = Ty::dictionary(Box::new(Ty::Int8), Box::new(Array::from(values)));
+
+    static VALIDITY: [u8; 1] = [0b1011];
+    static INDICES: [i8; 4] = [1, 0, 0, 1];
+    let indices = unsafe { slice::from_raw_parts(INDICES.as_ptr() as *const u8, INDICES.len()) };
+    let buffers = vec![
+      Some(SharedBuffer::from(ForeignBuffer::from_static(&VALIDITY))),
+      Some(SharedBuffer::from(ForeignBuffer::from_static(indices)))
+    ];
+    let array = Array::new(Arc::new(ArrayData::new(dictionary_type, 4, 0, 1, buffers, Vec::new()).unwrap()));
+
+    let decoded = array.decode(pool.clone()).unwrap();
+    assert_eq!(&Ty::Float, decoded.ty());
+    assert_eq!(4, decoded.len());
+    assert!(decoded.is_null(2));
+    assert_eq!(-2.25f32, ArrowValue::<f32>::value(&decoded, 0));
+    assert_eq!(1.5f32, ArrowValue::<f32>::value(&decoded, 1));
+    assert_eq!(-2.25f32, ArrowValue::<f32>::value(&decoded, 3));
+
+    let mut values = ArrayBuilder::new_fixed_width(Ty::fixed_sized_binary(2), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    values.append(&[1u8, 2][..]).unwrap();
+    values.append(&[3u8, 4][..]).unwrap();
+    let dictionary_type = Ty::dictionary(Box::new(Ty::Int8), Box::new(Array::from(values)));
+    let buffers = vec![None, Some(SharedBuffer::from(ForeignBuffer::from_static(indices)))];
+    let array = Array::new(Arc::new(ArrayData::new(dictionary_type, 4, 0, 0, buffers, Vec::new()).unwrap()));
+
+    let decoded = array.decode(pool).unwrap();
+    assert_eq!(&Ty::fixed_sized_binary(2), decoded.ty());
+    assert_eq!(&[3, 4, 1, 2, 1, 2, 3, 4], decoded.fixed_size_values());
+  }
+}
@@ -2,9 +2,16 @@ use common::status::ArrowError;
 use common::bit_util;
 use memory_pool::MemoryPool;
 
+use std::io;
 use std::mem;
-use std::cell::RefCell;
+use std::any::Any;
+use std::alloc::{self, Layout};
+use std::ptr;
+use std::fs::File;
+use std::path::Path;
+use std::os::unix::io::AsRawFd;
 use std::sync::Arc;
+use std::fmt::{Debug, Formatter, Error};
 
 use libc;
 use num::Num;
@@ -29,7 +36,7 @@ pub trait ResizableBuffer {
   fn reserve(&mut self, new_capacity: i64) -> Result<(), ArrowError>;
 }
 
-fn resize(pool: &mut Arc<RefCell<MemoryPool>>, page: *const u8, size: i64, capacity: i64, new_size: i64) -> Result<(*const u8, i64, i64), ArrowError> {
+fn resize(pool: &Arc<MemoryPool>, page: *const u8, size: i64, capacity: i64, new_size: i64) -> Result<(*const u8, i64, i64), ArrowError> {
   if new_size > size {
     match reserve(pool, page, capacity, new_size) {
       Ok((new_page, new_capacity)) => Ok((new_page, new_size, new_capacity)),
@@ -39,10 +46,10 @@ fn resize(pool: &mut Arc<RefCell<MemoryPool>>, page: *const u8, size: i64, capac
     let new_capacity = bit_util::round_up_to_multiple_of_64(new_size);
     if capacity != new_capacity {
       if new_size == 0 {
-        pool.borrow_mut().free(page, capacity);
-        Ok((unsafe { mem::uninitialized() }, 0, 0))
+        pool.free(page, capacity);
+        Ok((empty_page(), 0, 0))
       } else {
-        match pool.borrow_mut().reallocate(capacity, new_capacity, page) {
+        match pool.reallocate(capacity, new_capacity, page) {
           Ok(new_page) => {
             Ok((new_page, new_size, new_capacity))
           },
@@ -55,10 +62,10 @@ fn resize(pool: &mut Arc<RefCell<MemoryPool>>, page: *const u8, size: i64, capac
   }
 }
 
-fn reserve(pool: &mut Arc<RefCell<MemoryPool>>, page: *const u8, capacity: i64, new_capacity: i64) -> Result<(*const u8, i64), ArrowError> {
+fn reserve(pool: &Arc<MemoryPool>, page: *const u8, capacity: i64, new_capacity: i64) -> Result<(*const u8, i64), ArrowError> {
   if new_capacity > capacity {
     let new_capacity = bit_util::round_up_to_multiple_of_64(new_capacity);
-    match pool.borrow_mut().reallocate(capacity, new_capacity, page) {
+    match pool.reallocate(capacity, new_capacity, page) {
       Ok(new_page) => {
         Ok((new_page, new_capacity))
       },
@@ -69,6 +76,13 @@ fn reserve(pool: &mut Arc<RefCell<MemoryPool>>, page: *const u8, capacity: i64,
   }
 }
 
+// buffers without memory still point to an aligned address, so that empty slices of their values
+// can be made
+#[inline]
+fn empty_page() -> *const u8 {
+  ptr::NonNull::<u64>::dangling().as_ptr() as *const u8
+}
+
 fn as_mut<T>(p: *const u8) -> *mut T {
   unsafe { mem::transmute::<*const u8, *mut T>(p) }
 }
@@ -77,31 +91,32 @@ fn as_mut<T>(p: *const u8) -> *mut T {
 // Copy?
 
 pub struct PoolBuffer {
-  pool: Arc<RefCell<MemoryPool>>,
+  pool: Arc<MemoryPool>,
   page: *const u8,
   size: i64,
   capacity: i64
-//  parent: Option<Box<Buffer>>
 }
 
+// a pool buffer exclusively owns its memory, which is mutated only through `&mut self`
+unsafe impl Send for PoolBuffer {}
+unsafe impl Sync for PoolBuffer {}
+
 impl PoolBuffer {
-  pub fn new(pool: Arc<RefCell<MemoryPool>>) -> PoolBuffer {
+  pub fn new(pool: Arc<MemoryPool>) -> PoolBuffer {
     PoolBuffer {
       pool,
-      page: unsafe { mem::uninitialized() },
+      page: empty_page(),
       size: 0,
-      capacity: 0,
-//      parent: None
+      capacity: 0
     }
   }
 
-  pub fn from(pool: Arc<RefCell<MemoryPool>>, page: *const u8, size: i64, capacity: i64) -> PoolBuffer {
+  pub fn from(pool: Arc<MemoryPool>, page: *const u8, size: i64, capacity: i64) -> PoolBuffer {
     PoolBuffer {
       pool,
       page,
       size,
-      capacity,
-//      parent: None
+      capacity
     }
   }
 
@@ -121,17 +136,6 @@ impl PoolBuffer {
     self.page
   }
 
-  // TODO: fix this
-  pub fn as_vec<T>(&self) -> Vec<T> {
-    let v = unsafe { Vec::from_raw_parts(as_mut(self.page), self.size as usize, self.capacity as usize) };
-    unsafe { mem::forget(self.page) }
-    v
-  }
-
-  pub fn forget(&self) {
-    unsafe { mem::forget(self.page) }
-  }
-
 //  pub fn as_slice<T>(&self) -> &[T] {
 //    use std::slice;
 //
@@ -175,6 +179,27 @@ impl PartialEq for PoolBuffer {
 
 impl Eq for PoolBuffer {}
 
+impl Buffer for PoolBuffer {
+  #[inline]
+  fn capacity(&self) -> i64 {
+    self.capacity
+  }
+
+  #[inline]
+  fn size(&self) -> i64 {
+    self.size
+  }
+
+  #[inline]
+  fn data(&self) -> *const u8 {
+    self.page
+  }
+
+  fn parent(&self) -> Option<&Buffer> {
+    None
+  }
+}
+
 impl MutableBuffer for PoolBuffer {
   #[inline]
   fn data_as_mut(&mut self) -> *mut u8 {
@@ -184,7 +209,7 @@ impl MutableBuffer for PoolBuffer {
 
 impl ResizableBuffer for PoolBuffer {
   fn resize(&mut self, new_size: i64) -> Result<(), ArrowError> {
-    match resize(&mut self.pool, self.page, self.size, self.capacity, new_size) {
+    match resize(&self.pool, self.page, self.size, self.capacity, new_size) {
       Ok((new_page, new_size, new_capacity)) => {
         self.page = new_page;
         self.size = new_size;
@@ -196,7 +221,7 @@ impl ResizableBuffer for PoolBuffer {
   }
 
   fn reserve(&mut self, new_capacity: i64) -> Result<(), ArrowError> {
-    match reserve(&mut self.pool, self.page, self.capacity, new_capacity) {
+    match reserve(&self.pool, self.page, self.capacity, new_capacity) {
       Ok((new_page, new_capacity)) => {
         self.page = new_page;
         self.capacity = new_capacity;
@@ -210,11 +235,475 @@ impl ResizableBuffer for PoolBuffer {
 impl Drop for PoolBuffer {
   fn drop(&mut self) {
     if self.capacity > 0 {
-      self.pool.borrow_mut().free(self.page, self.capacity);
+      self.pool.free(self.page, self.capacity);
     }
   }
 }
 
+/// An immutable buffer which can be shared without copying. The memory is freed when the last
+/// buffer sharing it is dropped, and a slice keeps its parent alive.
+#[derive(Clone)]
+pub struct SharedBuffer {
+  // the buffer owning the memory
+  memory: Arc<SharedMemory>,
+  parent: Option<Arc<SharedBuffer>>,
+  data: *const u8,
+  size: i64
+}
+
+// the buffers which can own the memory of shared buffers
+trait SharedMemory: Buffer + Send + Sync {
+  fn as_any(&self) -> &Any;
+
+  fn into_any(self: Arc<Self>) -> Arc<Any + Send + Sync>;
+}
+
+impl<B: Buffer + Send + Sync + 'static> SharedMemory for B {
+  fn as_any(&self) -> &Any {
+    self
+  }
+
+  fn into_any(self: Arc<Self>) -> Arc<Any + Send + Sync> {
+    self
+  }
+}
+
+// the memory of a shared buffer is never mutated
+unsafe impl Send for SharedBuffer {}
+unsafe impl Sync for SharedBuffer {}
+
+impl SharedBuffer {
+  pub fn new<B: Buffer + Send + Sync + 'static>(buffer: B) -> SharedBuffer {
+    let data = buffer.data();
+    let size = buffer.size();
+    SharedBuffer {
+      memory: Arc::new(buffer),
+      parent: None,
+      data,
+      size
+    }
+  }
+
+  /// Returns a zero-copy slice of `len` bytes starting from `offset`. The parent of the slice is
+  /// the buffer which isn't a slice, so slicing a slice doesn't chain buffers.
+  pub fn slice(&self, offset: i64, len: i64) -> SharedBuffer {
+    if offset < 0 || len < 0 || offset + len > self.size {
+      panic!("slice [{}, {}) is out of the buffer of size [{}]", offset, offset + len, self.size)
+    }
+
+    let parent = match self.parent {
+      Some(ref parent) => parent.clone(),
+      None => Arc::new(self.clone())
+    };
+    SharedBuffer {
+      memory: self.memory.clone(),
+      parent: Some(parent),
+      data: unsafe { self.data.offset(offset as isize) },
+      size: len
+    }
+  }
+
+  /// Converts the buffer back into the vector it's created from without copying. This succeeds only
+  /// if the buffer is not a slice, no other buffer shares its memory, and the memory can be owned
+  /// by a `Vec<T>` as described in `ForeignBuffer::into_vec()`. Otherwise, the buffer is returned
+  /// as it is.
+  pub fn into_vec<T: NativeType>(self) -> Result<Vec<T>, SharedBuffer> {
+    let convertible = self.parent.is_none() && Arc::strong_count(&self.memory) == 1 &&
+      match self.memory.as_any().downcast_ref::<ForeignBuffer>() {
+        Some(buffer) => buffer.is_convertible_to_vec::<T>(),
+        None => false
+      };
+    if !convertible {
+      return Err(self);
+    }
+
+    match self.memory.into_any().downcast::<ForeignBuffer>() {
+      Ok(memory) => {
+        match Arc::try_unwrap(memory) {
+          Ok(buffer) => Ok(buffer.into_vec().ok().unwrap()),
+          Err(_) => unreachable!()
+        }
+      },
+      Err(_) => unreachable!()
+    }
+  }
+
+  /// Returns true if both buffers share the same memory.
+  pub fn shares_memory_with(&self, other: &SharedBuffer) -> bool {
+    Arc::ptr_eq(&self.memory, &other.memory)
+  }
+}
+
+impl From<PoolBuffer> for SharedBuffer {
+  fn from(buffer: PoolBuffer) -> SharedBuffer {
+    SharedBuffer::new(buffer)
+  }
+}
+
+impl From<ForeignBuffer> for SharedBuffer {
+  fn from(buffer: ForeignBuffer) -> SharedBuffer {
+    SharedBuffer::new(buffer)
+  }
+}
+
+impl From<MmapBuffer> for SharedBuffer {
+  fn from(buffer: MmapBuffer) -> SharedBuffer {
+    SharedBuffer::new(buffer)
+  }
+}
+
+impl Buffer for SharedBuffer {
+  fn capacity(&self) -> i64 {
+    match self.parent {
+      Some(_) => self.size,
+      None => self.memory.capacity()
+    }
+  }
+
+  #[inline]
+  fn size(&self) -> i64 {
+    self.size
+  }
+
+  #[inline]
+  fn data(&self) -> *const u8 {
+    self.data
+  }
+
+  fn parent(&self) -> Option<&Buffer> {
+    match self.parent {
+      Some(ref parent) => Some(parent.as_ref()),
+      None => None
+    }
+  }
+}
+
+impl PartialEq for SharedBuffer {
+  fn eq(&self, other: &SharedBuffer) -> bool {
+    self.size == other.size &&
+      (unsafe { self.data == other.data ||
+        libc::memcmp(
+          mem::transmute::<*const u8, *const libc::c_void>(self.data),
+          mem::transmute::<*const u8, *const libc::c_void>(other.data),
+          self.size as usize
+        ) == 0})
+  }
+}
+
+impl Eq for SharedBuffer {}
+
+impl Debug for SharedBuffer {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    f.debug_struct("SharedBuffer")
+      .field("data", &self.data)
+      .field("size", &self.size)
+      .field("parent", &self.parent)
+      .finish()
+  }
+}
+
+/// Fixed-width numeric types, whose values have no padding and are valid for any bit pattern.
+/// Buffers can be created from and converted back into vectors of these types only, because their
+/// bytes are read and reinterpreted as values of other types. This trait is sealed.
+pub trait NativeType: Copy + sealed::Sealed {}
+
+mod sealed {
+  pub trait Sealed {}
+}
+
+macro_rules! native_types {
+  ($($ty: ty),*) => {
+    $(
+      impl sealed::Sealed for $ty {}
+      impl NativeType for $ty {}
+    )*
+  };
+}
+
+native_types!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// A read-only buffer wrapping memory which is not allocated from a memory pool, such as the values
+/// of a `Vec` or static bytes. Creating the buffer doesn't copy the memory, which is released when
+/// the buffer is dropped.
+///
+/// Arrays read the values of their buffers as slices of native types, so a buffer backing the
+/// values of an array must be aligned to the width of the values, e.g. 4 bytes for int32 values or
+/// value offsets. Buffers created by `from_vec()` are aligned to their element type, while
+/// `from_static()` and `from_raw_parts()` give no guarantee beyond that of their memory.
+/// `ArrayData::new()` rejects buffers which are not aligned.
+pub struct ForeignBuffer {
+  data: *const u8,
+  size: i64,
+  capacity: i64,
+  owner: ForeignMemory
+}
+
+enum ForeignMemory {
+  // allocated by the global allocator for a vector whose values are aligned to `align` bytes
+  Vec { align: usize },
+  Static,
+  Custom(Option<Box<FnOnce() + Send>>)
+}
+
+// the memory is never mutated, and the release hook is called only when the buffer is dropped
+unsafe impl Send for ForeignBuffer {}
+unsafe impl Sync for ForeignBuffer {}
+
+impl ForeignBuffer {
+  /// Takes the ownership of the values of `vec`.
+  pub fn from_vec<T: NativeType>(vec: Vec<T>) -> ForeignBuffer {
+    let mut vec = vec;
+    let buffer = ForeignBuffer {
+      data: vec.as_mut_ptr() as *const u8,
+      size: (vec.len() * mem::size_of::<T>()) as i64,
+      capacity: (vec.capacity() * mem::size_of::<T>()) as i64,
+      owner: ForeignMemory::Vec { align: mem::align_of::<T>() }
+    };
+    mem::forget(vec);
+    buffer
+  }
+
+  pub fn from_boxed_slice<T: NativeType>(values: Box<[T]>) -> ForeignBuffer {
+    ForeignBuffer::from_vec(values.into_vec())
+  }
+
+  /// Wraps static bytes, which are aligned only to 1 byte unless they are the bytes of values of a
+  /// wider type.
+  pub fn from_static(bytes: &'static [u8]) -> ForeignBuffer {
+    ForeignBuffer {
+      data: bytes.as_ptr(),
+      size: bytes.len() as i64,
+      capacity: bytes.len() as i64,
+      owner: ForeignMemory::Static
+    }
+  }
+
+  /// Wraps `size` bytes starting from `data`, and calls `release` when the buffer is dropped. The
+  /// buffer has the alignment of `data`.
+  ///
+  /// This is unsafe because the memory must stay valid and unmodified until `release` is called.
+  pub unsafe fn from_raw_parts(data: *const u8, size: i64, release: Box<FnOnce() + Send>) -> ForeignBuffer {
+    ForeignBuffer {
+      data,
+      size,
+      capacity: size,
+      owner: ForeignMemory::Custom(Some(release))
+    }
+  }
+
+  /// Converts the buffer back into a vector without copying. Any bytes are valid values of a
+  /// `NativeType`, so the vector can be of a type other than the original one. This succeeds only
+  /// if the buffer is created from a vector whose values have the same alignment as `T`, and its
+  /// size and capacity are multiples of the size of `T`. Otherwise, the buffer is returned as it is.
+  pub fn into_vec<T: NativeType>(self) -> Result<Vec<T>, ForeignBuffer> {
+    if !self.is_convertible_to_vec::<T>() {
+      return Err(self);
+    }
+
+    let type_size = mem::size_of::<T>() as i64;
+    let vec = unsafe {
+      Vec::from_raw_parts(self.data as *mut T, (self.size / type_size) as usize, (self.capacity / type_size) as usize)
+    };
+    // the vector owns the memory now
+    mem::forget(self);
+    Ok(vec)
+  }
+
+  fn is_convertible_to_vec<T: NativeType>(&self) -> bool {
+    let type_size = mem::size_of::<T>() as i64;
+    match self.owner {
+      ForeignMemory::Vec { align } => {
+        type_size > 0 && align == mem::align_of::<T>() && self.size % type_size == 0 && self.capacity % type_size == 0
+      },
+      _ => false
+    }
+  }
+}
+
+impl Buffer for ForeignBuffer {
+  #[inline]
+  fn capacity(&self) -> i64 {
+    self.capacity
+  }
+
+  #[inline]
+  fn size(&self) -> i64 {
+    self.size
+  }
+
+  #[inline]
+  fn data(&self) -> *const u8 {
+    self.data
+  }
+
+  fn parent(&self) -> Option<&Buffer> {
+    None
+  }
+}
+
+impl Drop for ForeignBuffer {
+  fn drop(&mut self) {
+    match self.owner {
+      ForeignMemory::Vec { align } => {
+        // vectors of zero-sized values or without capacity don't allocate
+        if self.capacity > 0 {
+          unsafe {
+            alloc::dealloc(self.data as *mut u8, Layout::from_size_align_unchecked(self.capacity as usize, align));
+          }
+        }
+      },
+      ForeignMemory::Static => {},
+      ForeignMemory::Custom(ref mut release) => {
+        if let Some(release) = release.take() {
+          release();
+        }
+      }
+    }
+  }
+}
+
+impl Debug for ForeignBuffer {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    f.debug_struct("ForeignBuffer")
+      .field("data", &self.data)
+      .field("size", &self.size)
+      .field("capacity", &self.capacity)
+      .finish()
+  }
+}
+
+/// A read-only buffer backed by a memory mapping of a file region. Reading it doesn't copy the file
+/// into memory, and the mapping is unmapped when the buffer is dropped. Arrays can point into the
+/// mapping by wrapping the buffer into a `SharedBuffer`, which keeps the mapping alive for as long
+/// as any slice of it is referenced. An IPC stream in a mapped file is read without copying by
+/// `StreamReader::from_buffer`.
+///
+/// The file must not be truncated or modified while it's mapped.
+pub struct MmapBuffer {
+  // the mapping starts at a page boundary, which can be before the region
+  mapping: *const u8,
+  mapping_len: usize,
+  data: *const u8,
+  size: i64
+}
+
+// the mapping is never mutated
+unsafe impl Send for MmapBuffer {}
+unsafe impl Sync for MmapBuffer {}
+
+impl MmapBuffer {
+  /// Maps `len` bytes of `file` starting from `offset`, which must be a multiple of 8 bytes so that
+  /// values read from the buffer are aligned. The file can be closed after mapping.
+  ///
+  /// This is unsafe because the buffer is immutable only as long as the file is not truncated or
+  /// modified by this or another process while it's mapped. Reading a truncated region raises
+  /// `SIGBUS`.
+  pub unsafe fn new(file: &File, offset: i64, len: i64) -> Result<MmapBuffer, ArrowError> {
+    let file_len = match file.metadata() {
+      Ok(metadata) => metadata.len() as i64,
+      Err(e) => return Err(ArrowError::io_error(format!("cannot read the file metadata: {}", e)))
+    };
+    let in_file = offset >= 0 && len >= 0 && match offset.checked_add(len) {
+      Some(end) => end <= file_len,
+      None => false
+    };
+    if !in_file {
+      return Err(ArrowError::invalid(
+        format!("region of [{}] bytes at [{}] is out of the file of size [{}]", len, offset, file_len)
+      ));
+    }
+    if offset % 8 != 0 {
+      return Err(ArrowError::invalid(format!("offset [{}] is not a multiple of 8 bytes", offset)));
+    }
+    if len == 0 {
+      // empty mappings are not allowed, but the data pointer must still be non-null and aligned
+      let dangling = ptr::NonNull::<u64>::dangling().as_ptr() as *const u8;
+      return Ok(MmapBuffer {
+        mapping: dangling,
+        mapping_len: 0,
+        data: dangling,
+        size: 0
+      });
+    }
+
+    let page_size = libc::sysconf(libc::_SC_PAGESIZE) as i64;
+    let mapping_offset = offset - offset % page_size;
+    let mapping_len = (offset - mapping_offset + len) as usize;
+    let mapping = libc::mmap(
+      ptr::null_mut(),
+      mapping_len,
+      libc::PROT_READ,
+      libc::MAP_PRIVATE,
+      file.as_raw_fd(),
+      mapping_offset as libc::off_t
+    );
+    if mapping == libc::MAP_FAILED {
+      Err(ArrowError::io_error(format!("mmap of region [{}, {}) failed: {}", offset, offset + len, io::Error::last_os_error())))
+    } else {
+      let mapping = mapping as *const u8;
+      Ok(MmapBuffer {
+        mapping,
+        mapping_len,
+        data: mapping.offset((offset - mapping_offset) as isize),
+        size: len
+      })
+    }
+  }
+
+  /// Maps the whole file at `path`.
+  ///
+  /// This is unsafe for the same reason as `new`.
+  pub unsafe fn from_path<P: AsRef<Path>>(path: P) -> Result<MmapBuffer, ArrowError> {
+    match File::open(path.as_ref()) {
+      Ok(file) => {
+        match file.metadata() {
+          Ok(metadata) => MmapBuffer::new(&file, 0, metadata.len() as i64),
+          Err(e) => Err(ArrowError::io_error(format!("cannot read the file metadata: {}", e)))
+        }
+      },
+      Err(e) => Err(ArrowError::io_error(format!("cannot open {:?}: {}", path.as_ref(), e)))
+    }
+  }
+}
+
+impl Buffer for MmapBuffer {
+  #[inline]
+  fn capacity(&self) -> i64 {
+    self.size
+  }
+
+  #[inline]
+  fn size(&self) -> i64 {
+    self.size
+  }
+
+  #[inline]
+  fn data(&self) -> *const u8 {
+    self.data
+  }
+
+  fn parent(&self) -> Option<&Buffer> {
+    None
+  }
+}
+
+impl Drop for MmapBuffer {
+  fn drop(&mut self) {
+    if self.mapping_len > 0 {
+      unsafe { libc::munmap(self.mapping as *mut libc::c_void, self.mapping_len); }
+    }
+  }
+}
+
+impl Debug for MmapBuffer {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    f.debug_struct("MmapBuffer")
+      .field("data", &self.data)
+      .field("size", &self.size)
+      .finish()
+  }
+}
+
 pub trait TypedBufferBuilder<T> {
   fn append_typed_val(&mut self, val: T) -> Result<(), ArrowError>;
 
@@ -226,17 +715,20 @@ pub trait TypedBufferBuilder<T> {
 }
 
 pub struct BufferBuilder {
-  pool: Arc<RefCell<MemoryPool>>,
+  pool: Arc<MemoryPool>,
   page: *const u8,
   size: i64,
   capacity: i64
 }
 
+unsafe impl Send for BufferBuilder {}
+unsafe impl Sync for BufferBuilder {}
+
 impl BufferBuilder {
-  pub fn new(pool: Arc<RefCell<MemoryPool>>) -> BufferBuilder {
+  pub fn new(pool: Arc<MemoryPool>) -> BufferBuilder {
     BufferBuilder {
       pool,
-      page: unsafe { mem::uninitialized() },
+      page: empty_page(),
       size: 0,
       capacity: 0
     }
@@ -247,13 +739,13 @@ impl BufferBuilder {
       Ok(())
     } else {
       let old_capacity = self.capacity;
-      match resize(&mut self.pool, self.page, self.size, self.capacity, elements) {
+      match resize(&self.pool, self.page, self.size, self.capacity, elements) {
         Ok((new_page, _, new_capacity)) => {
           self.page = new_page;
           self.capacity = new_capacity;
           if new_capacity > old_capacity {
             unsafe {
-              libc::memset(as_mut(self.page), 0, (new_capacity - old_capacity) as usize);
+              libc::memset(as_mut(self.page.offset(old_capacity as isize)), 0, (new_capacity - old_capacity) as usize);
             }
           }
           Ok(())
@@ -293,7 +785,7 @@ impl BufferBuilder {
 
   pub fn advance(&mut self, len: i64) -> Result<(), ArrowError> {
     if self.capacity < len + self.size {
-      match resize(&mut self.pool, self.page, self.size, self.capacity, self.size + len) {
+      match resize(&self.pool, self.page, self.size, self.capacity, self.size + len) {
         Ok((new_page, new_size, new_capacity)) => {
           self.page = new_page;
           self.size = new_size;
@@ -1,28 +1,36 @@
 use common::status::ArrowError;
 use common::bit_util;
-use common::ty;
-use common::ty::Ty;
-use memory_pool::MemoryPool;
-use buffer::{Buffer, PoolBuffer, ResizableBuffer, MutableBuffer};
-use array::{Array, Blob};
+use common::ty::{Ty, UnionMode, IntervalUnit};
+use common::field::Field;
+use common::half::f16;
+use common::decimal;
+use common::decimal::Decimal128;
+use common::temporal;
+use buffer::{PoolBuffer, SharedBuffer, ForeignBuffer, ResizableBuffer, MutableBuffer};
+use array::{Array, ArrayData, Blob};
 
 use std::ptr;
 use std::mem;
+use std::i32;
+use std::str;
+use std::slice;
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
 const MIN_BUILDER_CAPACITY: i64 = 1 << 5;
 
-// TODO: make ArrayData and ues different interfaces for building an array and reading from it
-#[derive(Eq, PartialEq)]
-pub struct ArrayBuilder<'a> {
-  ty: Ty<'a>,
+#[derive(Clone, Eq, PartialEq)]
+pub struct ArrayBuilder {
+  ty: Ty,
   null_count: i64,
   length: i64,
   capacity: i64,
   data: BuilderData
 }
 
-impl <'a> ArrayBuilder<'a> {
-  pub fn null(len: i64) -> ArrayBuilder<'a> {
+impl ArrayBuilder {
+  pub fn null(len: i64) -> ArrayBuilder {
     ArrayBuilder {
       ty: Ty::NA,
       null_count: 0,
@@ -32,7 +40,7 @@ impl <'a> ArrayBuilder<'a> {
     }
   }
 
-  pub fn binary(null_bitmap: PoolBuffer, lengths_and_data: PoolBuffer) -> ArrayBuilder<'a> {
+  pub fn binary(null_bitmap: PoolBuffer, offsets: PoolBuffer, data: PoolBuffer) -> ArrayBuilder {
     ArrayBuilder {
       ty: Ty::Binary,
       null_count: 0,
@@ -40,13 +48,155 @@ impl <'a> ArrayBuilder<'a> {
       capacity: 0,
       data: BuilderData::Binary {
         null_bitmap,
-        lengths_and_data,
-        cur_offset: 0
+        offsets,
+        data
+      }
+    }
+  }
+
+  pub fn string(null_bitmap: PoolBuffer, offsets: PoolBuffer, data: PoolBuffer) -> ArrayBuilder {
+    ArrayBuilder {
+      ty: Ty::String,
+      null_count: 0,
+      length: 0,
+      capacity: 0,
+      data: BuilderData::String {
+        null_bitmap,
+        offsets,
+        data
+      }
+    }
+  }
+
+  /// Creates a builder for a list array. Values of lists are appended to `value_builder`, which
+  /// can be accessed via `value_builder_mut()`.
+  pub fn list(null_bitmap: PoolBuffer, offsets: PoolBuffer, value_builder: ArrayBuilder) -> ArrayBuilder {
+    ArrayBuilder {
+      ty: Ty::list(Box::new(value_builder.ty.clone())),
+      null_count: 0,
+      length: 0,
+      capacity: 0,
+      data: BuilderData::List {
+        null_bitmap,
+        offsets,
+        value_builder: Box::new(value_builder)
+      }
+    }
+  }
+
+  /// Creates a builder for a struct array. Each field builder must have the same type with the
+  /// corresponding field, otherwise this fails with `StatusCode::Invalid`.
+  pub fn struct_type(null_bitmap: PoolBuffer, fields: Vec<Field>, field_builders: Vec<ArrayBuilder>) -> Result<ArrayBuilder, ArrowError> {
+    match check_field_types(&fields, &field_builders) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+
+    Ok(ArrayBuilder {
+      ty: Ty::struct_type(fields),
+      null_count: 0,
+      length: 0,
+      capacity: 0,
+      data: BuilderData::Struct {
+        null_bitmap,
+        field_builders: field_builders.into_iter().map(|b| Box::new(b)).collect()
+      }
+    })
+  }
+
+  /// Creates a builder for a sparse union array. Each field builder must have the same type with the
+  /// corresponding field, and the i-th type code identifies values of the i-th field. Type codes
+  /// must be distinct and non-negative.
+  ///
+  /// Unions don't have validity bitmaps, so nulls of a union are the nulls of its fields.
+  pub fn sparse_union(type_ids: PoolBuffer, fields: Vec<Field>, type_codes: Vec<i8>, field_builders: Vec<ArrayBuilder>) -> Result<ArrayBuilder, ArrowError> {
+    ArrayBuilder::union(type_ids, None, fields, type_codes, UnionMode::SPARSE, field_builders)
+  }
+
+  /// Creates a builder for a dense union array. See `sparse_union()`.
+  pub fn dense_union(type_ids: PoolBuffer, value_offsets: PoolBuffer, fields: Vec<Field>, type_codes: Vec<i8>, field_builders: Vec<ArrayBuilder>) -> Result<ArrayBuilder, ArrowError> {
+    ArrayBuilder::union(type_ids, Some(value_offsets), fields, type_codes, UnionMode::DENSE, field_builders)
+  }
+
+  fn union(type_ids: PoolBuffer, value_offsets: Option<PoolBuffer>, fields: Vec<Field>, type_codes: Vec<i8>, mode: UnionMode, field_builders: Vec<ArrayBuilder>) -> Result<ArrayBuilder, ArrowError> {
+    if fields.len() != type_codes.len() {
+      return Err(ArrowError::invalid(
+        format!("the number of fields [{}] is different from the number of type codes [{}]", fields.len(), type_codes.len())
+      ));
+    }
+    for (i, code) in type_codes.iter().enumerate() {
+      if *code < 0 || type_codes[..i].contains(code) {
+        return Err(ArrowError::invalid(format!("[{}] is not a valid type code of {:?}", code, type_codes)));
+      }
+    }
+    match check_field_types(&fields, &field_builders) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+
+    let field_lengths = field_builders.iter().map(|b| b.len()).collect();
+    Ok(ArrayBuilder {
+      ty: Ty::union_with_mode(fields, type_codes, mode),
+      null_count: 0,
+      length: 0,
+      capacity: 0,
+      data: BuilderData::Union {
+        type_ids,
+        value_offsets,
+        field_builders: field_builders.into_iter().map(|b| Box::new(b)).collect(),
+        field_lengths
       }
+    })
+  }
+
+  /// Creates a builder for a dictionary array. Appended values are deduplicated into
+  /// `value_builder`, and `index_builder` receives the position of each value in the dictionary.
+  ///
+  /// The type of this builder is a dictionary type whose dictionary is empty, because the
+  /// dictionary is known only when the array is built.
+  ///
+  /// The index builder must be an empty integer builder, and the value builder must be an empty
+  /// integer, binary or string builder. Otherwise this fails with `StatusCode::Invalid`.
+  pub fn dictionary(index_builder: ArrayBuilder, value_builder: ArrayBuilder) -> Result<ArrayBuilder, ArrowError> {
+    ArrayBuilder::new_dictionary(index_builder, value_builder, false)
+  }
+
+  /// Creates a builder for a dictionary array whose dictionary order is meaningful. See
+  /// `dictionary()`.
+  pub fn ordered_dictionary(index_builder: ArrayBuilder, value_builder: ArrayBuilder) -> Result<ArrayBuilder, ArrowError> {
+    ArrayBuilder::new_dictionary(index_builder, value_builder, true)
+  }
+
+  fn new_dictionary(index_builder: ArrayBuilder, value_builder: ArrayBuilder, ordered: bool) -> Result<ArrayBuilder, ArrowError> {
+    if !index_builder.ty.is_integer() {
+      return Err(ArrowError::invalid(format!("index type [{:?}] is not an integer", index_builder.ty)));
+    }
+    if !value_builder.ty.is_integer() && value_builder.ty != Ty::Binary && value_builder.ty != Ty::String {
+      return Err(ArrowError::invalid(format!("[{:?}] is not supported for dictionary values", value_builder.ty)));
+    }
+    if index_builder.len() > 0 || value_builder.len() > 0 {
+      return Err(ArrowError::invalid(String::from("index builder and value builder must be empty")));
     }
+
+    let index_type = Box::new(index_builder.ty.clone());
+    let dictionary = Box::new(empty_array(&value_builder.ty));
+    Ok(ArrayBuilder {
+      ty: Ty::Dictionary { index_type, dictionary, ordered },
+      null_count: 0,
+      length: 0,
+      capacity: 0,
+      data: BuilderData::Dictionary {
+        indices: Box::new(index_builder),
+        values: Box::new(value_builder),
+        ordered,
+        memo_table: HashMap::new()
+      }
+    })
   }
 
-  pub fn new_fixed_width(ty: Ty<'a>, null_bitmap: PoolBuffer, data: PoolBuffer) -> ArrayBuilder<'a> {
+  /// Creates a builder for a fixed-width type. This fails with `StatusCode::Invalid` if `ty` isn't
+  /// a fixed-width type or the precision of a decimal type is out of range.
+  pub fn new_fixed_width(ty: Ty, null_bitmap: PoolBuffer, data: PoolBuffer) -> Result<ArrayBuilder, ArrowError> {
     let builder_data = match ty {
       Ty::Bool => BuilderData::Bool { null_bitmap, data },
 
@@ -70,18 +220,32 @@ impl <'a> ArrayBuilder<'a> {
       Ty::Timestamp { unit: ref _unit, timezone: ref _timezone } => BuilderData::Timestamp { null_bitmap, data },
       Ty::Interval { unit: ref _unit } => BuilderData::Interval { null_bitmap, data },
 
-      Ty::FixedSizeBinary { byte_width } => BuilderData::FixedSizeBinary { null_bitmap, data },
+      Ty::FixedSizeBinary { byte_width } => {
+        // the bit width of the type must fit in i32
+        if byte_width < 0 || byte_width > i32::max_value() / 8 {
+          return Err(ArrowError::invalid(format!("[{}] is not a valid byte width of fixed-size binaries", byte_width)));
+        }
+        BuilderData::FixedSizeBinary { null_bitmap, data }
+      },
+      Ty::Decimal { precision, scale } => {
+        if precision <= 0 || precision > decimal::MAX_PRECISION {
+          return Err(ArrowError::invalid(
+            format!("precision [{}] is out of range [1, {}]", precision, decimal::MAX_PRECISION)
+          ));
+        }
+        BuilderData::Decimal { null_bitmap, data }
+      },
 
-      _ => panic!("[{:?}] is not supported type", ty)
+      _ => return Err(ArrowError::invalid(format!("[{:?}] is not a fixed-width type", ty)))
     };
 
-    ArrayBuilder {
+    Ok(ArrayBuilder {
       ty,
       null_count: 0,
       length: 0,
       capacity: 0,
       data: builder_data,
-    }
+    })
   }
 
   #[inline]
@@ -89,6 +253,15 @@ impl <'a> ArrayBuilder<'a> {
     &self.ty
   }
 
+  /// Returns the type of the values appended to this builder, which is the type of the dictionary
+  /// for dictionary builders.
+  fn value_type(&self) -> &Ty {
+    match self.data {
+      BuilderData::Dictionary { ref values, .. } => &values.ty,
+      _ => &self.ty
+    }
+  }
+
   #[inline]
   pub fn null_bitmap(&self) ->Option<&PoolBuffer> {
     self.data.null_bitmap()
@@ -114,10 +287,84 @@ impl <'a> ArrayBuilder<'a> {
     &self.data
   }
 
-  fn get_capacity_for_type(ty: &Ty, req_capacity: i64) -> i64 {
-    match ty {
-      &Ty::Bool => i64::min(req_capacity, MIN_BUILDER_CAPACITY),
-      _ => req_capacity
+  /// Finishes building and returns the immutable data of the built array. The buffers of this
+  /// builder are moved into the array data without copying.
+  pub fn finish(self) -> ArrayData {
+    let ArrayBuilder { ty, null_count, length, data, .. } = self;
+    // builders keep their buffers valid for the values appended so far, which is checked in debug
+    // builds
+    let data = unsafe { ArrayBuilder::finish_data(ty, null_count, length, data) };
+    if cfg!(debug_assertions) {
+      match data.validate() {
+        Ok(_) => {},
+        Err(e) => panic!("builder of {:?} built invalid array data: {:?}", data.ty(), e)
+      }
+    }
+    data
+  }
+
+  unsafe fn finish_data(ty: Ty, null_count: i64, length: i64, data: BuilderData) -> ArrayData {
+    match data {
+      BuilderData::Null => ArrayData::new_unchecked(ty, length, 0, length, Vec::new(), Vec::new()),
+      BuilderData::Bool { null_bitmap, data } |
+      BuilderData::Int8 { null_bitmap, data } |
+      BuilderData::UInt8 { null_bitmap, data } |
+      BuilderData::Int16 { null_bitmap, data } |
+      BuilderData::UInt16 { null_bitmap, data } |
+      BuilderData::Int32 { null_bitmap, data } |
+      BuilderData::UInt32 { null_bitmap, data } |
+      BuilderData::Int64 { null_bitmap, data } |
+      BuilderData::UInt64 { null_bitmap, data } |
+      BuilderData::HalfFloat { null_bitmap, data } |
+      BuilderData::Float { null_bitmap, data } |
+      BuilderData::Double { null_bitmap, data } |
+      BuilderData::Date32 { null_bitmap, data } |
+      BuilderData::Date64 { null_bitmap, data } |
+      BuilderData::Time32 { null_bitmap, data } |
+      BuilderData::Time64 { null_bitmap, data } |
+      BuilderData::Timestamp { null_bitmap, data } |
+      BuilderData::Interval { null_bitmap, data } |
+      BuilderData::Decimal { null_bitmap, data } |
+      BuilderData::FixedSizeBinary { null_bitmap, data } => {
+        ArrayData::new_unchecked(ty, length, 0, null_count, vec![shared(null_bitmap), shared(data)], Vec::new())
+      },
+      BuilderData::Binary { null_bitmap, offsets, data } |
+      BuilderData::String { null_bitmap, offsets, data } => {
+        ArrayData::new_unchecked(ty, length, 0, null_count, vec![shared(null_bitmap), shared(offsets), shared(data)], Vec::new())
+      },
+      BuilderData::List { null_bitmap, offsets, value_builder } => {
+        let child_data = vec![Arc::new(value_builder.finish())];
+        let ty = with_child_types(ty, &child_data);
+        ArrayData::new_unchecked(ty, length, 0, null_count, vec![shared(null_bitmap), shared(offsets)], child_data)
+      },
+      BuilderData::Struct { null_bitmap, field_builders } => {
+        let child_data = field_builders.into_iter().map(|b| Arc::new(b.finish())).collect();
+        let ty = with_child_types(ty, &child_data);
+        ArrayData::new_unchecked(ty, length, 0, null_count, vec![shared(null_bitmap)], child_data)
+      },
+      BuilderData::Union { type_ids, value_offsets, field_builders, .. } => {
+        // unions don't have validity bitmaps
+        let mut buffers = vec![None, shared(type_ids)];
+        match value_offsets {
+          Some(value_offsets) => buffers.push(shared(value_offsets)),
+          None => {}
+        }
+        let child_data = field_builders.into_iter().map(|b| Arc::new(b.finish())).collect();
+        let ty = with_child_types(ty, &child_data);
+        ArrayData::new_unchecked(ty, length, 0, 0, buffers, child_data)
+      },
+      BuilderData::Dictionary { indices, values, ordered, .. } => {
+        // the dictionary type contains the dictionary which is built together with the indices
+        let indices = indices.finish();
+        let index_type = Box::new(indices.ty().clone());
+        let dictionary = Box::new(Array::from(*values));
+        let ty = if ordered {
+          Ty::ordered_dictionary(index_type, dictionary)
+        } else {
+          Ty::dictionary(index_type, dictionary)
+        };
+        ArrayData::new_unchecked(ty, length, 0, null_count, indices.buffers().clone(), Vec::new())
+      }
     }
   }
 
@@ -140,7 +387,13 @@ impl <'a> ArrayBuilder<'a> {
     let new_capacity = bit_util::next_power_2(new_len);
     // the capacity of null bitmap is always same with the array capacity
     match self.data.resize_null_bitmap(new_capacity) {
-      Ok(_) => Ok(new_capacity),
+      Ok(_) => {
+        // offsets must be able to hold one more entry than the array capacity
+        match self.data.resize_offsets(new_capacity + 1) {
+          Ok(_) => Ok(new_capacity),
+          Err(e) => Err(e)
+        }
+      },
       Err(e) => Err(e)
     }
   }
@@ -148,8 +401,8 @@ impl <'a> ArrayBuilder<'a> {
   fn reserve_bool(&mut self) -> Result<(), ArrowError> {
     match self.reserve_null_bitmap(MIN_BUILDER_CAPACITY) {
       Ok(_) => {
-        let new_bits = self.length - self.null_count + 1;
-        let new_bytes = bit_util::bytes_for_bits(new_bits);
+        // values are set at their positions, including those of nulls
+        let new_bytes = bit_util::bytes_for_bits(self.length + 1);
         self.data.resize_data(new_bytes)
       },
       Err(e) => Err(e)
@@ -157,6 +410,10 @@ impl <'a> ArrayBuilder<'a> {
   }
 
   fn reserve_fixed_width_type<T: Size>(&mut self, item: T) -> Result<(), ArrowError> {
+    self.reserve_fixed_width(item.len())
+  }
+
+  fn reserve_fixed_width(&mut self, byte_width: i64) -> Result<(), ArrowError> {
     let new_length = self.length + 1;
     let null_bitmap_prepare_result = if new_length > self.capacity {
       match self.force_resize_null_bitmap(new_length) {
@@ -171,12 +428,12 @@ impl <'a> ArrayBuilder<'a> {
     };
 
     match null_bitmap_prepare_result {
-      Ok(_) => self.data.resize_data(self.capacity * item.len()),
+      Ok(_) => self.data.resize_data(self.capacity * byte_width),
       Err(e) => Err(e)
     }
   }
 
-  fn reserve_blob<T: Size>(&mut self, item: &T) -> Result<(), ArrowError> {
+  fn reserve_blob(&mut self, value_len: i64) -> Result<(), ArrowError> {
     let new_length = self.length + 1;
     let null_bitmap_prepare_result = if new_length > self.capacity {
       match self.force_resize_null_bitmap(new_length) {
@@ -191,7 +448,7 @@ impl <'a> ArrayBuilder<'a> {
     };
 
     match null_bitmap_prepare_result {
-      Ok(_) => self.data.reserve_data(item.len()),
+      Ok(_) => self.data.reserve_data(self.length, value_len),
       Err(e) => Err(e)
     }
   }
@@ -217,63 +474,111 @@ impl <'a> ArrayBuilder<'a> {
         }
         Ok(())
       },
-      _ => {
-        match self.reserve_null_bitmap(1) {
+      BuilderData::Struct { .. } => {
+        // fields of a null struct still need a slot
+        match self.check_field_lengths(self.length + 1) {
+          Ok(_) => self.append_null_bit(),
+          Err(e) => Err(e)
+        }
+      },
+      BuilderData::Dictionary { ref mut indices, .. } => {
+        match indices.append_null() {
           Ok(_) => {
+            self.capacity = indices.capacity();
             self.null_count = self.null_count + 1;
             self.length = self.length + 1;
             Ok(())
           },
           Err(e) => Err(e)
         }
-      }
+      },
+      BuilderData::Union { .. } => {
+        // field builders must not have new values since the last slot, which is checked before any
+        // of them is touched
+        match self.check_nested_null().and_then(|_| self.reserve_null_bitmap(1)) {
+          Ok(_) => self.append_union_null(),
+          Err(e) => Err(e)
+        }
+      },
+      _ => self.append_null_bit()
     }
   }
-}
 
-pub trait Size {
-  fn len(&self) -> i64;
-}
-
-macro_rules! impl_size_for_primitive_types {
-    ($ty: ty) => {
-      impl Size for $ty {
-        fn len(&self) -> i64 {
-          mem::size_of::<$ty>() as i64
-        }
+  fn append_null_bit(&mut self) -> Result<(), ArrowError> {
+    // nulls of fixed-width types take a value slot, which is zeroed when the data grows
+    let reserve_result = match self.data {
+      BuilderData::Bool { .. } => self.reserve_bool(),
+      BuilderData::Binary { .. } |
+      BuilderData::String { .. } |
+      BuilderData::List { .. } |
+      BuilderData::Struct { .. } => self.reserve_null_bitmap(1),
+      _ => {
+        let byte_width = self.ty.bit_width() as i64 / 8;
+        self.reserve_fixed_width(byte_width)
       }
     };
+    match reserve_result {
+      Ok(_) => {
+        match self.data {
+          BuilderData::Binary { ref mut null_bitmap, ref mut offsets, .. } |
+          BuilderData::String { ref mut null_bitmap, ref mut offsets, .. } |
+          BuilderData::List { ref mut null_bitmap, ref mut offsets, .. } => {
+            bit_util::clear_bit(null_bitmap.data_as_mut(), self.length);
+            // a null slot is an empty value
+            let end = value_offset(offsets, self.length);
+            set_value_offset(offsets, self.length + 1, end);
+          },
+          _ => {
+            match self.data.null_bitmap() {
+              Some(null_bitmap) => bit_util::clear_bit(as_mut(null_bitmap.data()), self.length),
+              None => {}
+            }
+          }
+        }
+        self.null_count = self.null_count + 1;
+        self.length = self.length + 1;
+        Ok(())
+      },
+      Err(e) => Err(e)
+    }
+  }
 }
 
-impl_size_for_primitive_types!(u8);
-impl_size_for_primitive_types!(i8);
-impl_size_for_primitive_types!(u16);
-impl_size_for_primitive_types!(i16);
-impl_size_for_primitive_types!(u32);
-impl_size_for_primitive_types!(i32);
-impl_size_for_primitive_types!(u64);
-impl_size_for_primitive_types!(i64);
+impl ArrayBuilder {
+  #[inline]
+  pub fn value_builder(&self) -> &ArrayBuilder {
+    match self.data {
+      BuilderData::List { ref null_bitmap, ref offsets, ref value_builder } => value_builder,
+      _ => panic!("{:?} is not a list builder", self.ty)
+    }
+  }
 
-pub trait Append<T> {
-  fn append(&mut self, val: T) -> Result<(), ArrowError>;
-}
+  #[inline]
+  pub fn value_builder_mut(&mut self) -> &mut ArrayBuilder {
+    match self.data {
+      BuilderData::List { ref null_bitmap, ref offsets, ref mut value_builder } => value_builder,
+      _ => panic!("{:?} is not a list builder", self.ty)
+    }
+  }
 
-impl <'a> Append<bool> for ArrayBuilder<'a> {
-  fn append(&mut self, val: bool) -> Result<(), ArrowError> {
-    match self.reserve_bool() {
+  /// Appends a list which consists of the values appended to the value builder since the last
+  /// list was appended.
+  pub fn append_list(&mut self) -> Result<(), ArrowError> {
+    let values_len = self.value_builder().len();
+    if values_len > i32::MAX as i64 {
+      // value offsets are int32
+      return Err(ArrowError::invalid(format!("list values of length [{}] overflow int32 offsets", values_len)));
+    }
+    match self.reserve_null_bitmap(1) {
       Ok(_) => {
         match self.data {
-          BuilderData::Bool { ref mut null_bitmap, ref mut data } => {
+          BuilderData::List { ref mut null_bitmap, ref mut offsets, ref value_builder } => {
             bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
-            if val {
-              bit_util::set_bit(data.data_as_mut(), self.length);
-            } else {
-              bit_util::clear_bit(data.data_as_mut(), self.length);
-            }
+            set_value_offset(offsets, self.length + 1, value_builder.len() as i32);
             self.length = self.length + 1;
             Ok(())
           },
-          _ => panic!()
+          _ => panic!("{:?} is not a list builder", self.ty)
         }
       },
       Err(e) => Err(e)
@@ -281,470 +586,2393 @@ impl <'a> Append<bool> for ArrayBuilder<'a> {
   }
 }
 
-macro_rules! impl_append_for_primitive_type {
-    ($ty: ty, $builder_data: path) => {
-      impl <'a> Append<$ty> for ArrayBuilder<'a> {
-        fn append(&mut self, val: $ty) -> Result<(), ArrowError> {
-          match self.reserve_fixed_width_type(val) {
-            Ok(_) => {
-              match self.data {
-                $builder_data { ref mut null_bitmap, ref mut data } => {
-                  bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
-                  unsafe { *(mem::transmute::<*mut u8, *mut $ty>(data.data_as_mut()).offset(self.length as isize)) = val }
-                  self.length = self.length + 1;
-                  Ok(())
-                },
-                _ => panic!()
-              }
-            },
-            Err(e) => Err(e)
-          }
+impl ArrayBuilder {
+  #[inline]
+  pub fn num_fields(&self) -> usize {
+    match self.data {
+      BuilderData::Struct { ref field_builders, .. } |
+      BuilderData::Union { ref field_builders, .. } => field_builders.len(),
+      _ => panic!("{:?} is not a struct or union builder", self.ty)
+    }
+  }
+
+  #[inline]
+  pub fn field_builder(&self, i: usize) -> &ArrayBuilder {
+    match self.data {
+      BuilderData::Struct { ref field_builders, .. } |
+      BuilderData::Union { ref field_builders, .. } => &field_builders[i],
+      _ => panic!("{:?} is not a struct or union builder", self.ty)
+    }
+  }
+
+  #[inline]
+  pub fn field_builder_mut(&mut self, i: usize) -> &mut ArrayBuilder {
+    match self.data {
+      BuilderData::Struct { ref mut field_builders, .. } |
+      BuilderData::Union { ref mut field_builders, .. } => &mut field_builders[i],
+      _ => panic!("{:?} is not a struct or union builder", self.ty)
+    }
+  }
+
+  /// Appends a struct which consists of the last values of field builders. Every field builder must
+  /// have exactly one more value than this builder.
+  pub fn append_struct(&mut self) -> Result<(), ArrowError> {
+    match self.check_field_lengths(self.length + 1) {
+      Ok(_) => {
+        match self.reserve_null_bitmap(1) {
+          Ok(_) => {
+            match self.data {
+              BuilderData::Struct { ref mut null_bitmap, ref field_builders } => {
+                bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+                self.length = self.length + 1;
+                Ok(())
+              },
+              _ => panic!("{:?} is not a struct builder", self.ty)
+            }
+          },
+          Err(e) => Err(e)
         }
-      }
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Appends a union value of the given type code.
+  ///
+  /// For sparse unions, the value must be appended to the field builder of the type code, and
+  /// every other field builder must be advanced as well so that all field builders have exactly
+  /// one more value than this builder. For dense unions, exactly one value must be appended only
+  /// to the field builder of the type code, and that value is referenced.
+  ///
+  /// A null is appended by `append_null()` without touching the field builders beforehand. It
+  /// appends a null to the first field builder, and to every other field builder of a sparse
+  /// union, and the null slot refers to the null of the first field. Nulls are appended to the
+  /// fields of struct field builders as well.
+  pub fn append_union(&mut self, type_code: i8) -> Result<(), ArrowError> {
+    let child_id = match self.ty.union_type_codes().iter().position(|code| *code == type_code) {
+      Some(child_id) => child_id,
+      None => return Err(ArrowError::invalid(format!("unknown type code [{}] for {:?}", type_code, self.ty)))
     };
-}
 
-impl_append_for_primitive_type!(u8, BuilderData::UInt8);
-impl_append_for_primitive_type!(i8, BuilderData::Int8);
-impl_append_for_primitive_type!(u16, BuilderData::UInt16);
-impl_append_for_primitive_type!(i16, BuilderData::Int16);
-impl_append_for_primitive_type!(u32, BuilderData::UInt32);
-impl_append_for_primitive_type!(i32, BuilderData::Int32);
-impl_append_for_primitive_type!(u64, BuilderData::UInt64);
-impl_append_for_primitive_type!(i64, BuilderData::Int64);
+    let check_result = match self.ty.union_mode() {
+      &UnionMode::SPARSE => self.check_field_lengths(self.length + 1),
+      &UnionMode::DENSE => self.check_dense_field_lengths(Some(child_id))
+    };
 
-impl <'a> Append<Blob> for ArrayBuilder<'a> {
-  fn append(&mut self, val: Blob) -> Result<(), ArrowError> {
-    let reserve_result = self.reserve_blob(&val);
-    match reserve_result {
+    match check_result {
       Ok(_) => {
-        match self.data {
-          BuilderData::Binary { ref mut null_bitmap, ref mut lengths_and_data, ref mut cur_offset } => {
-            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
-            unsafe {
-              use std::intrinsics;
-              use libc;
-              // write offset
-              *(mem::transmute::<*mut u8, *mut i32>(lengths_and_data.data_as_mut().offset(*cur_offset))) = val.len() as i32;
-              // write data
-              intrinsics::copy(val.p(), lengths_and_data.data_as_mut().offset(*cur_offset + mem::size_of::<i32>() as isize), val.len() as usize);
-//              libc::memcpy(
-//                mem::transmute::<*mut u8, *mut li bc::c_void>(lengths_and_data.data_as_mut().offset(*cur_offset + mem::size_of::<i32>() as isize)),
-//                mem::transmute::<*const u8, *const libc::c_void>(val.p()),
-//                val.len() as libc::size_t
-//              );
+        match self.reserve_null_bitmap(1) {
+          Ok(_) => {
+            match self.data {
+              BuilderData::Union { ref mut type_ids, ref mut value_offsets, ref mut field_lengths, .. } => {
+                unsafe { *type_ids.data_as_mut().offset(self.length as isize) = type_code as u8; }
+                match value_offsets {
+                  &mut Some(ref mut value_offsets) => {
+                    set_value_offset(value_offsets, self.length, field_lengths[child_id] as i32);
+                    field_lengths[child_id] = field_lengths[child_id] + 1;
+                  },
+                  &mut None => {}
+                }
+                self.length = self.length + 1;
+                Ok(())
+              },
+              _ => panic!("{:?} is not a union builder", self.ty)
             }
-            *cur_offset = *cur_offset + mem::size_of::<i32>() as isize + val.len() as isize;
-            self.length = self.length + 1;
-            Ok(())
           },
-          _ => panic!()
+          Err(e) => Err(e)
         }
       },
       Err(e) => Err(e)
     }
   }
-}
 
-#[derive(Clone, Eq, PartialEq)]
-pub enum BuilderData {
-  Null,
-  Bool {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+  // appends a null slot of a union, whose buffers must have been reserved
+  fn append_union_null(&mut self) -> Result<(), ArrowError> {
+    let num_fields = match self.ty.union_mode() {
+      &UnionMode::SPARSE => self.num_fields(),
+      &UnionMode::DENSE => 1
+    };
+    for i in 0..num_fields {
+      match self.field_builder_mut(i).append_nested_null() {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    match self.data {
+      BuilderData::Union { ref mut type_ids, ref mut value_offsets, ref mut field_lengths, .. } => {
+        unsafe { *type_ids.data_as_mut().offset(self.length as isize) = self.ty.union_type_codes()[0] as u8; }
+        match value_offsets {
+          &mut Some(ref mut value_offsets) => {
+            set_value_offset(value_offsets, self.length, field_lengths[0] as i32);
+            field_lengths[0] = field_lengths[0] + 1;
+          },
+          &mut None => {}
+        }
+        self.length = self.length + 1;
+        Ok(())
+      },
+      _ => panic!("{:?} is not a union builder", self.ty)
+    }
+  }
 
-  UInt8 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Int8 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  UInt16 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Int16 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  UInt32 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Int32 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  UInt64 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Int64 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+  // checks that nulls can be appended to this builder and its nested builders by
+  // `append_nested_null()`, so that no builder is touched if any of them would fail
+  fn check_nested_null(&self) -> Result<(), ArrowError> {
+    let (check_result, num_fields) = match self.data {
+      BuilderData::Struct { ref field_builders, .. } => (self.check_field_lengths(self.length), field_builders.len()),
+      BuilderData::Union { ref field_builders, .. } => {
+        match self.ty.union_mode() {
+          &UnionMode::SPARSE => (self.check_field_lengths(self.length), field_builders.len()),
+          // only the first field of a dense union gets a null
+          &UnionMode::DENSE => (self.check_dense_field_lengths(None), 1)
+        }
+      },
+      _ => return Ok(())
+    };
+    match check_result {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    for i in 0..num_fields {
+      match self.field_builder(i).check_nested_null() {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(())
+  }
 
-  HalfFloat {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Float {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Double {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+  // appends a null to this builder and to the fields of a struct, which must have been checked by
+  // `check_nested_null()`
+  fn append_nested_null(&mut self) -> Result<(), ArrowError> {
+    match self.data {
+      BuilderData::Struct { ref mut field_builders, .. } => {
+        for field_builder in field_builders.iter_mut() {
+          match field_builder.append_nested_null() {
+            Ok(_) => {},
+            Err(e) => return Err(e)
+          }
+        }
+      },
+      _ => {}
+    }
+    self.append_null()
+  }
 
-  Binary {
-    null_bitmap: PoolBuffer,
-    lengths_and_data: PoolBuffer,
-    cur_offset: isize
-  },
-  String {
+  /// Checks that only the field builder of `child_id` has one new value since the last slot of a
+  /// dense union.
+  fn check_dense_field_lengths(&self, child_id: Option<usize>) -> Result<(), ArrowError> {
+    match self.data {
+      BuilderData::Union { ref field_builders, ref field_lengths, .. } => {
+        for (i, field_builder) in field_builders.iter().enumerate() {
+          let expected = if child_id == Some(i) { field_lengths[i] + 1 } else { field_lengths[i] };
+          if field_builder.len() != expected {
+            return Err(ArrowError::invalid(
+              format!("field [{}] has {} values, but {} values are expected", self.ty.child(i).name(), field_builder.len(), expected)
+            ));
+          }
+        }
+        Ok(())
+      },
+      _ => panic!("{:?} is not a union builder", self.ty)
+    }
+  }
 
-  },
-  FixedSizeBinary {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+  fn check_field_lengths(&self, expected: i64) -> Result<(), ArrowError> {
+    match self.data {
+      BuilderData::Struct { ref field_builders, .. } |
+      BuilderData::Union { ref field_builders, .. } => {
+        for (i, field_builder) in field_builders.iter().enumerate() {
+          if field_builder.len() != expected {
+            return Err(ArrowError::invalid(
+              format!("field [{}] has {} values, but {} values are expected", self.ty.child(i).name(), field_builder.len(), expected)
+            ));
+          }
+        }
+        Ok(())
+      },
+      _ => panic!("{:?} is not a struct builder", self.ty)
+    }
+  }
+}
 
-  Date64 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Date32 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Timestamp {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Time32 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Time64 {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
-  Interval {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+impl ArrayBuilder {
+  /// Appends the index of a value to a dictionary builder. The value is appended to the dictionary
+  /// by `append_value` only when no value of the same bytes has been appended before. Values are
+  /// looked up by their bytes, so `is_value_type` must first accept the dictionary value builder
+  /// for the type of the value.
+  fn append_dictionary_value<F, G>(&mut self, value_type: &str, key: &[u8], is_value_type: G, append_value: F) -> Result<(), ArrowError>
+    where F: FnOnce(&mut ArrayBuilder) -> Result<(), ArrowError>, G: FnOnce(&BuilderData) -> bool {
+    let result = match self.data {
+      BuilderData::Dictionary { ref mut indices, ref mut values, ref mut memo_table, .. } => {
+        if !is_value_type(&values.data) {
+          return Err(ArrowError::invalid(format!("{} cannot be appended to {:?}", value_type, self.ty)));
+        }
+        let memo_index = memo_table.get(key).map(|index| *index);
+        let index_result = match memo_index {
+          Some(index) => Ok(index),
+          None => {
+            let index = values.len();
+            if index > max_index(indices.ty()) {
+              Err(ArrowError::invalid(format!("dictionary of [{}] values cannot be indexed by [{:?}]", index + 1, indices.ty())))
+            } else {
+              match append_value(values) {
+                Ok(_) => {
+                  memo_table.insert(key.to_vec(), index);
+                  Ok(index)
+                },
+                Err(e) => Err(e)
+              }
+            }
+          }
+        };
+        match index_result {
+          Ok(index) => append_index(indices, index).map(|_| indices.capacity()),
+          Err(e) => Err(e)
+        }
+      },
+      _ => panic!("{:?} is not a dictionary builder", self.ty)
+    };
 
-  Decimal {
-    null_bitmap: PoolBuffer,
-    data: PoolBuffer
-  },
+    match result {
+      Ok(capacity) => {
+        self.capacity = capacity;
+        self.length = self.length + 1;
+        Ok(())
+      },
+      Err(e) => Err(e)
+    }
+  }
+}
 
-  List {
+fn max_index(index_type: &Ty) -> i64 {
+  match index_type {
+    &Ty::Int8 => i8::max_value() as i64,
+    &Ty::UInt8 => u8::max_value() as i64,
+    &Ty::Int16 => i16::max_value() as i64,
+    &Ty::UInt16 => u16::max_value() as i64,
+    &Ty::Int32 => i32::max_value() as i64,
+    &Ty::UInt32 => u32::max_value() as i64,
+    &Ty::Int64 | &Ty::UInt64 => i64::max_value(),
+    _ => panic!("index type [{:?}] is not an integer", index_type)
+  }
+}
 
-  },
-  Struct {
+fn append_index(indices: &mut ArrayBuilder, index: i64) -> Result<(), ArrowError> {
+  match indices.ty().clone() {
+    Ty::Int8 => indices.append(index as i8),
+    Ty::UInt8 => indices.append(index as u8),
+    Ty::Int16 => indices.append(index as i16),
+    Ty::UInt16 => indices.append(index as u16),
+    Ty::Int32 => indices.append(index as i32),
+    Ty::UInt32 => indices.append(index as u32),
+    Ty::Int64 => indices.append(index),
+    Ty::UInt64 => indices.append(index as u64),
+    ty => panic!("index type [{:?}] is not an integer", ty)
+  }
+}
 
-  },
-  Union {
+#[inline]
+fn value_bytes<T>(val: &T) -> &[u8] {
+  unsafe { slice::from_raw_parts(val as *const T as *const u8, mem::size_of::<T>()) }
+}
 
-  },
+fn check_field_types(fields: &Vec<Field>, field_builders: &Vec<ArrayBuilder>) -> Result<(), ArrowError> {
+  if fields.len() != field_builders.len() {
+    return Err(ArrowError::invalid(
+      format!("the number of fields [{}] is different from the number of field builders [{}]", fields.len(), field_builders.len())
+    ));
+  }
+  for (field, field_builder) in fields.iter().zip(field_builders.iter()) {
+    if !builds_type(field.data_type(), &field_builder.ty) {
+      return Err(ArrowError::invalid(
+        format!("field [{}] of [{:?}] cannot be built by a builder of [{:?}]", field.name(), field.data_type(), field_builder.ty)
+      ));
+    }
+  }
+  Ok(())
+}
 
-  Dictionary {
+/// Returns true if a builder of `builder_ty` builds arrays of `ty`. Dictionary types are compared
+/// by the type of their values, because the dictionary of a builder is empty until it's built.
+fn builds_type(ty: &Ty, builder_ty: &Ty) -> bool {
+  match (ty, builder_ty) {
+    (&Ty::Dictionary { ref index_type, ref dictionary, ordered },
+     &Ty::Dictionary { index_type: ref builder_index_type, dictionary: ref builder_dictionary, ordered: builder_ordered }) => {
+      index_type == builder_index_type && dictionary.ty() == builder_dictionary.ty() && ordered == builder_ordered
+    },
+    (&Ty::List { ref value_type }, &Ty::List { value_type: ref builder_value_type }) => builds_type(value_type, builder_value_type),
+    (&Ty::Struct { ref fields }, &Ty::Struct { fields: ref builder_fields }) => builds_fields(fields, builder_fields),
+    (&Ty::Union { ref fields, ref type_codes, ref mode },
+     &Ty::Union { fields: ref builder_fields, type_codes: ref builder_type_codes, mode: ref builder_mode }) => {
+      type_codes == builder_type_codes && mode == builder_mode && builds_fields(fields, builder_fields)
+    },
+    _ => ty == builder_ty
+  }
+}
+
+fn builds_fields(fields: &Vec<Field>, builder_fields: &Vec<Field>) -> bool {
+  fields.len() == builder_fields.len() &&
+    fields.iter().zip(builder_fields.iter()).all(|(field, builder_field)| {
+      field.name() == builder_field.name() &&
+        field.nullable() == builder_field.nullable() &&
+        field.metadata() == builder_field.metadata() &&
+        builds_type(field.data_type(), builder_field.data_type())
+    })
+}
 
+/// Returns `ty` with the types of its built children, which have the dictionaries of
+/// dictionary-encoded children.
+fn with_child_types(ty: Ty, child_data: &Vec<Arc<ArrayData>>) -> Ty {
+  let with_types = |fields: &Vec<Field>| -> Vec<Field> {
+    fields.iter().zip(child_data.iter()).map(|(field, child)| field.with_data_type(child.ty().clone())).collect()
+  };
+  match ty {
+    Ty::List { .. } => Ty::list(Box::new(child_data[0].ty().clone())),
+    Ty::Struct { ref fields } => Ty::struct_type(with_types(fields)),
+    Ty::Union { ref fields, ref type_codes, ref mode } => Ty::union_with_mode(with_types(fields), type_codes.clone(), mode.clone()),
+    ty => ty
   }
 }
 
-impl BuilderData {
-  fn resize_null_bitmap(&mut self, new_capacity: i64) -> Result<(), ArrowError> {
-    match self {
-      &mut BuilderData::Null => Ok(()),
-      &mut BuilderData::Bool { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::Int8 { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::UInt8 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::Int16 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt16 { ref mut null_bitmap, ref mut data } |
-      &mut BuilderData::Int32 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt32 { ref mut null_bitmap, ref mut data } |
-      &mut BuilderData::Int64 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt64 { ref mut null_bitmap, ref mut data } => {
-        let new_bytes = bit_util::bytes_for_bits(new_capacity);
-        if null_bitmap.size() != new_bytes {
-          null_bitmap.resize(new_bytes)
-        } else {
-          Ok(())
+/// Returns an empty array of `ty`, which must not be a nested type.
+fn empty_array(ty: &Ty) -> Array {
+  // empty vectors of u64 have dangling pointers which are aligned to any value of an array
+  let buffers = ty.get_buffer_layout().iter()
+    .map(|_| Some(SharedBuffer::from(ForeignBuffer::from_vec(Vec::<u64>::new()))))
+    .collect();
+  Array::new(Arc::new(ArrayData::new(ty.clone(), 0, 0, 0, buffers, Vec::new()).unwrap()))
+}
+
+pub trait Size {
+  fn len(&self) -> i64;
+}
+
+macro_rules! impl_size_for_primitive_types {
+    ($ty: ty) => {
+      impl Size for $ty {
+        fn len(&self) -> i64 {
+          mem::size_of::<$ty>() as i64
         }
-      },
-      &mut BuilderData::Binary { ref mut null_bitmap, ref mut lengths_and_data, cur_offset } => {
-        let new_bytes = bit_util::bytes_for_bits(new_capacity);
-        if null_bitmap.size() != new_bytes {
-          null_bitmap.resize(new_bytes)
-        } else {
-          Ok(())
+      }
+    };
+}
+
+impl_size_for_primitive_types!(u8);
+impl_size_for_primitive_types!(i8);
+impl_size_for_primitive_types!(u16);
+impl_size_for_primitive_types!(i16);
+impl_size_for_primitive_types!(u32);
+impl_size_for_primitive_types!(i32);
+impl_size_for_primitive_types!(u64);
+impl_size_for_primitive_types!(i64);
+impl_size_for_primitive_types!(f16);
+impl_size_for_primitive_types!(f32);
+impl_size_for_primitive_types!(f64);
+impl_size_for_primitive_types!(Decimal128);
+
+pub trait Append<T> {
+  fn append(&mut self, val: T) -> Result<(), ArrowError>;
+}
+
+impl Append<bool> for ArrayBuilder {
+  fn append(&mut self, val: bool) -> Result<(), ArrowError> {
+    match self.data {
+      BuilderData::Bool { .. } => {},
+      _ => return Err(ArrowError::invalid(format!("bool cannot be appended to {:?}", self.ty)))
+    }
+
+    match self.reserve_bool() {
+      Ok(_) => {
+        match self.data {
+          BuilderData::Bool { ref mut null_bitmap, ref mut data } => {
+            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+            if val {
+              bit_util::set_bit(data.data_as_mut(), self.length);
+            } else {
+              bit_util::clear_bit(data.data_as_mut(), self.length);
+            }
+            self.length = self.length + 1;
+            Ok(())
+          },
+          _ => unreachable!()
         }
       },
-      _ => panic!()
+      Err(e) => Err(e)
     }
   }
+}
 
-  fn reserve_data(&mut self, reserve_bytes: i64) -> Result<(), ArrowError> {
-    match self {
-      &mut BuilderData::Null => Ok(()),
-      &mut BuilderData::Bool { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::Int8 { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::UInt8 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::Int16 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt16 { ref mut null_bitmap, ref mut data } |
-      &mut BuilderData::Int32 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt32 { ref mut null_bitmap, ref mut data } |
-      &mut BuilderData::Int64 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt64 { ref mut null_bitmap, ref mut data } => {
-        if reserve_bytes > 0 {
-          let new_bytes = reserve_bytes + data.size();
-          data.resize(new_bytes)
-        } else {
-          Ok(())
+// temporal types share the append of their storage type
+macro_rules! impl_append_for_primitive_type {
+    ($ty: ty, $($builder_data: path),+) => {
+      impl Append<$ty> for ArrayBuilder {
+        fn append(&mut self, val: $ty) -> Result<(), ArrowError> {
+          if self.data.is_dictionary() {
+            return self.append_dictionary_value(
+              stringify!($ty),
+              value_bytes(&val),
+              |values| match values { $(&$builder_data { .. })|+ => true, _ => false },
+              |values| values.append(val)
+            );
+          }
+          // intervals are stored as i32 or i64 depending on their unit
+          match self.data {
+            BuilderData::Interval { .. } if self.ty.bit_width() as usize != mem::size_of::<$ty>() * 8 => {
+              return Err(ArrowError::invalid(format!("{} cannot be appended to {:?}", stringify!($ty), self.ty)));
+            },
+            $($builder_data { .. })|+ => {},
+            _ => return Err(ArrowError::invalid(format!("{} cannot be appended to {:?}", stringify!($ty), self.ty)))
+          }
+
+          match self.reserve_fixed_width_type(val) {
+            Ok(_) => {
+              match self.data {
+                $($builder_data { ref mut null_bitmap, ref mut data })|+ => {
+                  bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+                  unsafe { *(mem::transmute::<*mut u8, *mut $ty>(data.data_as_mut()).offset(self.length as isize)) = val }
+                  self.length = self.length + 1;
+                  Ok(())
+                },
+                _ => unreachable!()
+              }
+            },
+            Err(e) => Err(e)
+          }
         }
-      },
-      &mut BuilderData::Binary { ref mut null_bitmap, ref mut lengths_and_data, cur_offset } => {
-        if reserve_bytes > 0 {
-          let new_bytes = mem::size_of::<i32>() as i64 + reserve_bytes + lengths_and_data.size();
-          lengths_and_data.resize(new_bytes)
-        } else {
-          Ok(())
+      }
+    };
+}
+
+impl_append_for_primitive_type!(u8, BuilderData::UInt8);
+impl_append_for_primitive_type!(i8, BuilderData::Int8);
+impl_append_for_primitive_type!(u16, BuilderData::UInt16);
+impl_append_for_primitive_type!(i16, BuilderData::Int16);
+impl_append_for_primitive_type!(u32, BuilderData::UInt32);
+impl_append_for_primitive_type!(i32, BuilderData::Int32, BuilderData::Date32, BuilderData::Time32, BuilderData::Interval);
+impl_append_for_primitive_type!(u64, BuilderData::UInt64);
+impl_append_for_primitive_type!(i64, BuilderData::Int64, BuilderData::Date64, BuilderData::Time64, BuilderData::Timestamp, BuilderData::Interval);
+impl_append_for_primitive_type!(f16, BuilderData::HalfFloat);
+impl_append_for_primitive_type!(f32, BuilderData::Float);
+impl_append_for_primitive_type!(f64, BuilderData::Double);
+
+impl Append<Decimal128> for ArrayBuilder {
+  fn append(&mut self, val: Decimal128) -> Result<(), ArrowError> {
+    let precision = match self.ty {
+      Ty::Decimal { precision, .. } => precision,
+      _ => return Err(ArrowError::invalid(format!("{:?} cannot be appended to {:?}", val, self.ty)))
+    };
+    if !val.fits_in_precision(precision) {
+      return Err(ArrowError::invalid(format!("{:?} exceeds the precision [{}]", val, precision)));
+    }
+
+    match self.reserve_fixed_width_type(val) {
+      Ok(_) => {
+        match self.data {
+          BuilderData::Decimal { ref mut null_bitmap, ref mut data } => {
+            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+            unsafe { *(mem::transmute::<*mut u8, *mut Decimal128>(data.data_as_mut()).offset(self.length as isize)) = val }
+            self.length = self.length + 1;
+            Ok(())
+          },
+          _ => unreachable!()
         }
       },
-      _ => panic!()
+      Err(e) => Err(e)
     }
   }
+}
 
-  fn resize_data(&mut self, new_bytes: i64) -> Result<(), ArrowError> {
-    match self {
-      &mut BuilderData::Null => Ok(()),
-      &mut BuilderData::Bool { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::Int8 { ref mut null_bitmap, ref mut data }   |
-      &mut BuilderData::UInt8 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::Int16 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt16 { ref mut null_bitmap, ref mut data } |
-      &mut BuilderData::Int32 { ref mut null_bitmap, ref mut data }  |
+// the length of a blob is int32 like value offsets
+fn blob_len(len: usize) -> Result<i32, ArrowError> {
+  if len > i32::MAX as usize {
+    Err(ArrowError::invalid(format!("value of [{}] bytes overflows int32 offsets", len)))
+  } else {
+    Ok(len as i32)
+  }
+}
+
+/// Appends a value to a fixed-size binary builder, whose length must be the byte width of the
+/// builder. Binary builders accept values of any length.
+impl <'b> Append<&'b [u8]> for ArrayBuilder {
+  fn append(&mut self, val: &'b [u8]) -> Result<(), ArrowError> {
+    if self.data.is_dictionary() {
+      return self.append_dictionary_value("&[u8]", val, BuilderData::is_binary, |values| values.append(val));
+    }
+
+    let byte_width = match self.ty {
+      Ty::FixedSizeBinary { byte_width } => byte_width as i64,
+      Ty::Binary => {
+        return match blob_len(val.len()) {
+          Ok(len) => self.append(Blob::new(val.as_ptr(), len)),
+          Err(e) => Err(e)
+        };
+      },
+      _ => return Err(ArrowError::invalid(format!("value of [{}] bytes cannot be appended to {:?}", val.len(), self.ty)))
+    };
+    if val.len() as i64 != byte_width {
+      return Err(ArrowError::invalid(format!("value of [{}] bytes cannot be appended to {:?}", val.len(), self.ty)));
+    }
+
+    match self.reserve_fixed_width(byte_width) {
+      Ok(_) => {
+        match self.data {
+          BuilderData::FixedSizeBinary { ref mut null_bitmap, ref mut data } => {
+            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+            unsafe { ptr::copy_nonoverlapping(val.as_ptr(), data.data_as_mut().offset((self.length * byte_width) as isize), val.len()); }
+            self.length = self.length + 1;
+            Ok(())
+          },
+          _ => unreachable!()
+        }
+      },
+      Err(e) => Err(e)
+    }
+  }
+}
+
+/// Appends a point in time to a timestamp or date builder in the unit of the builder.
+impl Append<SystemTime> for ArrayBuilder {
+  fn append(&mut self, val: SystemTime) -> Result<(), ArrowError> {
+    match self.value_type().clone() {
+      Ty::Timestamp { unit, .. } => match temporal::system_time_to_timestamp(&val, &unit) {
+        Ok(value) => self.append(value),
+        Err(e) => Err(e)
+      },
+      Ty::Date32 { unit } => match temporal::system_time_to_date32(&val, &unit) {
+        Ok(value) => self.append(value),
+        Err(e) => Err(e)
+      },
+      Ty::Date64 { unit } => match temporal::system_time_to_date(&val, &unit) {
+        Ok(value) => self.append(value),
+        Err(e) => Err(e)
+      },
+      ty => Err(ArrowError::invalid(format!("{:?} cannot be appended to {:?}", val, ty)))
+    }
+  }
+}
+
+/// Appends a duration to a time builder as the time elapsed since midnight, or to a day-time
+/// interval builder.
+impl Append<Duration> for ArrayBuilder {
+  fn append(&mut self, val: Duration) -> Result<(), ArrowError> {
+    match self.value_type().clone() {
+      Ty::Time32 { unit } => match temporal::time_of_day(&val, &unit) {
+        Ok(value) if value > i32::MAX as i64 => {
+          Err(ArrowError::invalid(format!("{:?} is out of the range of time32 of [{:?}] unit", val, unit)))
+        },
+        Ok(value) => self.append(value as i32),
+        Err(e) => Err(e)
+      },
+      Ty::Time64 { unit } => match temporal::time_of_day(&val, &unit) {
+        Ok(value) => self.append(value),
+        Err(e) => Err(e)
+      },
+      Ty::Interval { unit: IntervalUnit::DayTime } => match temporal::interval_from_duration(&val, &IntervalUnit::DayTime) {
+        Ok(value) => self.append(value),
+        Err(e) => Err(e)
+      },
+      ty => Err(ArrowError::invalid(format!("{:?} cannot be appended to {:?}", val, ty)))
+    }
+  }
+}
+
+impl ArrayBuilder {
+  /// Appends a decimal string which is parsed with the scale of this decimal builder.
+  pub fn append_decimal_str(&mut self, val: &str) -> Result<(), ArrowError> {
+    let scale = match self.ty {
+      Ty::Decimal { scale, .. } => scale,
+      _ => return Err(ArrowError::invalid(format!("decimal string [{}] cannot be appended to {:?}", val, self.ty)))
+    };
+    match Decimal128::parse(val, scale) {
+      Ok(decimal) => self.append(decimal),
+      Err(e) => Err(e)
+    }
+  }
+}
+
+impl Append<Blob> for ArrayBuilder {
+  fn append(&mut self, val: Blob) -> Result<(), ArrowError> {
+    if val.len() < 0 {
+      return Err(ArrowError::invalid(format!("blob has negative length [{}]", val.len())));
+    }
+    if self.data.is_dictionary() {
+      let key = unsafe { slice::from_raw_parts(val.p(), val.len() as usize) };
+      return self.append_dictionary_value("Blob", key, BuilderData::is_binary, |values| values.append(val));
+    }
+    match self.data {
+      BuilderData::Binary { .. } => {},
+      _ => return Err(ArrowError::invalid(format!("{:?} cannot be appended to {:?}", val, self.ty)))
+    }
+
+    let reserve_result = self.reserve_blob(val.len());
+    match reserve_result {
+      Ok(_) => {
+        match self.data {
+          BuilderData::Binary { ref mut null_bitmap, ref mut offsets, ref mut data } => {
+            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+            append_var_width_value(offsets, data, self.length, val.p(), val.len());
+            self.length = self.length + 1;
+            Ok(())
+          },
+          _ => unreachable!()
+        }
+      },
+      Err(e) => Err(e)
+    }
+  }
+}
+
+impl <'b> Append<&'b str> for ArrayBuilder {
+  fn append(&mut self, val: &'b str) -> Result<(), ArrowError> {
+    if self.data.is_dictionary() {
+      return self.append_dictionary_value("&str", val.as_bytes(), BuilderData::is_string, |values| values.append(val));
+    }
+
+    // &str is always valid UTF-8
+    unsafe { self.append_utf8_unchecked(val.as_bytes()) }
+  }
+}
+
+impl ArrayBuilder {
+  /// Appends a UTF-8 encoded value to a string array. Returns an error if the value is not valid
+  /// UTF-8.
+  pub fn append_utf8(&mut self, val: &[u8]) -> Result<(), ArrowError> {
+    match str::from_utf8(val) {
+      Ok(val) => self.append(val),
+      Err(e) => Err(ArrowError::invalid(format!("invalid utf-8 sequence: {}", e)))
+    }
+  }
+
+  /// Appends a value to a string array without checking UTF-8 validity. The caller must make sure
+  /// that the value is valid UTF-8.
+  pub unsafe fn append_utf8_unchecked(&mut self, val: &[u8]) -> Result<(), ArrowError> {
+    match self.data {
+      BuilderData::String { .. } => {},
+      _ => return Err(ArrowError::invalid(format!("string cannot be appended to {:?}", self.ty)))
+    }
+
+    match self.reserve_blob(val.len() as i64) {
+      Ok(_) => {
+        match self.data {
+          BuilderData::String { ref mut null_bitmap, ref mut offsets, ref mut data } => {
+            bit_util::set_bit(null_bitmap.data_as_mut(), self.length);
+            append_var_width_value(offsets, data, self.length, val.as_ptr(), val.len() as i64);
+            self.length = self.length + 1;
+            Ok(())
+          },
+          _ => unreachable!()
+        }
+      },
+      Err(e) => Err(e)
+    }
+  }
+}
+
+#[inline]
+fn value_offset(offsets: &PoolBuffer, i: i64) -> i32 {
+  unsafe { *mem::transmute::<*const u8, *const i32>(offsets.data()).offset(i as isize) }
+}
+
+#[inline]
+fn set_value_offset(offsets: &mut PoolBuffer, i: i64, offset: i32) {
+  unsafe { *mem::transmute::<*mut u8, *mut i32>(offsets.data_as_mut()).offset(i as isize) = offset }
+}
+
+/// Copies a variable-width value into the end of the value buffer and writes the end offset of
+/// the i-th slot. The caller must have reserved enough space for both buffers.
+fn append_var_width_value(offsets: &mut PoolBuffer, data: &mut PoolBuffer, i: i64, p: *const u8, len: i64) {
+  let start = value_offset(offsets, i);
+  unsafe {
+    ptr::copy(p, data.data_as_mut().offset(start as isize), len as usize);
+  }
+  set_value_offset(offsets, i + 1, start + len as i32);
+}
+
+#[inline]
+fn as_mut(p: *const u8) -> *mut u8 {
+  unsafe { mem::transmute::<*const u8, *mut u8>(p) }
+}
+
+#[inline]
+fn shared(buffer: PoolBuffer) -> Option<SharedBuffer> {
+  Some(SharedBuffer::new(buffer))
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub enum BuilderData {
+  Null,
+  Bool {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  UInt8 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Int8 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  UInt16 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Int16 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  UInt32 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Int32 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  UInt64 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Int64 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  HalfFloat {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Float {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Double {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  Binary {
+    null_bitmap: PoolBuffer,
+    offsets: PoolBuffer,
+    data: PoolBuffer
+  },
+  String {
+    null_bitmap: PoolBuffer,
+    offsets: PoolBuffer,
+    data: PoolBuffer
+  },
+  FixedSizeBinary {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  Date64 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Date32 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Timestamp {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Time32 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Time64 {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+  Interval {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  Decimal {
+    null_bitmap: PoolBuffer,
+    data: PoolBuffer
+  },
+
+  List {
+    null_bitmap: PoolBuffer,
+    offsets: PoolBuffer,
+    value_builder: Box<ArrayBuilder>
+  },
+  Struct {
+    null_bitmap: PoolBuffer,
+    field_builders: Vec<Box<ArrayBuilder>>
+  },
+  Union {
+    type_ids: PoolBuffer,
+    // only dense unions have value offsets
+    value_offsets: Option<PoolBuffer>,
+    field_builders: Vec<Box<ArrayBuilder>>,
+    // the number of values of each field builder that are referenced by dense union slots
+    field_lengths: Vec<i64>
+  },
+
+  Dictionary {
+    indices: Box<ArrayBuilder>,
+    values: Box<ArrayBuilder>,
+    ordered: bool,
+    // bytes of each value to its position in the dictionary
+    memo_table: HashMap<Vec<u8>, i64>
+  }
+}
+
+impl BuilderData {
+  #[inline]
+  fn is_dictionary(&self) -> bool {
+    match self {
+      &BuilderData::Dictionary { .. } => true,
+      _ => false
+    }
+  }
+
+  fn is_binary(&self) -> bool {
+    match self {
+      &BuilderData::Binary { .. } => true,
+      _ => false
+    }
+  }
+
+  fn is_string(&self) -> bool {
+    match self {
+      &BuilderData::String { .. } => true,
+      _ => false
+    }
+  }
+
+  fn resize_null_bitmap(&mut self, new_capacity: i64) -> Result<(), ArrowError> {
+    match self {
+      &mut BuilderData::Null => Ok(()),
+      &mut BuilderData::Bool { ref mut null_bitmap, ref mut data }   |
+      &mut BuilderData::Int8 { ref mut null_bitmap, ref mut data }   |
+      &mut BuilderData::UInt8 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::Int16 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::UInt16 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Int32 { ref mut null_bitmap, ref mut data }  |
       &mut BuilderData::UInt32 { ref mut null_bitmap, ref mut data } |
       &mut BuilderData::Int64 { ref mut null_bitmap, ref mut data }  |
-      &mut BuilderData::UInt64 { ref mut null_bitmap, ref mut data } => {
-        if data.size() != new_bytes {
-          data.resize(new_bytes)
+      &mut BuilderData::UInt64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::HalfFloat { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Float { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::Double { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Date32 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Date64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Time32 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Time64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Timestamp { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Interval { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Decimal { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::FixedSizeBinary { ref mut null_bitmap, ref mut data } => {
+        let new_bytes = bit_util::bytes_for_bits(new_capacity);
+        if null_bitmap.size() != new_bytes {
+          null_bitmap.resize(new_bytes)
+        } else {
+          Ok(())
+        }
+      },
+      &mut BuilderData::Binary { ref mut null_bitmap, ref mut offsets, .. } |
+      &mut BuilderData::String { ref mut null_bitmap, ref mut offsets, .. } |
+      &mut BuilderData::List { ref mut null_bitmap, ref mut offsets, .. } => {
+        let new_bytes = bit_util::bytes_for_bits(new_capacity);
+        if null_bitmap.size() != new_bytes {
+          null_bitmap.resize(new_bytes)
         } else {
           Ok(())
         }
       },
-      &mut BuilderData::Binary { ref mut null_bitmap, ref mut lengths_and_data, cur_offset } => {
-        if lengths_and_data.size() != new_bytes {
-          lengths_and_data.resize(new_bytes)
+      &mut BuilderData::Struct { ref mut null_bitmap, ref mut field_builders } => {
+        let new_bytes = bit_util::bytes_for_bits(new_capacity);
+        if null_bitmap.size() != new_bytes {
+          null_bitmap.resize(new_bytes)
         } else {
           Ok(())
         }
       },
+      &mut BuilderData::Union { ref mut type_ids, ref mut value_offsets, .. } => {
+        // unions don't have null bitmaps, but type ids and value offsets grow with the capacity
+        let result = type_ids.resize(new_capacity);
+        match value_offsets {
+          &mut Some(ref mut value_offsets) => result.and_then(|_| value_offsets.resize(new_capacity * mem::size_of::<i32>() as i64)),
+          &mut None => result
+        }
+      },
       _ => panic!()
     }
-  }
+  }
+
+  fn resize_offsets(&mut self, new_len: i64) -> Result<(), ArrowError> {
+    match self {
+      &mut BuilderData::Binary { ref mut null_bitmap, ref mut offsets, .. } |
+      &mut BuilderData::String { ref mut null_bitmap, ref mut offsets, .. } |
+      &mut BuilderData::List { ref mut null_bitmap, ref mut offsets, .. } => {
+        let new_bytes = new_len * mem::size_of::<i32>() as i64;
+        if offsets.size() < new_bytes {
+          let is_empty = offsets.size() == 0;
+          match offsets.resize(new_bytes) {
+            Ok(_) => {
+              if is_empty {
+                // the first value always starts at 0
+                set_value_offset(offsets, 0, 0);
+              }
+              Ok(())
+            },
+            Err(e) => Err(e)
+          }
+        } else {
+          Ok(())
+        }
+      },
+      _ => Ok(())
+    }
+  }
+
+  fn reserve_data(&mut self, length: i64, reserve_bytes: i64) -> Result<(), ArrowError> {
+    match self {
+      &mut BuilderData::Null => Ok(()),
+      &mut BuilderData::Bool { ref mut null_bitmap, ref mut data }   |
+      &mut BuilderData::Int8 { ref mut null_bitmap, ref mut data }   |
+      &mut BuilderData::UInt8 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::Int16 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::UInt16 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Int32 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::UInt32 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Int64 { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::UInt64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::HalfFloat { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Float { ref mut null_bitmap, ref mut data }  |
+      &mut BuilderData::Double { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Date32 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Date64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Time32 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Time64 { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Timestamp { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Interval { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::Decimal { ref mut null_bitmap, ref mut data } |
+      &mut BuilderData::FixedSizeBinary { ref mut null_bitmap, ref mut data } => {
+        if reserve_bytes > 0 {
+          let new_bytes = reserve_bytes + data.size();
+          data.resize(new_bytes)
+        } else {
+          Ok(())
+        }
+      },
+      &mut BuilderData::Binary { ref mut null_bitmap, ref mut offsets, ref mut data } |
+      &mut BuilderData::String { ref mut null_bitmap, ref mut offsets, ref mut data } => {
+        let new_bytes = value_offset(offsets, length) as i64 + reserve_bytes;
+        if new_bytes > i32::MAX as i64 {
+          // value offsets are int32
+          Err(ArrowError::invalid(format!("value data of [{}] bytes overflows int32 offsets", new_bytes)))
+        } else if new_bytes > data.size() {
+          data.resize(bit_util::next_power_2(new_bytes))
+        } else {
+          Ok(())
+        }
+      },
+      _ => panic!()
+    }
+  }
+
+  fn resize_data(&mut self, new_bytes: i64) -> Result<(), ArrowError> {
+    match self {
+      &mut BuilderData::Null => Ok(()),
+      &mut BuilderData::Bool { ref mut data, .. }   |
+      &mut BuilderData::Int8 { ref mut data, .. }   |
+      &mut BuilderData::UInt8 { ref mut data, .. }  |
+      &mut BuilderData::Int16 { ref mut data, .. }  |
+      &mut BuilderData::UInt16 { ref mut data, .. } |
+      &mut BuilderData::Int32 { ref mut data, .. }  |
+      &mut BuilderData::UInt32 { ref mut data, .. } |
+      &mut BuilderData::Int64 { ref mut data, .. }  |
+      &mut BuilderData::UInt64 { ref mut data, .. } |
+      &mut BuilderData::HalfFloat { ref mut data, .. } |
+      &mut BuilderData::Float { ref mut data, .. }  |
+      &mut BuilderData::Double { ref mut data, .. } |
+      &mut BuilderData::Date32 { ref mut data, .. } |
+      &mut BuilderData::Date64 { ref mut data, .. } |
+      &mut BuilderData::Time32 { ref mut data, .. } |
+      &mut BuilderData::Time64 { ref mut data, .. } |
+      &mut BuilderData::Timestamp { ref mut data, .. } |
+      &mut BuilderData::Interval { ref mut data, .. } |
+      &mut BuilderData::Decimal { ref mut data, .. } |
+      &mut BuilderData::FixedSizeBinary { ref mut data, .. } => {
+        let old_bytes = data.size();
+        if old_bytes != new_bytes {
+          match data.resize(new_bytes) {
+            Ok(_) => {
+              // slots of nulls are never written, so they are zeroed when the data grows
+              if new_bytes > old_bytes {
+                unsafe { ptr::write_bytes(data.data_as_mut().offset(old_bytes as isize), 0, (new_bytes - old_bytes) as usize); }
+              }
+              Ok(())
+            },
+            Err(e) => Err(e)
+          }
+        } else {
+          Ok(())
+        }
+      },
+      &mut BuilderData::Binary { ref mut null_bitmap, ref mut offsets, ref mut data } |
+      &mut BuilderData::String { ref mut null_bitmap, ref mut offsets, ref mut data } => {
+        if data.size() != new_bytes {
+          data.resize(new_bytes)
+        } else {
+          Ok(())
+        }
+      },
+      _ => panic!()
+    }
+  }
+
+  fn null_bitmap(&self) -> Option<&PoolBuffer> {
+    match self {
+      &BuilderData::Bool { ref null_bitmap, .. } |
+      &BuilderData::Int8 { ref null_bitmap, .. } |
+      &BuilderData::UInt8 { ref null_bitmap, .. } |
+      &BuilderData::Int16 { ref null_bitmap, .. } |
+      &BuilderData::UInt16 { ref null_bitmap, .. } |
+      &BuilderData::Int32 { ref null_bitmap, .. } |
+      &BuilderData::UInt32 { ref null_bitmap, .. } |
+      &BuilderData::Int64 { ref null_bitmap, .. } |
+      &BuilderData::UInt64 { ref null_bitmap, .. } |
+      &BuilderData::HalfFloat { ref null_bitmap, .. } |
+      &BuilderData::Float { ref null_bitmap, .. } |
+      &BuilderData::Double { ref null_bitmap, .. } |
+      &BuilderData::Date32 { ref null_bitmap, .. } |
+      &BuilderData::Date64 { ref null_bitmap, .. } |
+      &BuilderData::Time32 { ref null_bitmap, .. } |
+      &BuilderData::Time64 { ref null_bitmap, .. } |
+      &BuilderData::Timestamp { ref null_bitmap, .. } |
+      &BuilderData::Interval { ref null_bitmap, .. } |
+      &BuilderData::Decimal { ref null_bitmap, .. } |
+      &BuilderData::FixedSizeBinary { ref null_bitmap, .. } => Some(null_bitmap),
+      &BuilderData::Binary { ref null_bitmap, .. } |
+      &BuilderData::String { ref null_bitmap, .. } => Some(null_bitmap),
+      &BuilderData::List { ref null_bitmap, .. } => Some(null_bitmap),
+      &BuilderData::Struct { ref null_bitmap, .. } => Some(null_bitmap),
+      // nulls of a dictionary array are the nulls of its indices
+      &BuilderData::Dictionary { ref indices, .. } => indices.null_bitmap(),
+      _ => None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use memory_pool::DefaultMemoryPool;
+  use buffer::{Buffer, PoolBuffer};
+  use common::ty::{Ty, TimeUnit, DateUnit, IntervalUnit};
+  use std::sync::Arc;
+  use builder::{ArrayBuilder, Append};
+  use array::{Array, ArrowValue, ArrowSlice};
+  use rand;
+
+  #[test]
+  fn test_null_builder() {
+    let mut builder = ArrayBuilder::null(0);
+    for i in 0..100 {
+      builder.append_null();
+    }
+
+    assert_eq!(100, builder.len());
+    assert_eq!(100, builder.null_count());
+    assert_eq!(128, builder.capacity());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&Ty::NA, array.ty());
+    assert_eq!(100, array.null_count());
+    assert_eq!(100, array.len());
+//    assert_eq!(0, array.offset());
+  }
+
+  #[test]
+  fn test_bool_builder() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Bool, null_bitmap, data).unwrap();
+    let mut expected: Vec<bool> = Vec::new();
+    for i in 0..100 {
+      let val = rand::random::<bool>();
+      builder.append(val);
+      expected.push(val);
+    }
+
+    assert_eq!(100, builder.len());
+    assert_eq!(256, builder.capacity());
+    assert_eq!(0, builder.null_count());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&Ty::Bool, array.ty());
+    assert_eq!(100, array.len());
+    assert_eq!(0, array.null_count());
+//    assert_eq!(0, array.offset());
+
+    for i in 0..100 {
+      assert_eq!(expected[i], array.value(i as i64));
+    }
+  }
+
+  // TODO: test boolean with null
+
+  macro_rules! test_primitive_type_builder {
+      ($test_name: ident, $ty: expr, $prim_ty: ty, $expected_capacity: expr) => {
+        #[test]
+        fn $test_name() {
+          let pool = Arc::new(DefaultMemoryPool::new());
+          let null_bitmap = PoolBuffer::new(pool.clone());
+          let data = PoolBuffer::new(pool.clone());
+
+          let mut builder = ArrayBuilder::new_fixed_width($ty, null_bitmap, data).unwrap();
+          let mut expected: Vec<$prim_ty> = Vec::new();
+          for i in 0..100 {
+            let val = rand::random::<$prim_ty>();
+            builder.append(val);
+            expected.push(val);
+          }
+
+          assert_eq!(100, builder.len());
+          assert_eq!($expected_capacity, builder.capacity());
+          assert_eq!(0, builder.null_count());
+
+          let array = Array::from(builder);
+
+          assert_eq!(&$ty, array.ty());
+          assert_eq!(100, array.len());
+          assert_eq!(0, array.null_count());
+//          assert_eq!(0, array.offset());
+
+          for i in 0..100 {
+            assert_eq!(expected[i], array.value(i as i64));
+          }
+
+          assert_eq!(expected, array.values());
+        }
+      };
+  }
+
+  test_primitive_type_builder!(test_i8_builder, Ty::Int8, i8, 128);
+  test_primitive_type_builder!(test_u8_builder, Ty::UInt8, u8, 128);
+  test_primitive_type_builder!(test_i16_builder, Ty::Int16, i16, 128);
+  test_primitive_type_builder!(test_u16_builder, Ty::UInt16, u16, 128);
+  test_primitive_type_builder!(test_i32_builder, Ty::Int32, i32, 128);
+  test_primitive_type_builder!(test_u32_builder, Ty::UInt32, u32, 128);
+  test_primitive_type_builder!(test_i64_builder, Ty::Int64, i64, 128);
+  test_primitive_type_builder!(test_u64_builder, Ty::UInt64, u64, 128);
+  test_primitive_type_builder!(test_f32_builder, Ty::Float, f32, 128);
+  test_primitive_type_builder!(test_f64_builder, Ty::Double, f64, 128);
+  test_primitive_type_builder!(test_date32_builder, Ty::date32_with_unit(DateUnit::Day), i32, 128);
+  test_primitive_type_builder!(test_date64_builder, Ty::date64(), i64, 128);
+  test_primitive_type_builder!(test_time32_builder, Ty::time32_with_unit(TimeUnit::Second), i32, 128);
+  test_primitive_type_builder!(test_time64_builder, Ty::time64_with_unit(TimeUnit::Nano), i64, 128);
+  test_primitive_type_builder!(test_timestamp_builder, Ty::timestamp_with_unit(TimeUnit::Micro), i64, 128);
+  test_primitive_type_builder!(test_interval_builder, Ty::interval_with_unit(IntervalUnit::DayTime), i64, 128);
+  test_primitive_type_builder!(test_year_month_interval_builder, Ty::interval_with_unit(IntervalUnit::YearMonth), i32, 128);
+
+  #[test]
+  fn test_fixed_size_binary_builder() {
+    use array::FixedSizeBinaryArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let ty = Ty::fixed_sized_binary(16);
+    let mut builder = ArrayBuilder::new_fixed_width(ty.clone(), null_bitmap, data).unwrap();
+    let mut expected: Vec<[u8; 16]> = Vec::new();
+    for i in 0..100 {
+      // UUID-like values
+      let mut uuid = [0u8; 16];
+      for j in 0..16 {
+        uuid[j] = rand::random::<u8>();
+      }
+      uuid[6] = (uuid[6] & 0x0f) | 0x40;
+      uuid[8] = (uuid[8] & 0x3f) | 0x80;
+
+      if i % 7 == 0 {
+        builder.append_null().unwrap();
+      } else {
+        builder.append(&uuid[..]).unwrap();
+      }
+      expected.push(uuid);
+    }
+
+    let short: &[u8] = &[1, 2, 3];
+    assert!(builder.append(short).is_err());
+    assert!(builder.append(&[0u8; 17][..]).is_err());
+
+    assert_eq!(100, builder.len());
+    assert_eq!(15, builder.null_count());
+    assert_eq!(128, builder.capacity());
+
+    let array = Array::from(builder);
+    assert_eq!(&ty, array.ty());
+    assert_eq!(16, array.byte_width());
+    assert_eq!(100, array.len());
+    assert_eq!(15, array.null_count());
+    assert_eq!(1600, array.fixed_size_values().len());
+
+    for i in 0..100 {
+      if i % 7 == 0 {
+        assert!(array.is_null(i as i64));
+      } else {
+        assert_eq!(&expected[i][..], array.fixed_size_value(i as i64));
+        let val: &[u8] = array.value(i as i64);
+        assert_eq!(16, val.len());
+        assert_eq!(&expected[i][..], val);
+      }
+    }
+  }
+
+  #[test]
+  fn test_decimal_builder() {
+    use common::decimal::Decimal128;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::decimal(7, 2), null_bitmap, data).unwrap();
+    builder.append_decimal_str("12345.67").unwrap();
+    builder.append(Decimal128::new(-1)).unwrap();
+    builder.append_null().unwrap();
+    builder.append_decimal_str("-99999.99").unwrap();
+    builder.append_decimal_str("0.5").unwrap();
+
+    // exceeds the precision
+    assert!(builder.append_decimal_str("123456.78").is_err());
+    assert!(builder.append(Decimal128::new(10_000_000)).is_err());
+    // exceeds the scale
+    assert!(builder.append_decimal_str("1.234").is_err());
+
+    assert_eq!(5, builder.len());
+    assert_eq!(1, builder.null_count());
+
+    let array = Array::from(builder);
+    assert_eq!(&Ty::decimal(7, 2), array.ty());
+    assert_eq!(5, array.len());
+    assert!(array.is_null(2));
+
+    let expected = vec!["12345.67", "-0.01", "", "-99999.99", "0.50"];
+    for i in 0..5 {
+      if i != 2 {
+        let val: Decimal128 = array.value(i as i64);
+        assert_eq!(expected[i], val.to_string_with_scale(array.ty().decimal_scale()));
+      }
+    }
+  }
+
+  #[test]
+  fn test_append_decimal_to_non_decimal_builder() {
+    use common::decimal::Decimal128;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_decimal_str("1.0").unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append(Decimal128::new(1)).unwrap_err().code());
+    assert_eq!(0, builder.len());
+  }
+
+  #[test]
+  fn test_fixed_width_builder_invalid_type() {
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    for ty in vec![Ty::decimal(0, 0), Ty::decimal(39, 2), Ty::fixed_sized_binary(-4), Ty::String] {
+      let result = ArrayBuilder::new_fixed_width(ty, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+      assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+    }
+  }
+
+  #[test]
+  fn test_trailing_nulls() {
+    use array::{ArrayData, FixedSizeBinaryArray};
+    use common::decimal::Decimal128;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut ints = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut bools = ArrayBuilder::new_fixed_width(Ty::Bool, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut decimals = ArrayBuilder::new_fixed_width(Ty::decimal(7, 2), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut bytes = ArrayBuilder::new_fixed_width(Ty::fixed_sized_binary(3), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    ints.append(1).unwrap();
+    bools.append(true).unwrap();
+    decimals.append(Decimal128::new(1)).unwrap();
+    bytes.append(&[1u8, 2, 3][..]).unwrap();
+    // nulls grow the values as well as the null bitmap
+    for _ in 0..1000 {
+      ints.append_null().unwrap();
+      bools.append_null().unwrap();
+      decimals.append_null().unwrap();
+      bytes.append_null().unwrap();
+    }
+
+    let ints = Array::from(ints);
+    assert_eq!(1001, ints.len());
+    assert_eq!(1000, ints.null_count());
+    assert_eq!(1001, ArrowSlice::<i32>::values(&ints).len());
+    assert_eq!(1, ArrowValue::<i32>::value(&ints, 0));
+    // values of nulls are zeroed
+    assert!(ArrowSlice::<i32>::values(&ints)[1..].iter().all(|val| *val == 0));
+    assert!(ints.data().buffer(1).size() >= 1001 * 4);
+
+    let bools = Array::from(bools);
+    assert_eq!(true, ArrowValue::<bool>::value(&bools, 0));
+    assert_eq!(false, ArrowValue::<bool>::value(&bools, 1000));
+    let decimals = Array::from(decimals);
+    assert_eq!(Decimal128::new(0), ArrowValue::<Decimal128>::value(&decimals, 1000));
+    let bytes = Array::from(bytes);
+    assert_eq!(3003, bytes.fixed_size_values().len());
+    assert_eq!(&[0u8, 0, 0][..], bytes.fixed_size_value(1000));
+
+    // the buffers are valid array data of the whole length
+    for array in vec![ints, bools, decimals, bytes] {
+      let data = ArrayData::new(array.ty().clone(), array.len(), 0, array.null_count(), array.data().buffers().clone(), Vec::new());
+      assert!(data.is_ok(), "{:?}", array.ty());
+    }
+  }
+
+  #[test]
+  fn test_timestamp_system_time() {
+    use common::temporal;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let ty = Ty::timestamp_with_unit(TimeUnit::Micro);
+    let mut builder = ArrayBuilder::new_fixed_width(ty.clone(), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let times: Vec<_> = (0..10).map(|i| UNIX_EPOCH + Duration::new(1_500_000_000 + i * 3_600, i as u32 * 1_000)).collect();
+    for (i, time) in times.iter().enumerate() {
+      if i == 5 {
+        builder.append_null().unwrap();
+      } else {
+        builder.append(*time).unwrap();
+      }
+    }
+
+    let array = Array::from(builder);
+    assert_eq!(1, array.null_count());
+    assert!(array.is_null(5));
+    for (i, time) in times.iter().enumerate() {
+      if i != 5 {
+        let val: i64 = array.value(i as i64);
+        assert_eq!(*time, temporal::system_time_from_timestamp(val, array.ty().time_unit()));
+      }
+    }
+  }
+
+  #[test]
+  fn test_date32_system_time() {
+    use common::temporal;
+    use common::status::StatusCode;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let ty = Ty::date32_with_unit(DateUnit::Day);
+    let mut builder = ArrayBuilder::new_fixed_width(ty.clone(), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let time = UNIX_EPOCH + Duration::from_secs(17_000 * 86_400 + 1_234);
+    builder.append(time).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append(Duration::from_secs(1)).unwrap_err().code());
+
+    let array = Array::from(builder);
+    let val: i32 = array.value(0);
+    assert_eq!(17_000, val);
+    assert_eq!(UNIX_EPOCH + Duration::from_secs(17_000 * 86_400), temporal::system_time_from_date(val as i64, array.ty().date_unit()).unwrap());
+
+    // milliseconds since the epoch don't fit in date32
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::date32_with_unit(DateUnit::Milli), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append(time).unwrap_err().code());
+    assert_eq!(0, builder.len());
+  }
+
+  #[test]
+  fn test_duration_builders() {
+    use common::temporal;
+    use common::status::StatusCode;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let builder = |ty: Ty| ArrayBuilder::new_fixed_width(ty, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let duration = Duration::new(45_296, 789_000_000);
+
+    let mut time32 = builder(Ty::time32_with_unit(TimeUnit::Milli));
+    time32.append(duration).unwrap();
+    assert_eq!(&StatusCode::Invalid, time32.append(Duration::from_secs(86_400)).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, time32.append(UNIX_EPOCH).unwrap_err().code());
+    let array = Array::from(time32);
+    assert_eq!(&[45_296_789], ArrowSlice::<i32>::values(&array));
+
+    // time32 can't hold a time of day in nanoseconds
+    let mut time32 = builder(Ty::time32_with_unit(TimeUnit::Nano));
+    assert_eq!(&StatusCode::Invalid, time32.append(duration).unwrap_err().code());
+
+    let mut time64 = builder(Ty::time64_with_unit(TimeUnit::Nano));
+    time64.append(duration).unwrap();
+    let array = Array::from(time64);
+    assert_eq!(&[45_296_789_000_000], ArrowSlice::<i64>::values(&array));
+
+    let mut interval = builder(Ty::interval_with_unit(IntervalUnit::DayTime));
+    interval.append(Duration::from_secs(2 * 86_400 + 5)).unwrap();
+    assert_eq!(&StatusCode::Invalid, interval.append(3i32).unwrap_err().code());
+    let array = Array::from(interval);
+    assert_eq!((2, 5_000), temporal::day_time_interval_parts(ArrowValue::<i64>::value(&array, 0)));
+
+    // year-month intervals are months in i32, which durations can't express
+    let mut interval = builder(Ty::interval_with_unit(IntervalUnit::YearMonth));
+    interval.append(14i32).unwrap();
+    assert_eq!(&StatusCode::Invalid, interval.append(14i64).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, interval.append(duration).unwrap_err().code());
+    let array = Array::from(interval);
+    assert_eq!(&[14], ArrowSlice::<i32>::values(&array));
+  }
+
+  #[test]
+  fn test_append_value_of_another_type() {
+    use array::Blob;
+    use common::decimal::Decimal128;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let builder = |ty: Ty| ArrayBuilder::new_fixed_width(ty, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let mut float = builder(Ty::Float);
+    assert_eq!(&StatusCode::Invalid, float.append(1.0f64).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, float.append(true).unwrap_err().code());
+    float.append(1.0f32).unwrap();
+
+    let mut int = builder(Ty::Int32);
+    assert_eq!(&StatusCode::Invalid, int.append(1i64).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, int.append(&[1u8, 2, 3, 4][..]).unwrap_err().code());
+    int.append(1i32).unwrap();
+
+    let mut decimal = builder(Ty::decimal(5, 2));
+    assert_eq!(&StatusCode::Invalid, decimal.append(1i64).unwrap_err().code());
+    decimal.append(Decimal128::new(1)).unwrap();
+
+    let mut fixed_size_binary = builder(Ty::fixed_sized_binary(4));
+    assert_eq!(&StatusCode::Invalid, fixed_size_binary.append(1i32).unwrap_err().code());
+    fixed_size_binary.append(&[1u8, 2, 3, 4][..]).unwrap();
+
+    let mut string = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    assert_eq!(&StatusCode::Invalid, string.append(Blob::new(b"abc".as_ptr(), 3)).unwrap_err().code());
+    string.append("abc").unwrap();
+
+    let mut binary = ArrayBuilder::binary(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    assert_eq!(&StatusCode::Invalid, binary.append("abc").unwrap_err().code());
+    binary.append(&b"abc"[..]).unwrap();
+
+    // rejected values don't resize the buffers to their width
+    for builder in vec![float, int, decimal, fixed_size_binary, string, binary] {
+      assert_eq!(1, builder.len());
+      Array::from(builder);
+    }
+  }
+
+  #[test]
+  fn test_half_float_builder() {
+    use common::half::f16;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::HalfFloat, null_bitmap, data).unwrap();
+    let mut expected: Vec<f16> = Vec::new();
+    for i in 0..100 {
+      if i % 10 == 0 {
+        builder.append_null().unwrap();
+      } else {
+        let val = f16::from_f32(i as f32 * 0.5);
+        builder.append(val).unwrap();
+      }
+      expected.push(f16::from_f32(i as f32 * 0.5));
+    }
+
+    assert_eq!(100, builder.len());
+    assert_eq!(10, builder.null_count());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&Ty::HalfFloat, array.ty());
+    assert_eq!(100, array.len());
+    assert_eq!(10, array.null_count());
+
+    for i in 0..100 {
+      if i % 10 == 0 {
+        assert!(array.is_null(i as i64));
+      } else {
+        let val: f16 = array.value(i as i64);
+        assert_eq!(expected[i], val);
+        assert_eq!(i as f32 * 0.5, val.to_f32());
+      }
+    }
+  }
+
+  #[test]
+  fn test_float_array_eq() {
+    use std::f64;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let double_array = |values: &[f64]| {
+      let mut builder = ArrayBuilder::new_fixed_width(Ty::Double, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+      for value in values {
+        builder.append(*value).unwrap();
+      }
+      Array::from(builder)
+    };
+
+    // floating-point values are compared by their bits, so that equality is reflexive
+    assert_eq!(double_array(&[1.5, f64::NAN]), double_array(&[1.5, f64::NAN]));
+    assert!(double_array(&[0.0]) != double_array(&[-0.0]));
+  }
+
+  #[test]
+  fn test_binary_builder() {
+    use memory_pool::MemoryPool;
+    use builder::Size;
+    use array::Blob;
+    use array::ArrayIterator;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let offsets = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let mut builder = ArrayBuilder::binary(null_bitmap, offsets, data);
+    let mut expected: Vec<Blob> = Vec::new();
+    let generator = pool.clone();
+    let mut next_len = 10;
+    for i in 0..100 {
+      let len = next_len;
+      next_len = next_len + 2;
+      if next_len > 50 {
+        next_len = 10;
+      }
+      let p = generator.allocate(len).unwrap();
+      unsafe {
+        use std::mem;
+        use libc;
+        let dst = mem::transmute::<*const u8, *mut libc::c_void>(p);
+        libc::memset(dst, 0, len as usize);
+        libc::memcpy(dst, mem::transmute::<*const i64, *const libc::c_void>(&len), mem::size_of::<i64>());
+      }
+      let val = Blob::new(p, len as i32);
+      builder.append(val);
+      expected.push(val);
+    }
+
+    assert_eq!(100, builder.len());
+    assert_eq!(128, builder.capacity());
+    assert_eq!(0, builder.null_count());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&Ty::Binary, array.ty());
+    assert_eq!(100, array.len());
+    assert_eq!(0, array.null_count());
+//    assert_eq!(0, array.offset());
+
+    for i in 0..100 {
+      assert_eq!(expected[i], array.value(i as i64));
+    }
 
-  fn null_bitmap(&self) -> Option<&PoolBuffer> {
-    match self {
-      &BuilderData::Bool { ref null_bitmap, ref data } |
-      &BuilderData::Int8 { ref null_bitmap, ref data } |
-      &BuilderData::UInt8 { ref null_bitmap, ref data } |
-      &BuilderData::Int16 { ref null_bitmap, ref data } |
-      &BuilderData::UInt16 { ref null_bitmap, ref data } |
-      &BuilderData::Int32 { ref null_bitmap, ref data } |
-      &BuilderData::UInt32 { ref null_bitmap, ref data } |
-      &BuilderData::Int64 { ref null_bitmap, ref data } |
-      &BuilderData::UInt64 { ref null_bitmap, ref data } => Some(null_bitmap),
-      &BuilderData::Binary { ref null_bitmap, ref lengths_and_data, cur_offset } => Some(null_bitmap),
-      _ => None
+    let mut iter = ArrayIterator::new(&array);
+
+    for i in 0..100 {
+      assert_eq!(expected[i], iter.next().unwrap());
+    }
+
+    assert!(iter.next().is_none());
+
+    for blob in expected {
+      pool.free(blob.p(), blob.len())
     }
   }
-}
 
-fn init_buffer(buffer: &mut PoolBuffer, new_bits: i64) -> Result<(), ArrowError> {
-  let nbytes = bit_util::bytes_for_bits(new_bits);
-  buffer.resize(nbytes)
-}
+  #[test]
+  fn test_binary_offsets() {
+    use array::{Blob, VariableWidthArray};
+    use builder::Size;
 
-fn resize_buffer(buffer: &mut PoolBuffer, new_bits: i64) -> Result<(), ArrowError> {
-  let old_bytes = buffer.size();
-  let new_bytes = bit_util::bytes_for_bits(new_bits);
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let offsets = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
 
-  if old_bytes == new_bytes {
-    Ok(())
-  } else {
-    buffer.resize(new_bytes)
+    let vals: Vec<&[u8]> = vec![b"arrow", b"", b"iron", b"columnar"];
+
+    let mut builder = ArrayBuilder::binary(null_bitmap, offsets, data);
+    builder.append(Blob::new(vals[0].as_ptr(), vals[0].len() as i32)).unwrap();
+    builder.append(Blob::new(vals[1].as_ptr(), vals[1].len() as i32)).unwrap();
+    builder.append_null().unwrap();
+    builder.append(Blob::new(vals[2].as_ptr(), vals[2].len() as i32)).unwrap();
+    builder.append(Blob::new(vals[3].as_ptr(), vals[3].len() as i32)).unwrap();
+
+    assert_eq!(5, builder.len());
+    assert_eq!(1, builder.null_count());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&[0, 5, 5, 5, 9, 17], array.value_offsets());
+    assert_eq!(9, array.value_offset(4));
+    assert_eq!(8, array.value_len(4));
+    assert_eq!(0, array.value_len(2));
+
+    let values = unsafe { ::std::slice::from_raw_parts(array.value_data(), 17) };
+    assert_eq!(b"arrowironcolumnar", values);
+
+    assert_eq!(Blob::new(vals[2].as_ptr(), 4), array.value(3));
+    assert_eq!(Blob::new(vals[0].as_ptr(), 5), array.value(0));
+    assert_eq!(0, ArrowValue::<Blob>::value(&array, 1).len());
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use memory_pool::DefaultMemoryPool;
-  use buffer::{PoolBuffer, ResizableBuffer, MutableBuffer};
-  use common::ty::Ty;
-  use std::sync::Arc;
-  use std::cell::RefCell;
-  use builder::{ArrayBuilder, Append};
-  use array::{Array, ArrowSlice};
-  use rand;
+  #[test]
+  fn test_binary_offset_overflow() {
+    use array::Blob;
+    use builder::blob_len;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut builder = ArrayBuilder::binary(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let val = b"arrow";
+    builder.append(Blob::new(val.as_ptr(), val.len() as i32)).unwrap();
+
+    // the value is rejected before any byte of it is read
+    let result = builder.append(Blob::new(val.as_ptr(), i32::MAX - 4));
+    assert_eq!(&StatusCode::Invalid, result.unwrap_err().code());
+    let result = builder.append(Blob::new(val.as_ptr(), -1));
+    assert_eq!(&StatusCode::Invalid, result.unwrap_err().code());
+
+    // slices longer than int32 are not truncated
+    assert_eq!(i32::MAX, blob_len(i32::MAX as usize).unwrap());
+    assert_eq!(&StatusCode::Invalid, blob_len(i32::MAX as usize + 1).unwrap_err().code());
+    assert_eq!(1, builder.len());
+  }
 
   #[test]
-  fn test_null_builder() {
-    let mut builder = ArrayBuilder::null(0);
+  fn test_empty_binary_builder() {
+    use array::VariableWidthArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let builder = ArrayBuilder::binary(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let array = Array::from(builder);
+
+    assert_eq!(0, array.len());
+    assert_eq!(&[0], array.value_offsets());
+  }
+
+  #[test]
+  fn test_string_builder() {
+    use array::{ArrayIterator, VariableWidthArray};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let null_bitmap = PoolBuffer::new(pool.clone());
+    let offsets = PoolBuffer::new(pool.clone());
+    let data = PoolBuffer::new(pool.clone());
+
+    let mut builder = ArrayBuilder::string(null_bitmap, offsets, data);
+    let mut expected: Vec<String> = Vec::new();
     for i in 0..100 {
-      builder.append_null();
+      if i % 10 == 3 {
+        builder.append_null().unwrap();
+        expected.push(String::new());
+      } else {
+        let val = format!("v{}-가나다", i);
+        builder.append(val.as_str()).unwrap();
+        expected.push(val);
+      }
     }
 
     assert_eq!(100, builder.len());
-    assert_eq!(100, builder.null_count());
     assert_eq!(128, builder.capacity());
+    assert_eq!(10, builder.null_count());
 
     let array = Array::from(builder);
 
-    assert_eq!(&Ty::NA, array.ty());
-    assert_eq!(100, array.null_count());
+    assert_eq!(&Ty::String, array.ty());
     assert_eq!(100, array.len());
-//    assert_eq!(0, array.offset());
+    assert_eq!(10, array.null_count());
+
+    for i in 0..100 {
+      let val: &str = array.value(i as i64);
+      assert_eq!(expected[i].as_str(), val);
+      assert_eq!(expected[i].len() as i32, array.value_len(i as i64));
+    }
+
+    let mut iter = ArrayIterator::new(&array);
+    for i in 0..100 {
+      let val: &str = iter.next().unwrap();
+      assert_eq!(expected[i].as_str(), val);
+    }
+    assert!(iter.next().is_none());
   }
 
   #[test]
-  fn test_bool_builder() {
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
-    let null_bitmap = PoolBuffer::new(pool.clone());
-    let data = PoolBuffer::new(pool.clone());
+  fn test_string_builder_utf8_validation() {
+    use common::status::StatusCode;
 
-    let mut builder = ArrayBuilder::new_fixed_width(Ty::Bool, null_bitmap, data);
-    let mut expected: Vec<bool> = Vec::new();
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+
+    builder.append_utf8("arrow".as_bytes()).unwrap();
+    let result = builder.append_utf8(&[0x61, 0xff, 0x62]);
+    assert_eq!(&StatusCode::Invalid, result.unwrap_err().code());
+    unsafe { builder.append_utf8_unchecked("화살".as_bytes()).unwrap(); }
+
+    assert_eq!(2, builder.len());
+
+    let array = Array::from(builder);
+    let val: &str = array.value(0);
+    assert_eq!("arrow", val);
+    let val: &str = array.value(1);
+    assert_eq!("화살", val);
+  }
+
+  #[test]
+  fn test_list_builder() {
+    use array::{ListArray, VariableWidthArray};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let value_builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut builder = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), value_builder);
+
+    let mut expected: Vec<Vec<i32>> = Vec::new();
     for i in 0..100 {
-      let val = rand::random::<bool>();
-      builder.append(val);
-      expected.push(val);
+      if i % 7 == 0 {
+        builder.append_null().unwrap();
+        expected.push(Vec::new());
+      } else {
+        let mut list = Vec::new();
+        for j in 0..(i % 5) {
+          let val = rand::random::<i32>();
+          builder.value_builder_mut().append(val).unwrap();
+          list.push(val);
+        }
+        builder.append_list().unwrap();
+        expected.push(list);
+      }
     }
 
     assert_eq!(100, builder.len());
-    assert_eq!(256, builder.capacity());
-    assert_eq!(0, builder.null_count());
+    assert_eq!(15, builder.null_count());
+    assert_eq!(&Ty::list(Box::new(Ty::Int32)), builder.ty());
+
+    let total_values: usize = expected.iter().map(|list| list.len()).sum();
+    assert_eq!(total_values as i64, builder.value_builder().len());
 
     let array = Array::from(builder);
 
-    assert_eq!(&Ty::Bool, array.ty());
+    assert_eq!(&Ty::list(Box::new(Ty::Int32)), array.ty());
+    assert_eq!(&Ty::Int32, array.value_type());
     assert_eq!(100, array.len());
-    assert_eq!(0, array.null_count());
-//    assert_eq!(0, array.offset());
+    assert_eq!(15, array.null_count());
+    assert_eq!(total_values as i64, array.list_values().len());
 
     for i in 0..100 {
-      assert_eq!(expected[i], array.value(i as i64));
+      assert_eq!(i % 7 == 0, array.is_null(i as i64));
+      assert_eq!(expected[i].len() as i32, array.value_len(i as i64));
+
+      let list: Array = array.value(i as i64);
+      assert_eq!(&Ty::Int32, list.ty());
+      assert_eq!(expected[i].len() as i64, list.len());
+      let values: &[i32] = list.values();
+      assert_eq!(expected[i].as_slice(), values);
     }
   }
 
-  // TODO: test boolean with null
+  #[test]
+  fn test_list_offset_overflow() {
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    // null values don't take memory
+    let value_builder = ArrayBuilder::null(i32::MAX as i64);
+    let mut builder = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), value_builder);
+    builder.append_list().unwrap();
+    builder.value_builder_mut().append_null().unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_list().unwrap_err().code());
+    assert_eq!(1, builder.len());
+  }
 
-  macro_rules! test_primitive_type_builder {
-      ($test_name: ident, $ty: path, $prim_ty: ty, $expected_capacity: expr) => {
-        #[test]
-        fn $test_name() {
-          let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
-          let null_bitmap = PoolBuffer::new(pool.clone());
-          let data = PoolBuffer::new(pool.clone());
+  #[test]
+  fn test_nested_list_builder() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let value_builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let inner_builder = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), value_builder);
+    let mut builder = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), inner_builder);
+
+    // [[["a", "b"], []], null, [["c"]]]
+    {
+      let inner = builder.value_builder_mut();
+      inner.value_builder_mut().append("a").unwrap();
+      inner.value_builder_mut().append("b").unwrap();
+      inner.append_list().unwrap();
+      inner.append_list().unwrap();
+    }
+    builder.append_list().unwrap();
+    builder.append_null().unwrap();
+    {
+      let inner = builder.value_builder_mut();
+      inner.value_builder_mut().append("c").unwrap();
+      inner.append_list().unwrap();
+    }
+    builder.append_list().unwrap();
 
-          let mut builder = ArrayBuilder::new_fixed_width($ty, null_bitmap, data);
-          let mut expected: Vec<$prim_ty> = Vec::new();
-          for i in 0..100 {
-            let val = rand::random::<$prim_ty>();
-            builder.append(val);
-            expected.push(val);
-          }
+    let array = Array::from(builder);
+    assert_eq!(&Ty::list(Box::new(Ty::list(Box::new(Ty::String)))), array.ty());
+    assert_eq!(3, array.len());
+    assert_eq!(1, array.null_count());
+
+    let first: Array = array.value(0);
+    assert_eq!(2, first.len());
+    let first_inner: Array = first.value(0);
+    let val: &str = first_inner.value(1);
+    assert_eq!("b", val);
+    let second_inner: Array = first.value(1);
+    assert_eq!(0, second_inner.len());
+
+    let second: Array = array.value(1);
+    assert_eq!(0, second.len());
+
+    let third: Array = array.value(2);
+    assert_eq!(1, third.len());
+    let third_inner: Array = third.value(0);
+    assert_eq!(2, third_inner.offset());
+    let val: &str = third_inner.value(0);
+    assert_eq!("c", val);
+  }
 
-          assert_eq!(100, builder.len());
-          assert_eq!($expected_capacity, builder.capacity());
-          assert_eq!(0, builder.null_count());
+  #[test]
+  fn test_struct_builder() {
+    use common::field::Field;
+    use array::StructArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![
+      Field::new(String::from("f1"), Ty::Int32),
+      Field::new(String::from("f2"), Ty::String)
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()))
+    ];
+    let mut builder = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields.clone(), field_builders).unwrap();
+    assert_eq!(2, builder.num_fields());
 
-          let array = Array::from(builder);
+    for i in 0..100 {
+      if i % 4 == 0 {
+        builder.field_builder_mut(0).append_null().unwrap();
+        builder.field_builder_mut(1).append_null().unwrap();
+        builder.append_null().unwrap();
+      } else {
+        builder.field_builder_mut(0).append(i as i32).unwrap();
+        builder.field_builder_mut(1).append(format!("{}", i).as_str()).unwrap();
+        builder.append_struct().unwrap();
+      }
+    }
 
-          assert_eq!(&$ty, array.ty());
-          assert_eq!(100, array.len());
-          assert_eq!(0, array.null_count());
-//          assert_eq!(0, array.offset());
+    assert_eq!(100, builder.len());
+    assert_eq!(25, builder.null_count());
+    assert_eq!(100, builder.field_builder(1).len());
 
-          for i in 0..100 {
-            assert_eq!(expected[i], array.value(i as i64));
-          }
+    let array = Array::from(builder);
 
-          assert_eq!(expected, array.values());
-        }
-      };
+    assert_eq!(&Ty::struct_type(fields), array.ty());
+    assert_eq!(100, array.len());
+    assert_eq!(25, array.null_count());
+    assert_eq!(2, array.num_fields());
+
+    let f1 = array.field(0);
+    let f2 = array.field_by_name("f2").unwrap();
+    assert!(array.field_by_name("f3").is_none());
+    assert_eq!(&Ty::Int32, f1.ty());
+    assert_eq!(&Ty::String, f2.ty());
+
+    for i in 0..100 {
+      assert_eq!(i % 4 == 0, array.is_null(i as i64));
+      if i % 4 != 0 {
+        assert_eq!(i as i32, f1.value(i as i64));
+        let val: &str = f2.value(i as i64);
+        assert_eq!(format!("{}", i).as_str(), val);
+      }
+    }
   }
 
-  test_primitive_type_builder!(test_i8_builder, Ty::Int8, i8, 128);
-  test_primitive_type_builder!(test_u8_builder, Ty::UInt8, u8, 128);
-  test_primitive_type_builder!(test_i16_builder, Ty::Int16, i16, 128);
-  test_primitive_type_builder!(test_u16_builder, Ty::UInt16, u16, 128);
-  test_primitive_type_builder!(test_i32_builder, Ty::Int32, i32, 128);
-  test_primitive_type_builder!(test_u32_builder, Ty::UInt32, u32, 128);
-  test_primitive_type_builder!(test_i64_builder, Ty::Int64, i64, 128);
-  test_primitive_type_builder!(test_u64_builder, Ty::UInt64, u64, 128);
+  #[test]
+  fn test_struct_builder_lockstep() {
+    use common::field::Field;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![
+      Field::new(String::from("f1"), Ty::Int32),
+      Field::new(String::from("f2"), Ty::Int64)
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+    ];
+    let mut builder = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, field_builders).unwrap();
+
+    builder.field_builder_mut(0).append(1i32).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_struct().unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append_null().unwrap_err().code());
+    assert_eq!(0, builder.len());
+
+    builder.field_builder_mut(1).append(1i64).unwrap();
+    builder.append_struct().unwrap();
+    assert_eq!(1, builder.len());
+
+    builder.field_builder_mut(0).append(2i32).unwrap();
+    builder.field_builder_mut(0).append(3i32).unwrap();
+    builder.field_builder_mut(1).append(2i64).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_struct().unwrap_err().code());
+  }
 
   #[test]
-  fn test_binary_builder() {
-    use memory_pool::MemoryPool;
-    use builder::Size;
-    use array::Blob;
-    use array::ArrayIterator;
+  fn test_struct_builder_type_mismatch() {
+    use common::field::Field;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![Field::new(String::from("f1"), Ty::Int32)];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+    ];
+    let result = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields.clone(), field_builders);
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+
+    let result = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, Vec::new());
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+  }
 
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
-    let null_bitmap = PoolBuffer::new(pool.clone());
-    let data = PoolBuffer::new(pool.clone());
+  #[test]
+  fn test_sparse_union_builder() {
+    use common::field::Field;
+    use common::status::StatusCode;
+    use common::ty::UnionMode;
+    use array::UnionArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![
+      Field::new(String::from("i"), Ty::Int64),
+      Field::new(String::from("s"), Ty::String)
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()))
+    ];
+    let mut builder = ArrayBuilder::sparse_union(PoolBuffer::new(pool.clone()), fields.clone(), vec![5, 7], field_builders).unwrap();
 
-    let mut builder = ArrayBuilder::binary(null_bitmap, data);
-    let mut expected: Vec<Blob> = Vec::new();
-    let generator = pool.clone();
-    let mut next_len = 10;
     for i in 0..100 {
-      let len = next_len;
-      next_len = next_len + 2;
-      if next_len > 50 {
-        next_len = 10;
-      }
-      let p = generator.borrow_mut().allocate(len).unwrap();
-      unsafe {
-        use std::mem;
-        use libc;
-        let dst = mem::transmute::<*const u8, *mut libc::c_void>(p);
-        libc::memset(dst, 0, len as usize);
-        libc::memcpy(dst, mem::transmute::<*const i64, *const libc::c_void>(&len), mem::size_of::<i64>());
+      if i % 10 == 9 {
+        // nulls are appended to every field
+        builder.append_null().unwrap();
+      } else if i % 2 == 0 {
+        builder.field_builder_mut(0).append(i as i64).unwrap();
+        builder.field_builder_mut(1).append_null().unwrap();
+        builder.append_union(5).unwrap();
+      } else {
+        builder.field_builder_mut(0).append_null().unwrap();
+        builder.field_builder_mut(1).append(format!("{}", i).as_str()).unwrap();
+        builder.append_union(7).unwrap();
       }
-      let val = Blob::new(p, len as i32);
-      builder.append(val);
-      expected.push(val);
     }
 
+    builder.field_builder_mut(0).append(0i64).unwrap();
+    builder.field_builder_mut(1).append_null().unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_union(6).unwrap_err().code());
+    builder.field_builder_mut(0).append(0i64).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_union(5).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append_null().unwrap_err().code());
+
     assert_eq!(100, builder.len());
-    assert_eq!(128, builder.capacity());
-    assert_eq!(0, builder.null_count());
+    assert_eq!(102, builder.field_builder(0).len());
+    assert_eq!(101, builder.field_builder(1).len());
 
     let array = Array::from(builder);
 
-    assert_eq!(&Ty::Binary, array.ty());
+    assert_eq!(&Ty::union_with_mode(fields, vec![5, 7], UnionMode::SPARSE), array.ty());
     assert_eq!(100, array.len());
+    // unions don't have validity bitmaps
     assert_eq!(0, array.null_count());
-//    assert_eq!(0, array.offset());
+    assert!(array.null_bitmap_buffer().is_none());
+
+    let ints = array.union_field(0);
+    let strings = array.union_field(1);
+    for i in 0..100 {
+      if i % 10 == 9 {
+        assert_eq!(5, array.type_id(i));
+        assert!(ints.is_null(i));
+        assert!(strings.is_null(i));
+      } else if i % 2 == 0 {
+        assert_eq!(5, array.type_id(i));
+        assert_eq!((0, i), array.child_slot(i));
+        assert_eq!(i, ints.value(i));
+      } else {
+        assert_eq!(7, array.type_id(i));
+        assert_eq!((1, i), array.child_slot(i));
+        let val: &str = strings.value(i);
+        assert_eq!(format!("{}", i).as_str(), val);
+      }
+    }
+  }
+
+  #[test]
+  fn test_union_builder_nested_nulls() {
+    use common::field::Field;
+    use common::status::StatusCode;
+    use array::{StructArray, UnionArray};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let struct_builder = |pool: &Arc<DefaultMemoryPool>| {
+      let fields = vec![Field::new(String::from("x"), Ty::Int32)];
+      let field_builders = vec![ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()];
+      ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, field_builders).unwrap()
+    };
+    let point = struct_builder(&pool);
+    let fields = vec![
+      Field::new(String::from("i"), Ty::Int64),
+      Field::new(String::from("p"), point.ty().clone())
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      point
+    ];
+    let mut sparse = ArrayBuilder::sparse_union(PoolBuffer::new(pool.clone()), fields.clone(), vec![0, 1], field_builders).unwrap();
+
+    // nulls are appended to the fields of a struct field
+    sparse.append_null().unwrap();
+    sparse.field_builder_mut(0).append_null().unwrap();
+    sparse.field_builder_mut(1).field_builder_mut(0).append(3).unwrap();
+    sparse.field_builder_mut(1).append_struct().unwrap();
+    sparse.append_union(1).unwrap();
+    sparse.append_null().unwrap();
+    assert_eq!(3, sparse.len());
+    assert_eq!(3, sparse.field_builder(1).len());
+    assert_eq!(3, sparse.field_builder(1).field_builder(0).len());
+
+    // no builder is touched if a nested builder has new values
+    sparse.field_builder_mut(1).field_builder_mut(0).append(4).unwrap();
+    assert_eq!(&StatusCode::Invalid, sparse.append_null().unwrap_err().code());
+    assert_eq!(3, sparse.len());
+    assert_eq!(3, sparse.field_builder(0).len());
+    assert_eq!(3, sparse.field_builder(1).len());
+    assert_eq!(4, sparse.field_builder(1).field_builder(0).len());
+    sparse.field_builder_mut(0).append_null().unwrap();
+    sparse.field_builder_mut(1).append_struct().unwrap();
+    sparse.append_union(1).unwrap();
+
+    let array = Array::from(sparse);
+    let points = array.union_field(1);
+    assert!(points.is_null(0));
+    assert!(points.field(0).is_null(0));
+    assert_eq!(3, ArrowValue::<i32>::value(&points.field(0), 1));
+    assert!(points.is_null(2));
+    assert_eq!(4, ArrowValue::<i32>::value(&points.field(0), 3));
+
+    // the null slot of a dense union is a null of its first field
+    let fields = vec![Field::new(String::from("p"), struct_builder(&pool).ty().clone())];
+    let mut dense = ArrayBuilder::dense_union(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), fields, vec![0], vec![struct_builder(&pool)]).unwrap();
+    dense.append_null().unwrap();
+    dense.append_null().unwrap();
+    let array = Array::from(dense);
+    assert_eq!((0, 1), array.child_slot(1));
+    assert_eq!(2, array.union_field(0).null_count());
+    assert_eq!(2, array.union_field(0).field(0).null_count());
+  }
 
-    let mut iter = ArrayIterator::new(array);
+  #[test]
+  fn test_union_builder_invalid_type_codes() {
+    use common::field::Field;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![
+      Field::new(String::from("i"), Ty::Int32),
+      Field::new(String::from("d"), Ty::UInt8)
+    ];
+    let field_builders = || vec![
+      ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::new_fixed_width(Ty::UInt8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+    ];
+
+    for type_codes in vec![vec![0], vec![1, 1], vec![0, -1]] {
+      let result = ArrayBuilder::sparse_union(PoolBuffer::new(pool.clone()), fields.clone(), type_codes, field_builders());
+      assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+    }
+    let result = ArrayBuilder::dense_union(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), fields.clone(), vec![0, 1], vec![]);
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+  }
 
+  #[test]
+  fn test_dense_union_builder() {
+    use common::field::Field;
+    use common::status::StatusCode;
+    use common::ty::UnionMode;
+    use array::UnionArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let fields = vec![
+      Field::new(String::from("i"), Ty::Int32),
+      Field::new(String::from("d"), Ty::UInt8)
+    ];
+    let field_builders = vec![
+      ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::new_fixed_width(Ty::UInt8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+    ];
+    let mut builder = ArrayBuilder::dense_union(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), fields.clone(), vec![0, 1], field_builders).unwrap();
+
+    assert_eq!(&StatusCode::Invalid, builder.append_union(0).unwrap_err().code());
+
+    let mut expected: Vec<(usize, i64)> = Vec::new();
+    let mut num_ints = 0;
+    let mut num_bytes = 0;
     for i in 0..100 {
-      assert_eq!(expected[i], iter.next().unwrap());
+      if i % 3 == 0 {
+        builder.field_builder_mut(1).append(i as u8).unwrap();
+        builder.append_union(1).unwrap();
+        expected.push((1, num_bytes));
+        num_bytes = num_bytes + 1;
+      } else {
+        builder.field_builder_mut(0).append(i as i32).unwrap();
+        builder.append_union(0).unwrap();
+        expected.push((0, num_ints));
+        num_ints = num_ints + 1;
+      }
     }
+    assert_eq!(&StatusCode::Invalid, builder.append_union(2).unwrap_err().code());
 
-    assert!(iter.next().is_none());
+    // a null is appended to the first field
+    builder.append_null().unwrap();
+    expected.push((0, num_ints));
 
-    for blob in expected {
-      pool.borrow_mut().free(blob.p(), blob.len())
+    // every value must be appended to exactly one field
+    builder.field_builder_mut(0).append(0i32).unwrap();
+    builder.field_builder_mut(1).append(0u8).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append_union(0).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append_null().unwrap_err().code());
+
+    assert_eq!(101, builder.len());
+    assert_eq!(35, builder.field_builder(1).len());
+    assert_eq!(68, builder.field_builder(0).len());
+
+    let array = Array::from(builder);
+
+    assert_eq!(&Ty::union_with_mode(fields, vec![0, 1], UnionMode::DENSE), array.ty());
+    assert_eq!(101, array.len());
+    assert_eq!(0, array.null_count());
+
+    let (child_id, slot) = array.child_slot(100);
+    assert_eq!(expected[100], (child_id, slot));
+    assert!(array.union_field(child_id).is_null(slot));
+
+    for i in 0..100 {
+      let (child_id, slot) = array.child_slot(i as i64);
+      assert_eq!(expected[i], (child_id, slot));
+      if child_id == 0 {
+        assert_eq!(i as i32, array.union_field(0).value(slot));
+      } else {
+        assert_eq!(i as u8, array.union_field(1).value(slot));
+      }
+    }
+  }
+
+  #[test]
+  fn test_string_dictionary_builder() {
+    use array::DictionaryArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let index_builder = ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let value_builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+
+    let mut builder = ArrayBuilder::dictionary(index_builder, value_builder).unwrap();
+    builder.append("arrow").unwrap();
+    builder.append("iron").unwrap();
+    builder.append_null().unwrap();
+    builder.append("arrow").unwrap();
+    builder.append_utf8(b"columnar").unwrap();
+    builder.append("iron").unwrap();
+
+    assert_eq!(6, builder.len());
+    assert_eq!(1, builder.null_count());
+
+    let array = Array::from(builder);
+
+    assert_eq!("dictionary", array.ty().name());
+    assert_eq!(&Box::new(Ty::Int8), array.ty().dictionary_index_type());
+    assert_eq!(false, array.ty().is_dictionary_ordered());
+    assert_eq!(6, array.len());
+    assert_eq!(1, array.null_count());
+    assert!(array.is_null(2));
+
+    let dictionary = array.dictionary();
+    assert_eq!(&Ty::String, dictionary.ty());
+    assert_eq!(3, dictionary.len());
+    let values: Vec<&str> = (0..3).map(|i| dictionary.value(i)).collect();
+    assert_eq!(vec!["arrow", "iron", "columnar"], values);
+
+    assert_eq!(0, array.index(0));
+    assert_eq!(1, array.index(1));
+    assert_eq!(0, array.index(3));
+    assert_eq!(2, array.index(4));
+    assert_eq!(1, array.index(5));
+
+    let decoded = array.decode(pool.clone()).unwrap();
+    assert_eq!(&Ty::String, decoded.ty());
+    assert_eq!(6, decoded.len());
+    assert_eq!(1, decoded.null_count());
+    assert!(decoded.is_null(2));
+    let expected = vec!["arrow", "iron", "", "arrow", "columnar", "iron"];
+    for i in 0..6 {
+      if i != 2 {
+        assert_eq!(expected[i], ArrowValue::<&str>::value(&decoded, i as i64));
+      }
+    }
+  }
+
+  #[test]
+  fn test_ordered_int_dictionary_builder() {
+    use array::DictionaryArray;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let index_builder = ArrayBuilder::new_fixed_width(Ty::UInt16, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let value_builder = ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let mut builder = ArrayBuilder::ordered_dictionary(index_builder, value_builder).unwrap();
+    for i in 0..100 {
+      builder.append((i % 10) as i64 * 100).unwrap();
+    }
+
+    let array = Array::from(builder);
+
+    assert!(array.ty().is_dictionary_ordered());
+    assert_eq!(&Box::new(Ty::UInt16), array.ty().dictionary_index_type());
+    assert_eq!(10, array.dictionary().len());
+    assert_eq!(100, array.indices().len());
+
+    let decoded = array.decode(pool.clone()).unwrap();
+    assert_eq!(&Ty::Int64, decoded.ty());
+    assert_eq!(0, decoded.null_count());
+    for i in 0..100 {
+      assert_eq!((i % 10) as u16, ArrowValue::<u16>::value(&array.indices(), i));
+      assert_eq!((i % 10) * 100, ArrowValue::<i64>::value(&decoded, i));
+    }
+  }
+
+  #[test]
+  fn test_dictionary_builder_index_overflow() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let index_builder = ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let value_builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let mut builder = ArrayBuilder::dictionary(index_builder, value_builder).unwrap();
+    for i in 0..128 {
+      builder.append(i as i32).unwrap();
+    }
+    // existing values can still be appended
+    builder.append(127 as i32).unwrap();
+    assert!(builder.append(128 as i32).is_err());
+    assert_eq!(129, builder.len());
+  }
+
+  #[test]
+  fn test_dictionary_builder_value_of_another_type() {
+    use array::DictionaryArray;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let index_builder = || ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    // values of the same bytes as a dictionary value are rejected if their type is different
+    let value_builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut builder = ArrayBuilder::dictionary(index_builder(), value_builder).unwrap();
+    builder.append(-1i32).unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append(u32::max_value()).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append(f32::from_bits(!0)).unwrap_err().code());
+    builder.append(-1i32).unwrap();
+    assert_eq!(2, builder.len());
+
+    let value_builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let mut builder = ArrayBuilder::dictionary(index_builder(), value_builder).unwrap();
+    builder.append("abcd").unwrap();
+    assert_eq!(&StatusCode::Invalid, builder.append(i32::from_le_bytes(*b"abcd")).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, builder.append(&b"abcd"[..]).unwrap_err().code());
+    builder.append("abcd").unwrap();
+    assert_eq!(2, builder.len());
+
+    let array = Array::from(builder);
+    assert_eq!(1, array.dictionary().len());
+  }
+
+  #[test]
+  fn test_dictionary_builder_invalid_builders() {
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let int_builder = || ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let double_builder = || ArrayBuilder::new_fixed_width(Ty::Double, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+
+    let result = ArrayBuilder::dictionary(double_builder(), int_builder());
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+    let result = ArrayBuilder::dictionary(int_builder(), double_builder());
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+
+    let mut value_builder = int_builder();
+    value_builder.append(1i32).unwrap();
+    let result = ArrayBuilder::ordered_dictionary(int_builder(), value_builder);
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+  }
+
+  #[test]
+  fn test_dictionary_builder_type() {
+    use array::{StructArray, DictionaryArray};
+    use common::field::Field;
+    use common::status::StatusCode;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let string_builder = || ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let dictionary_builder = || {
+      let index_builder = ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+      ArrayBuilder::dictionary(index_builder, string_builder()).unwrap()
+    };
+
+    let builder = dictionary_builder();
+    match builder.ty() {
+      &Ty::Dictionary { ref index_type, ref dictionary, ordered } => {
+        assert_eq!(&Ty::Int8, index_type.as_ref());
+        assert_eq!(&Ty::String, dictionary.ty());
+        assert_eq!(0, dictionary.len());
+        assert!(!ordered);
+      },
+      ty => panic!("{:?} is not a dictionary type", ty)
+    }
+
+    let fields = vec![Field::new(String::from("s"), Ty::String)];
+    let result = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, vec![dictionary_builder()]);
+    assert_eq!(&StatusCode::Invalid, result.err().unwrap().code());
+
+    let fields = vec![Field::new(String::from("s"), builder.ty().clone())];
+    let mut struct_builder = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), fields, vec![dictionary_builder()]).unwrap();
+    struct_builder.field_builder_mut(0).append("a").unwrap();
+    struct_builder.append_struct().unwrap();
+    let array = Array::from(struct_builder);
+    let child = array.field(0);
+    assert_eq!(1, child.dictionary().len());
+    match array.ty() {
+      &Ty::Struct { ref fields } => assert_eq!(child.ty(), fields[0].data_type()),
+      ty => panic!("{:?} is not a struct type", ty)
     }
   }
 }
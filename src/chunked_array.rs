@@ -0,0 +1,271 @@
+use array::{Array, ArrowValue};
+use common::status::ArrowError;
+use common::ty::Ty;
+
+use std::cmp;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// A column split into arrays of the same type. Values are addressed by their global index across
+/// the chunks, and chunks are not copied when the column is sliced.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ChunkedArray {
+  ty: Ty,
+  chunks: Vec<Array>,
+  // the global index of the first value of each chunk, followed by the total length
+  offsets: Vec<i64>
+}
+
+impl ChunkedArray {
+  /// Creates a column of `chunks`, whose type is the type of the first chunk. This fails with
+  /// `StatusCode::Invalid` if there is no chunk or chunks have different types.
+  pub fn new(chunks: Vec<Array>) -> Result<ChunkedArray, ArrowError> {
+    let ty = match chunks.first() {
+      Some(chunk) => chunk.ty().clone(),
+      None => return Err(ArrowError::invalid(String::from("cannot infer the type of a chunked array without chunks")))
+    };
+    ChunkedArray::with_type(ty, chunks)
+  }
+
+  /// Creates a column of `ty`, which can have no chunks. This fails with `StatusCode::Invalid` if
+  /// the type of a chunk is different from `ty`.
+  pub fn with_type(ty: Ty, chunks: Vec<Array>) -> Result<ChunkedArray, ArrowError> {
+    let mut offsets = Vec::with_capacity(chunks.len() + 1);
+    let mut len = 0;
+    for (i, chunk) in chunks.iter().enumerate() {
+      if chunk.ty() != &ty {
+        return Err(ArrowError::invalid(format!("chunk [{}] is of type {:?}, but {:?} is expected", i, chunk.ty(), ty)));
+      }
+      offsets.push(len);
+      len += chunk.len();
+    }
+    offsets.push(len);
+
+    Ok(ChunkedArray {
+      ty,
+      chunks,
+      offsets
+    })
+  }
+
+  #[inline]
+  pub fn ty(&self) -> &Ty {
+    &self.ty
+  }
+
+  #[inline]
+  pub fn len(&self) -> i64 {
+    self.offsets[self.chunks.len()]
+  }
+
+  pub fn null_count(&self) -> i64 {
+    self.chunks.iter().map(|chunk| chunk.null_count()).sum()
+  }
+
+  #[inline]
+  pub fn num_chunks(&self) -> usize {
+    self.chunks.len()
+  }
+
+  #[inline]
+  pub fn chunk(&self, i: usize) -> &Array {
+    &self.chunks[i]
+  }
+
+  #[inline]
+  pub fn chunks(&self) -> &Vec<Array> {
+    &self.chunks
+  }
+
+  /// Returns the chunk containing the i-th value and the index of the value in the chunk.
+  pub fn locate(&self, i: i64) -> (usize, i64) {
+    if i < 0 || i >= self.len() {
+      panic!("index [{}] is out of the chunked array of length [{}]", i, self.len())
+    }
+
+    // find the last chunk starting at or before i, which skips empty chunks
+    let chunk = match self.offsets.binary_search_by(|offset| if *offset <= i { Ordering::Less } else { Ordering::Greater }) {
+      Ok(_) => unreachable!(),
+      Err(next) => next - 1
+    };
+    (chunk, i - self.offsets[chunk])
+  }
+
+  pub fn value<'a, T>(&'a self, i: i64) -> T where Array: ArrowValue<'a, T> {
+    let (chunk, j) = self.locate(i);
+    self.chunks[chunk].value(j)
+  }
+
+  pub fn is_null(&self, i: i64) -> bool {
+    let (chunk, j) = self.locate(i);
+    self.chunks[chunk].is_null(j)
+  }
+
+  pub fn is_valid(&self, i: i64) -> bool {
+    !self.is_null(i)
+  }
+
+  /// Returns a zero-copy slice of `length` values starting from `offset`. Chunks are sliced at the
+  /// boundaries of the slice, and those out of the slice are dropped.
+  pub fn slice(&self, offset: i64, length: i64) -> ChunkedArray {
+    if offset < 0 || length < 0 || offset + length > self.len() {
+      panic!("slice [{}, {}) is out of the chunked array of length [{}]", offset, offset + length, self.len())
+    }
+
+    let mut chunks = Vec::new();
+    if length > 0 {
+      let end = offset + length;
+      let (first, _) = self.locate(offset);
+      for (chunk, &start) in self.chunks[first..].iter().zip(self.offsets[first..].iter()) {
+        if start >= end {
+          break;
+        }
+        let from = cmp::max(offset, start) - start;
+        let to = cmp::min(end, start + chunk.len()) - start;
+        if from < to {
+          chunks.push(chunk.slice(from, to - from));
+        }
+      }
+    }
+
+    match ChunkedArray::with_type(self.ty.clone(), chunks) {
+      Ok(sliced) => sliced,
+      Err(_) => unreachable!()
+    }
+  }
+
+  pub fn iter<'a, T>(&'a self) -> ChunkedArrayIterator<'a, T> where Array: ArrowValue<'a, T> {
+    ChunkedArrayIterator::new(self)
+  }
+}
+
+/// Iterates the values of all chunks in order. Nulls are yielded as `None`.
+pub struct ChunkedArrayIterator<'a, T> {
+  array: &'a ChunkedArray,
+  chunk: usize,
+  next: i64,
+  phantom: PhantomData<T>
+}
+
+impl <'a, T> ChunkedArrayIterator<'a, T> {
+  pub fn new(array: &'a ChunkedArray) -> ChunkedArrayIterator<'a, T> {
+    ChunkedArrayIterator {
+      array,
+      chunk: 0,
+      next: 0,
+      phantom: PhantomData
+    }
+  }
+}
+
+impl <'a, T> Iterator for ChunkedArrayIterator<'a, T> where Array: ArrowValue<'a, T> {
+  type Item = Option<T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let array: &'a ChunkedArray = self.array;
+    while self.chunk < array.num_chunks() {
+      let chunk = array.chunk(self.chunk);
+      if self.next < chunk.len() {
+        let i = self.next;
+        self.next = self.next + 1;
+        return Some(if chunk.is_null(i) { None } else { Some(chunk.value(i)) });
+      }
+      self.chunk = self.chunk + 1;
+      self.next = 0;
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use array::{Array, ArrowSlice};
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use chunked_array::ChunkedArray;
+  use common::status::StatusCode;
+  use common::ty::Ty;
+  use memory_pool::DefaultMemoryPool;
+  use std::sync::Arc;
+
+  // values from `start` to `end`, where multiples of 7 are null
+  fn chunk(pool: &Arc<DefaultMemoryPool>, start: i32, end: i32) -> Array {
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for i in start..end {
+      if i % 7 == 0 {
+        builder.append_null().unwrap();
+      } else {
+        builder.append(i).unwrap();
+      }
+    }
+    Array::from(builder)
+  }
+
+  #[test]
+  fn test_chunked_array() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let array = ChunkedArray::new(vec![chunk(&pool, 0, 10), chunk(&pool, 10, 10), chunk(&pool, 10, 25), chunk(&pool, 25, 30)]).unwrap();
+    assert_eq!(&Ty::Int32, array.ty());
+    assert_eq!(30, array.len());
+    assert_eq!(5, array.null_count());
+    assert_eq!(4, array.num_chunks());
+
+    // empty chunks are skipped
+    assert_eq!((0, 9), array.locate(9));
+    assert_eq!((2, 0), array.locate(10));
+    assert_eq!((3, 4), array.locate(29));
+    assert_eq!(13, array.value::<i32>(13));
+    assert_eq!(25, array.value::<i32>(25));
+    assert!(array.is_null(14));
+    assert!(array.is_valid(15));
+
+    let values: Vec<Option<i32>> = array.iter().collect();
+    assert_eq!(30, values.len());
+    assert_eq!(&[Some(6), None, Some(8)], &values[6..9]);
+    assert_eq!(&[Some(13), None, Some(15)], &values[13..16]);
+  }
+
+  #[test]
+  fn test_slice_chunked_array() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let array = ChunkedArray::new(vec![chunk(&pool, 0, 10), chunk(&pool, 10, 20), chunk(&pool, 20, 30)]).unwrap();
+
+    let slice = array.slice(5, 20);
+    assert_eq!(20, slice.len());
+    assert_eq!(3, slice.num_chunks());
+    assert_eq!(5, slice.chunk(0).len());
+    assert_eq!(5, slice.chunk(2).len());
+    assert_eq!(3, slice.null_count());
+    assert_eq!(5, slice.value::<i32>(0));
+    assert_eq!(24, slice.value::<i32>(19));
+    assert_eq!(&ArrowSlice::<i32>::values(array.chunk(1))[..], ArrowSlice::<i32>::values(slice.chunk(1)));
+
+    // a slice within a chunk
+    let slice = slice.slice(6, 3);
+    assert_eq!(1, slice.num_chunks());
+    assert_eq!(vec![Some(11), Some(12), Some(13)], slice.iter::<i32>().collect::<Vec<Option<i32>>>());
+
+    let empty = array.slice(30, 0);
+    assert_eq!(0, empty.len());
+    assert_eq!(0, empty.num_chunks());
+    assert_eq!(None, empty.iter::<i32>().next());
+  }
+
+  #[test]
+  fn test_invalid_chunked_array() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    assert_eq!(&StatusCode::Invalid, ChunkedArray::new(Vec::new()).unwrap_err().code());
+    assert_eq!(0, ChunkedArray::with_type(Ty::Int32, Vec::new()).unwrap().len());
+
+    let chunks = vec![chunk(&pool, 0, 3), Array::from(ArrayBuilder::null(3))];
+    assert_eq!(&StatusCode::Invalid, ChunkedArray::new(chunks).unwrap_err().code());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_chunked_array_index_out_of_range() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let array = ChunkedArray::new(vec![chunk(&pool, 0, 3), chunk(&pool, 3, 3)]).unwrap();
+    array.locate(3);
+  }
+}
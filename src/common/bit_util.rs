@@ -2,7 +2,6 @@ use std::i64;
 use std::mem;
 use std::cmp;
 use std::ptr;
-use std::intrinsics;
 
 const ROUND_TO: i64 = 64;
 const FORCE_CARRY_ADDEND: i64 = 64 - 1;
@@ -96,7 +95,7 @@ pub fn count_set_bits(data: *const u8, bit_offset: i64, len: i64) -> i64 {
 
   // popcount as much as possible with the widest possible count
   for i in 0..fast_counts {
-    count = count + unsafe { intrinsics::ctpop(u64_data.offset(i as isize).read()) } as i64;
+    count = count + unsafe { u64_data.offset(i as isize).read() }.count_ones() as i64;
   }
 
   // Account for left over bit (in theory we could fall back to smaller
@@ -118,17 +117,17 @@ mod test {
   use memory_pool::DefaultMemoryPool;
   use buffer::{PoolBuffer, ResizableBuffer, MutableBuffer};
   use std::sync::Arc;
-  use std::cell::RefCell;
+  use std::ptr;
 
   #[test]
   fn test_set_get_bit() {
     use common::bit_util::{set_bit, get_bit};
 
-    // TODO: this test sometimes fails
-
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
+    let pool = Arc::new(DefaultMemoryPool::new());
     let mut buffer = PoolBuffer::new(pool.clone());
-    buffer.reserve(100);
+    buffer.reserve(100).unwrap();
+    // reserved memory is not initialized
+    unsafe { ptr::write_bytes(buffer.data_as_mut(), 0, 100); }
 
     let mut true_indexes: Vec<i64> = Vec::new();
     let mut i = 0;
@@ -151,7 +150,7 @@ mod test {
   fn test_clear_bit() {
     use common::bit_util::{set_bit, get_bit, clear_bit};
 
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
+    let pool = Arc::new(DefaultMemoryPool::new());
     let mut buffer = PoolBuffer::new(pool.clone());
     buffer.reserve(5);
 
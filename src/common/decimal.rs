@@ -0,0 +1,179 @@
+use common::status::ArrowError;
+
+/// The maximum precision of a 128-bit decimal.
+pub const MAX_PRECISION: i32 = 38;
+
+/// A 128-bit decimal number, which is the value type of `Ty::Decimal`. It holds only the unscaled
+/// value, and the scale is given by the type of the array.
+///
+/// The value is kept as two little-endian 64-bit words rather than an `i128`, so that decimals are
+/// aligned to 8 bytes like the buffers of the IPC format instead of the 16 bytes of an `i128`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[repr(C)]
+pub struct Decimal128 {
+  low: u64,
+  high: i64
+}
+
+impl Decimal128 {
+  #[inline]
+  pub fn new(unscaled_value: i128) -> Decimal128 {
+    Decimal128 {
+      low: unscaled_value as u64,
+      high: (unscaled_value >> 64) as i64
+    }
+  }
+
+  #[inline]
+  pub fn unscaled_value(&self) -> i128 {
+    ((self.high as i128) << 64) | self.low as i128
+  }
+
+  /// Parses a decimal string such as `-123.45` into a decimal of `scale`. The string can have
+  /// fewer fraction digits than `scale`, but more digits are allowed only if they are zeros.
+  pub fn parse(s: &str, scale: i32) -> Result<Decimal128, ArrowError> {
+    if scale < 0 || scale > MAX_PRECISION {
+      return Err(ArrowError::invalid(format!("scale [{}] is out of range [0, {}]", scale, MAX_PRECISION)));
+    }
+
+    let (negative, digits) = if s.starts_with('-') {
+      (true, &s[1..])
+    } else if s.starts_with('+') {
+      (false, &s[1..])
+    } else {
+      (false, s)
+    };
+    let (integer, fraction) = match digits.find('.') {
+      Some(pos) => (&digits[..pos], &digits[pos + 1..]),
+      None => (digits, "")
+    };
+
+    let invalid = || ArrowError::invalid(format!("[{}] is not a valid decimal", s));
+    if integer.is_empty() && fraction.is_empty() {
+      return Err(invalid());
+    }
+    if !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+      return Err(invalid());
+    }
+
+    let scale = scale as usize;
+    if fraction.len() > scale && fraction[scale..].bytes().any(|b| b != b'0') {
+      return Err(ArrowError::invalid(format!("[{}] has more fraction digits than scale [{}]", s, scale)));
+    }
+
+    let overflow = || ArrowError::invalid(format!("[{}] is out of the range of a 128-bit decimal", s));
+    let mut value: i128 = 0;
+    let fraction_digits = fraction.bytes().chain(::std::iter::repeat(b'0')).take(scale);
+    for b in integer.bytes().chain(fraction_digits) {
+      value = match value.checked_mul(10).and_then(|v| v.checked_add((b - b'0') as i128)) {
+        Some(v) => v,
+        None => return Err(overflow())
+      };
+    }
+
+    Ok(Decimal128::new(if negative { -value } else { value }))
+  }
+
+  /// Formats this decimal with `scale` fraction digits.
+  pub fn to_string_with_scale(&self, scale: i32) -> String {
+    let unscaled = self.unscaled_value().to_string();
+    let (sign, digits) = if unscaled.starts_with('-') {
+      ("-", &unscaled[1..])
+    } else {
+      ("", &unscaled[..])
+    };
+
+    if scale <= 0 {
+      let zeros: String = ::std::iter::repeat('0').take(-scale as usize).collect();
+      return format!("{}{}{}", sign, digits, if digits == "0" { "" } else { &zeros });
+    }
+
+    let scale = scale as usize;
+    let padded = if digits.len() <= scale {
+      let zeros: String = ::std::iter::repeat('0').take(scale + 1 - digits.len()).collect();
+      zeros + digits
+    } else {
+      String::from(digits)
+    };
+    let point = padded.len() - scale;
+    format!("{}{}.{}", sign, &padded[..point], &padded[point..])
+  }
+
+  /// Returns the number of digits of the unscaled value.
+  pub fn num_digits(&self) -> i32 {
+    let mut value = self.unscaled_value();
+    let mut digits = 1;
+    while value >= 10 || value <= -10 {
+      value = value / 10;
+      digits = digits + 1;
+    }
+    digits
+  }
+
+  #[inline]
+  pub fn fits_in_precision(&self, precision: i32) -> bool {
+    self.num_digits() <= precision
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use common::decimal::Decimal128;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(Decimal128::new(12345), Decimal128::parse("123.45", 2).unwrap());
+    assert_eq!(Decimal128::new(-12345), Decimal128::parse("-123.45", 2).unwrap());
+    assert_eq!(Decimal128::new(12300), Decimal128::parse("123", 2).unwrap());
+    assert_eq!(Decimal128::new(12340), Decimal128::parse("+123.4", 2).unwrap());
+    assert_eq!(Decimal128::new(50), Decimal128::parse(".5", 2).unwrap());
+    assert_eq!(Decimal128::new(12345), Decimal128::parse("123.4500", 2).unwrap());
+    assert_eq!(Decimal128::new(123), Decimal128::parse("123", 0).unwrap());
+
+    assert!(Decimal128::parse("123.456", 2).is_err());
+    assert!(Decimal128::parse("", 2).is_err());
+    assert!(Decimal128::parse("-", 2).is_err());
+    assert!(Decimal128::parse("1.2.3", 2).is_err());
+    assert!(Decimal128::parse("12a", 2).is_err());
+    assert!(Decimal128::parse("1", -1).is_err());
+    assert!(Decimal128::parse("1000000000000000000000000000000000000000", 0).is_err());
+  }
+
+  #[test]
+  fn test_to_string() {
+    assert_eq!("123.45", Decimal128::new(12345).to_string_with_scale(2));
+    assert_eq!("-123.45", Decimal128::new(-12345).to_string_with_scale(2));
+    assert_eq!("0.05", Decimal128::new(5).to_string_with_scale(2));
+    assert_eq!("-0.05", Decimal128::new(-5).to_string_with_scale(2));
+    assert_eq!("0.00", Decimal128::new(0).to_string_with_scale(2));
+    assert_eq!("123", Decimal128::new(123).to_string_with_scale(0));
+    assert_eq!("12300", Decimal128::new(123).to_string_with_scale(-2));
+    assert_eq!("-170141183460469231731687303715884105.728", Decimal128::new(i128::min_value()).to_string_with_scale(3));
+
+    let decimal = Decimal128::parse("-98765.4321", 4).unwrap();
+    assert_eq!("-98765.4321", decimal.to_string_with_scale(4));
+  }
+
+  #[test]
+  fn test_precision() {
+    assert_eq!(1, Decimal128::new(0).num_digits());
+    assert_eq!(1, Decimal128::new(-9).num_digits());
+    assert_eq!(5, Decimal128::new(12345).num_digits());
+    assert_eq!(5, Decimal128::new(-99999).num_digits());
+    assert_eq!(39, Decimal128::new(i128::min_value()).num_digits());
+
+    assert!(Decimal128::new(99999).fits_in_precision(5));
+    assert!(!Decimal128::new(100000).fits_in_precision(5));
+  }
+
+  #[test]
+  fn test_unscaled_value() {
+    use std::mem;
+
+    for &value in [0, -1, 1 << 64, -(1 << 64) - 1, i128::min_value(), i128::max_value()].iter() {
+      assert_eq!(value, Decimal128::new(value).unscaled_value());
+    }
+    assert_eq!(8, mem::align_of::<Decimal128>());
+    assert_eq!(16, mem::size_of::<Decimal128>());
+  }
+}
@@ -5,15 +5,15 @@ use array::Array;
 use std::fmt::{Debug, Formatter, Error};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct Field<'a> {
+pub struct Field {
   name: String,
-  data_type: Ty<'a>,
+  data_type: Ty,
   nullable: bool,
   metadata: Option<KeyValueMetadata>
 }
 
-impl <'a> Field<'a> {
-  pub fn new(name: String, data_type: Ty<'a>) -> Field<'a> {
+impl Field {
+  pub fn new(name: String, data_type: Ty) -> Field {
     Field {
       name,
       data_type,
@@ -22,7 +22,7 @@ impl <'a> Field<'a> {
     }
   }
 
-  pub fn non_null(name: String, data_type: Ty<'a>) -> Field<'a> {
+  pub fn non_null(name: String, data_type: Ty) -> Field {
     Field {
       name,
       data_type,
@@ -31,7 +31,7 @@ impl <'a> Field<'a> {
     }
   }
 
-  pub fn new_with_metadata(name: String, data_type: Ty<'a>, metadata: KeyValueMetadata) -> Field<'a> {
+  pub fn new_with_metadata(name: String, data_type: Ty, metadata: KeyValueMetadata) -> Field {
     Field {
       name,
       data_type,
@@ -40,7 +40,7 @@ impl <'a> Field<'a> {
     }
   }
 
-  pub fn non_null_with_metadata(name: String, data_type: Ty<'a>, metadata: KeyValueMetadata) -> Field<'a> {
+  pub fn non_null_with_metadata(name: String, data_type: Ty, metadata: KeyValueMetadata) -> Field {
     Field {
       name,
       data_type,
@@ -68,7 +68,7 @@ impl <'a> Field<'a> {
     &self.metadata
   }
 
-  pub fn with_metadata(&self, metadata: KeyValueMetadata) -> Field<'a> {
+  pub fn with_metadata(&self, metadata: KeyValueMetadata) -> Field {
     Field {
       name: self.name.clone(),
       data_type: self.data_type.clone(),
@@ -77,7 +77,16 @@ impl <'a> Field<'a> {
     }
   }
 
-  pub fn without_metadata(&self) -> Field<'a> {
+  pub fn with_data_type(&self, data_type: Ty) -> Field {
+    Field {
+      name: self.name.clone(),
+      data_type,
+      nullable: self.nullable,
+      metadata: self.metadata.clone()
+    }
+  }
+
+  pub fn without_metadata(&self) -> Field {
     Field {
       name: self.name.clone(),
       data_type: self.data_type.clone(),
@@ -85,4 +94,13 @@ impl <'a> Field<'a> {
       metadata: None
     }
   }
+
+  /// Compares fields like `==`, but ignores their metadata and that of their child fields if
+  /// `check_metadata` is false.
+  pub fn equals(&self, other: &Field, check_metadata: bool) -> bool {
+    self.name == other.name &&
+      self.data_type.equals(&other.data_type, check_metadata) &&
+      self.nullable == other.nullable &&
+      (!check_metadata || self.metadata == other.metadata)
+  }
 }
@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter, Error};
+
+/// An IEEE 754 half-precision floating point number, which is the value type of `Ty::HalfFloat`.
+/// Two values are equal only if they have the same bits.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct f16(u16);
+
+impl f16 {
+  #[inline]
+  pub fn from_bits(bits: u16) -> f16 {
+    f16(bits)
+  }
+
+  #[inline]
+  pub fn to_bits(&self) -> u16 {
+    self.0
+  }
+
+  /// Converts a single-precision number into the nearest half-precision number. Ties are rounded
+  /// to even, and values out of the half-precision range become infinity.
+  pub fn from_f32(value: f32) -> f16 {
+    let x = value.to_bits();
+    let sign = (x >> 16) & 0x8000;
+    let exp = ((x >> 23) & 0xff) as i32;
+    let man = x & 0x7fffff;
+
+    // infinity or NaN
+    if exp == 0xff {
+      let nan_bit = if man == 0 { 0 } else { 0x0200 };
+      return f16((sign | 0x7c00 | nan_bit | (man >> 13)) as u16);
+    }
+
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+      return f16((sign | 0x7c00) as u16);
+    }
+
+    if half_exp <= 0 {
+      // too small even for a subnormal number
+      if half_exp < -10 {
+        return f16(sign as u16);
+      }
+      let man = man | 0x800000;
+      let shift = (14 - half_exp) as u32;
+      let half_man = man >> shift;
+      let round_bit = 1 << (shift - 1);
+      let round_up = (man & round_bit) != 0 && (man & (3 * round_bit - 1)) != 0;
+      return f16((sign | (half_man + round_up as u32)) as u16);
+    }
+
+    let half = sign | ((half_exp as u32) << 10) | (man >> 13);
+    let round_bit = 0x1000;
+    // a carry from the mantissa correctly increments the exponent
+    let round_up = (man & round_bit) != 0 && (man & (3 * round_bit - 1)) != 0;
+    f16((half + round_up as u32) as u16)
+  }
+
+  /// Converts this number into a single-precision number. The conversion is exact.
+  pub fn to_f32(&self) -> f32 {
+    let h = self.0 as u32;
+    let sign = (h & 0x8000) << 16;
+    let exp = (h >> 10) & 0x1f;
+    let man = h & 0x3ff;
+
+    match exp {
+      0 => {
+        // zero or subnormal
+        let abs = man as f32 * (2.0f32).powi(-24);
+        if sign == 0 { abs } else { -abs }
+      },
+      0x1f => f32::from_bits(sign | 0x7f800000 | (man << 13)),
+      _ => f32::from_bits(sign | ((exp + 127 - 15) << 23) | (man << 13))
+    }
+  }
+
+  #[inline]
+  pub fn is_nan(&self) -> bool {
+    (self.0 & 0x7c00) == 0x7c00 && (self.0 & 0x3ff) != 0
+  }
+}
+
+impl From<f32> for f16 {
+  fn from(value: f32) -> f16 {
+    f16::from_f32(value)
+  }
+}
+
+impl From<f16> for f32 {
+  fn from(value: f16) -> f32 {
+    value.to_f32()
+  }
+}
+
+impl Display for f16 {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    Display::fmt(&self.to_f32(), f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use common::half::f16;
+  use std::f32;
+
+  #[test]
+  fn test_exact_conversion() {
+    let vals = vec![0.0f32, -0.0, 1.0, -1.0, 0.5, 2.0, 1024.0, 65504.0, -65504.0, 0.25];
+    for val in vals {
+      assert_eq!(val, f16::from_f32(val).to_f32());
+    }
+
+    assert_eq!(0x3c00, f16::from_f32(1.0).to_bits());
+    assert_eq!(0xc000, f16::from_f32(-2.0).to_bits());
+    assert_eq!(0x7bff, f16::from_f32(65504.0).to_bits());
+  }
+
+  #[test]
+  fn test_rounding() {
+    // 1 + 2^-11 is a tie between 1 and 1 + 2^-10, and rounds to the even one
+    assert_eq!(1.0, f16::from_f32(1.0 + (2.0f32).powi(-11)).to_f32());
+    assert_eq!(1.0 + (2.0f32).powi(-9), f16::from_f32(1.0 + 3.0 * (2.0f32).powi(-11)).to_f32());
+    assert_eq!(0x3c01, f16::from_f32(1.0 + 0.75 * (2.0f32).powi(-10)).to_bits());
+  }
+
+  #[test]
+  fn test_subnormal() {
+    let min_positive = (2.0f32).powi(-24);
+    assert_eq!(0x0001, f16::from_f32(min_positive).to_bits());
+    assert_eq!(min_positive, f16::from_bits(0x0001).to_f32());
+    assert_eq!(0x03ff, f16::from_f32(1023.0 * min_positive).to_bits());
+    assert_eq!(0, f16::from_f32(min_positive / 4.0).to_bits());
+  }
+
+  #[test]
+  fn test_special_values() {
+    assert_eq!(0x7c00, f16::from_f32(f32::INFINITY).to_bits());
+    assert_eq!(0xfc00, f16::from_f32(f32::NEG_INFINITY).to_bits());
+    assert_eq!(0x7c00, f16::from_f32(1.0e10).to_bits());
+    assert_eq!(f32::INFINITY, f16::from_bits(0x7c00).to_f32());
+    assert!(f16::from_f32(f32::NAN).is_nan());
+    assert!(f16::from_f32(f32::NAN).to_f32().is_nan());
+    assert!(!f16::from_f32(f32::INFINITY).is_nan());
+  }
+}
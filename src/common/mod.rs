@@ -2,6 +2,10 @@ pub mod status;
 pub mod ty;
 pub mod bit_util;
 pub mod field;
+pub mod schema;
+pub mod half;
+pub mod decimal;
+pub mod temporal;
 
 use std::collections::HashMap;
 
@@ -113,6 +117,75 @@ mod tests {
     assert_eq!(&Some(expected_metadata), field.metadata());
   }
 
+  #[test]
+  fn test_schema() {
+    use common::KeyValueMetadata;
+    use common::schema::Schema;
+    use common::status::StatusCode;
+
+    let mut metadata = KeyValueMetadata::new();
+    metadata.append(String::from("k1"), String::from("v1"));
+    let fields = vec![
+      Field::new(String::from("f1"), Ty::int32()),
+      Field::non_null(String::from("f2"), Ty::string()),
+      Field::new_with_metadata(String::from("f3"), Ty::double(), metadata.clone())
+    ];
+    let schema = Schema::new_with_metadata(fields.clone(), metadata.clone());
+    assert_eq!(3, schema.num_fields());
+    assert_eq!(&fields, schema.fields());
+    assert_eq!(&Some(metadata.clone()), schema.metadata());
+
+    assert_eq!(1, schema.index_of("f2").unwrap());
+    assert_eq!(&Ty::Double, schema.field_with_name("f3").unwrap().data_type());
+    assert_eq!(&StatusCode::KeyError, schema.index_of("f4").unwrap_err().code());
+    assert!(!schema.has_duplicate_names());
+
+    let projected = schema.project(&[2, 0]).unwrap();
+    assert_eq!(vec![fields[2].clone(), fields[0].clone()], *projected.fields());
+    assert_eq!(schema.metadata(), projected.metadata());
+    assert_eq!(projected, schema.project_by_names(&["f3", "f1"]).unwrap());
+    assert_eq!(&StatusCode::Invalid, schema.project(&[3]).unwrap_err().code());
+    assert_eq!(&StatusCode::KeyError, schema.project_by_names(&["f4"]).unwrap_err().code());
+
+    // metadata can be ignored in comparison
+    let stripped = Schema::new(fields.iter().map(|field| field.without_metadata()).collect());
+    assert!(schema != stripped);
+    assert!(!schema.equals(&stripped, true));
+    assert!(schema.equals(&stripped, false));
+    assert!(!schema.equals(&projected, false));
+
+    // including that of child fields
+    let nested = |metadata: Option<KeyValueMetadata>| {
+      let child = match metadata {
+        Some(metadata) => Field::new_with_metadata(String::from("c"), Ty::int32(), metadata),
+        None => Field::new(String::from("c"), Ty::int32())
+      };
+      let struct_type = Ty::struct_type(vec![child.clone()]);
+      Schema::new(vec![
+        Field::new(String::from("s"), struct_type.clone()),
+        Field::new(String::from("l"), Ty::list(Box::new(struct_type))),
+        Field::new(String::from("u"), Ty::union(vec![child], vec![0]))
+      ])
+    };
+    let with_metadata = nested(Some(metadata.clone()));
+    let without_metadata = nested(None);
+    assert!(!with_metadata.equals(&without_metadata, true));
+    assert!(with_metadata.equals(&without_metadata, false));
+    assert!(!with_metadata.equals(&Schema::new(vec![Field::new(String::from("s"), Ty::struct_type(Vec::new()))]), false));
+    let renamed = Schema::new(vec![Field::new(String::from("s"), Ty::struct_type(vec![Field::new(String::from("d"), Ty::int32())]))]);
+    assert!(!Schema::new(vec![with_metadata.field(0).clone()]).equals(&renamed, false));
+
+    let duplicated = Schema::new(vec![
+      Field::new(String::from("f1"), Ty::int32()),
+      Field::new(String::from("f2"), Ty::int32()),
+      Field::new(String::from("f1"), Ty::int64()),
+      Field::new(String::from("f1"), Ty::int8())
+    ]);
+    assert_eq!(vec!["f1"], duplicated.duplicate_names());
+    assert_eq!(&StatusCode::Invalid, duplicated.field_with_name("f1").unwrap_err().code());
+    assert_eq!(1, duplicated.index_of("f2").unwrap());
+  }
+
   #[test]
   fn test_null() {
     let ty = Ty::null();
@@ -210,9 +283,13 @@ mod tests {
     let ty = Ty::interval();
     assert_eq!(Ty::Interval { unit: IntervalUnit::YearMonth }, ty);
     assert_eq!("interval", ty.name());
+    assert_eq!(32, ty.bit_width());
+    assert_eq!(vec![BufferDesc::validity_buffer(), BufferDesc::data_buffer(32)], ty.get_buffer_layout());
+    assert_eq!(&IntervalUnit::YearMonth, ty.interval_unit());
+
+    let ty = Ty::Interval { unit: IntervalUnit::DayTime };
     assert_eq!(64, ty.bit_width());
     assert_eq!(vec![BufferDesc::validity_buffer(), BufferDesc::data_buffer(64)], ty.get_buffer_layout());
-    assert_eq!(&IntervalUnit::YearMonth, ty.interval_unit());
   }
 
   #[test]
@@ -322,10 +399,34 @@ mod tests {
     assert_eq!(vec![BufferDesc::validity_buffer(), BufferDesc::type_buffer(), BufferDesc::offset_buffer()], ty.get_buffer_layout());
   }
 
-//  #[test]
-//  fn test_dictionary() {
-//    // TODO
-//  }
+  #[test]
+  fn test_dictionary() {
+    use array::Array;
+    use builder::{ArrayBuilder, Append};
+    use memory_pool::DefaultMemoryPool;
+    use buffer::PoolBuffer;
+    use std::sync::Arc;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut builder = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    builder.append("a").unwrap();
+    builder.append("b").unwrap();
+    let dictionary = Array::from(builder);
+
+    let ty = Ty::dictionary(Box::new(Ty::int16()), Box::new(dictionary.clone()));
+    assert_eq!(Ty::Dictionary { index_type: Box::new(Ty::int16()), dictionary: Box::new(dictionary.clone()), ordered: false }, ty);
+    assert_eq!("dictionary", ty.name());
+    assert_eq!(16, ty.bit_width());
+    assert_eq!(vec![BufferDesc::validity_buffer(), BufferDesc::data_buffer(16)], ty.get_buffer_layout());
+    assert_eq!(&Box::new(Ty::int16()), ty.dictionary_index_type());
+    assert_eq!(&Box::new(dictionary.clone()), ty.get_dictionary());
+    assert_eq!(false, ty.is_dictionary_ordered());
+    assert_eq!(ty.clone(), ty);
+    assert!(format!("{:?}", ty).starts_with("Dictionary"));
+
+    let ty = Ty::ordered_dictionary(Box::new(Ty::int16()), Box::new(dictionary));
+    assert!(ty.is_dictionary_ordered());
+  }
 
   #[test]
   fn test_is_integer() {
@@ -334,7 +435,6 @@ mod tests {
     use memory_pool::DefaultMemoryPool;
     use buffer::PoolBuffer;
     use std::sync::Arc;
-    use std::cell::RefCell;
 
     assert!(Ty::uint8().is_integer());
     assert!(Ty::uint16().is_integer());
@@ -363,7 +463,7 @@ mod tests {
     assert_eq!(false, Ty::struct_type(vec![Field::new(String::from("f1"), Ty::int8())]).is_integer());
     assert_eq!(false, Ty::union(vec![Field::new(String::from("f1"), Ty::int8())], vec![0]).is_integer());
 
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
+    let pool = Arc::new(DefaultMemoryPool::new());
     let null_bitmap = PoolBuffer::new(pool);
     let builder = ArrayBuilder::null(10);
     assert_eq!(false, Ty::dictionary(Box::new(Ty::int8()), Box::new(Array::from(builder))).is_integer());
@@ -376,7 +476,6 @@ mod tests {
     use memory_pool::DefaultMemoryPool;
     use buffer::PoolBuffer;
     use std::sync::Arc;
-    use std::cell::RefCell;
 
     assert!(Ty::halffloat().is_float());
     assert!(Ty::float().is_float());
@@ -405,7 +504,7 @@ mod tests {
     assert_eq!(false, Ty::struct_type(vec![Field::new(String::from("f1"), Ty::int8())]).is_float());
     assert_eq!(false, Ty::union(vec![Field::new(String::from("f1"), Ty::int8())], vec![0]).is_float());
 
-    let pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
+    let pool = Arc::new(DefaultMemoryPool::new());
     let null_bitmap = PoolBuffer::new(pool);
     let builder = ArrayBuilder::null(10);
     assert_eq!(false, Ty::dictionary(Box::new(Ty::int8()), Box::new(Array::from(builder))).is_float());
@@ -0,0 +1,136 @@
+use common::KeyValueMetadata;
+use common::field::Field;
+use common::status::ArrowError;
+
+use std::collections::HashSet;
+
+/// A sequence of fields describing the columns of record batches and tables. Field names are
+/// usually unique, but duplicate names are allowed. Looking up fields by a duplicate name fails.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Schema {
+  fields: Vec<Field>,
+  metadata: Option<KeyValueMetadata>
+}
+
+impl Schema {
+  pub fn new(fields: Vec<Field>) -> Schema {
+    Schema {
+      fields,
+      metadata: None
+    }
+  }
+
+  pub fn new_with_metadata(fields: Vec<Field>, metadata: KeyValueMetadata) -> Schema {
+    Schema {
+      fields,
+      metadata: Some(metadata)
+    }
+  }
+
+  #[inline]
+  pub fn fields(&self) -> &Vec<Field> {
+    &self.fields
+  }
+
+  #[inline]
+  pub fn field(&self, i: usize) -> &Field {
+    &self.fields[i]
+  }
+
+  #[inline]
+  pub fn num_fields(&self) -> usize {
+    self.fields.len()
+  }
+
+  pub fn metadata(&self) -> &Option<KeyValueMetadata> {
+    &self.metadata
+  }
+
+  pub fn with_metadata(&self, metadata: KeyValueMetadata) -> Schema {
+    Schema {
+      fields: self.fields.clone(),
+      metadata: Some(metadata)
+    }
+  }
+
+  pub fn without_metadata(&self) -> Schema {
+    Schema {
+      fields: self.fields.clone(),
+      metadata: None
+    }
+  }
+
+  /// Returns the index of the field named `name`. This fails with `StatusCode::KeyError` if there
+  /// is no such field, and with `StatusCode::Invalid` if more than one field has the name.
+  pub fn index_of(&self, name: &str) -> Result<usize, ArrowError> {
+    let mut indices = self.fields.iter()
+      .enumerate()
+      .filter(|&(_, field)| field.name() == name)
+      .map(|(i, _)| i);
+    match (indices.next(), indices.next()) {
+      (Some(i), None) => Ok(i),
+      (None, _) => Err(ArrowError::key_error(format!("field [{}] is not found", name))),
+      (Some(_), Some(_)) => Err(ArrowError::invalid(format!("field name [{}] is ambiguous", name)))
+    }
+  }
+
+  pub fn field_with_name(&self, name: &str) -> Result<&Field, ArrowError> {
+    match self.index_of(name) {
+      Ok(i) => Ok(&self.fields[i]),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns the names used by more than one field in the order of their first appearance.
+  pub fn duplicate_names(&self) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    for field in &self.fields {
+      let name = field.name().as_str();
+      if !seen.insert(name) && !duplicates.contains(&name) {
+        duplicates.push(name);
+      }
+    }
+    duplicates
+  }
+
+  #[inline]
+  pub fn has_duplicate_names(&self) -> bool {
+    !self.duplicate_names().is_empty()
+  }
+
+  /// Returns a schema of the fields at `indices` in the given order. The metadata is kept.
+  pub fn project(&self, indices: &[usize]) -> Result<Schema, ArrowError> {
+    let mut fields = Vec::with_capacity(indices.len());
+    for &i in indices {
+      if i >= self.fields.len() {
+        return Err(ArrowError::invalid(format!("field index [{}] is out of [{}] fields", i, self.fields.len())));
+      }
+      fields.push(self.fields[i].clone());
+    }
+    Ok(Schema {
+      fields,
+      metadata: self.metadata.clone()
+    })
+  }
+
+  /// Returns a schema of the fields named `names` in the given order. The metadata is kept.
+  pub fn project_by_names(&self, names: &[&str]) -> Result<Schema, ArrowError> {
+    let mut indices = Vec::with_capacity(names.len());
+    for name in names {
+      match self.index_of(name) {
+        Ok(i) => indices.push(i),
+        Err(e) => return Err(e)
+      }
+    }
+    self.project(&indices)
+  }
+
+  /// Compares schemas like `==`, but ignores the metadata of schemas and their fields if
+  /// `check_metadata` is false.
+  pub fn equals(&self, other: &Schema, check_metadata: bool) -> bool {
+    self.fields.len() == other.fields.len() &&
+      self.fields.iter().zip(other.fields.iter()).all(|(f1, f2)| f1.equals(f2, check_metadata)) &&
+      (!check_metadata || self.metadata == other.metadata)
+  }
+}
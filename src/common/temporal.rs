@@ -0,0 +1,251 @@
+use common::status::ArrowError;
+use common::ty::{TimeUnit, DateUnit, IntervalUnit};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Conversions between std::time and the values of temporal arrays. Timestamps and dates are
+// relative to the UNIX epoch, and times are relative to midnight.
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const MILLIS_PER_DAY: i64 = 86_400_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[inline]
+fn nanos_per_unit(unit: &TimeUnit) -> i64 {
+  match unit {
+    &TimeUnit::Second => NANOS_PER_SECOND,
+    &TimeUnit::Milli => 1_000_000,
+    &TimeUnit::Micro => 1_000,
+    &TimeUnit::Nano => 1
+  }
+}
+
+#[inline]
+fn units_per_second(unit: &TimeUnit) -> i64 {
+  NANOS_PER_SECOND / nanos_per_unit(unit)
+}
+
+fn units_to_duration(units: u64, unit: &TimeUnit) -> Duration {
+  let per_second = units_per_second(unit) as u64;
+  Duration::new(units / per_second, ((units % per_second) as i64 * nanos_per_unit(unit)) as u32)
+}
+
+#[inline]
+fn floor_div(a: i64, b: i64) -> i64 {
+  let q = a / b;
+  if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+/// Returns the number of whole `unit`s in `duration`. This fails with `StatusCode::Invalid` if the
+/// number doesn't fit in i64.
+pub fn duration_to_time_unit(duration: &Duration, unit: &TimeUnit) -> Result<i64, ArrowError> {
+  let units = if duration.as_secs() > i64::max_value() as u64 {
+    None
+  } else {
+    (duration.as_secs() as i64).checked_mul(units_per_second(unit))
+      .and_then(|units| units.checked_add(duration.subsec_nanos() as i64 / nanos_per_unit(unit)))
+  };
+  match units {
+    Some(units) => Ok(units),
+    None => Err(ArrowError::invalid(format!("{:?} overflows [{:?}] units", duration, unit)))
+  }
+}
+
+/// Returns the duration of `value` `unit`s. `value` must not be negative.
+pub fn duration_from_time_unit(value: i64, unit: &TimeUnit) -> Result<Duration, ArrowError> {
+  if value < 0 {
+    Err(ArrowError::invalid(format!("negative value [{}] cannot be a duration", value)))
+  } else {
+    Ok(units_to_duration(value as u64, unit))
+  }
+}
+
+/// Returns the value of a timestamp array of `unit` for `time`. Times before the epoch are
+/// rounded down.
+pub fn system_time_to_timestamp(time: &SystemTime, unit: &TimeUnit) -> Result<i64, ArrowError> {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(duration) => duration_to_time_unit(&duration, unit),
+    Err(e) => {
+      let duration = e.duration();
+      match duration_to_time_unit(&duration, unit) {
+        // -units - 1 doesn't overflow because units isn't negative
+        Ok(units) if duration.subsec_nanos() as i64 % nanos_per_unit(unit) != 0 => Ok(-units - 1),
+        Ok(units) => Ok(-units),
+        Err(e) => Err(e)
+      }
+    }
+  }
+}
+
+pub fn system_time_from_timestamp(value: i64, unit: &TimeUnit) -> SystemTime {
+  if value >= 0 {
+    UNIX_EPOCH + units_to_duration(value as u64, unit)
+  } else {
+    UNIX_EPOCH - units_to_duration(value.wrapping_neg() as u64, unit)
+  }
+}
+
+/// Returns the value of a date array of `unit` for `time`. A date of `DateUnit::Day` is the
+/// number of days since the epoch, and a date of `DateUnit::Milli` is the milliseconds since the
+/// epoch. Date32 arrays hold the value as i32, see `system_time_to_date32()`.
+pub fn system_time_to_date(time: &SystemTime, unit: &DateUnit) -> Result<i64, ArrowError> {
+  match system_time_to_timestamp(time, &TimeUnit::Milli) {
+    Ok(millis) => match unit {
+      &DateUnit::Day => Ok(floor_div(millis, MILLIS_PER_DAY)),
+      &DateUnit::Milli => Ok(millis)
+    },
+    Err(e) => Err(e)
+  }
+}
+
+/// Returns the value of a Date32 array of `unit` for `time`. This fails with
+/// `StatusCode::Invalid` if the value doesn't fit in i32.
+pub fn system_time_to_date32(time: &SystemTime, unit: &DateUnit) -> Result<i32, ArrowError> {
+  match system_time_to_date(time, unit) {
+    Ok(value) if value < i32::min_value() as i64 || value > i32::max_value() as i64 => {
+      Err(ArrowError::invalid(format!("{:?} is out of the range of date32 of [{:?}] unit", time, unit)))
+    },
+    Ok(value) => Ok(value as i32),
+    Err(e) => Err(e)
+  }
+}
+
+pub fn system_time_from_date(value: i64, unit: &DateUnit) -> Result<SystemTime, ArrowError> {
+  match unit {
+    &DateUnit::Day => match value.checked_mul(MILLIS_PER_DAY) {
+      Some(millis) => Ok(system_time_from_timestamp(millis, &TimeUnit::Milli)),
+      None => Err(ArrowError::invalid(format!("date of [{}] days overflows milliseconds", value)))
+    },
+    &DateUnit::Milli => Ok(system_time_from_timestamp(value, &TimeUnit::Milli))
+  }
+}
+
+/// Returns the value of a time array of `unit` for the time elapsed since midnight. Time32 arrays
+/// hold the value as i32.
+pub fn time_of_day(since_midnight: &Duration, unit: &TimeUnit) -> Result<i64, ArrowError> {
+  if since_midnight.as_secs() >= SECONDS_PER_DAY {
+    Err(ArrowError::invalid(format!("{:?} is not a time of day", since_midnight)))
+  } else {
+    duration_to_time_unit(since_midnight, unit)
+  }
+}
+
+/// Packs the days and milliseconds of a `IntervalUnit::DayTime` interval into its 64-bit value.
+pub fn day_time_interval(days: i32, millis: i32) -> i64 {
+  (days as u32 as i64) | ((millis as i64) << 32)
+}
+
+/// Returns the days and milliseconds of a `IntervalUnit::DayTime` interval.
+pub fn day_time_interval_parts(value: i64) -> (i32, i32) {
+  (value as i32, (value >> 32) as i32)
+}
+
+/// Returns the value of an interval array of `unit` for `duration`. Durations can't be expressed in
+/// months, so `IntervalUnit::YearMonth` is not supported.
+pub fn interval_from_duration(duration: &Duration, unit: &IntervalUnit) -> Result<i64, ArrowError> {
+  match unit {
+    &IntervalUnit::DayTime => {
+      let days = duration.as_secs() / SECONDS_PER_DAY;
+      if days > i32::max_value() as u64 {
+        Err(ArrowError::invalid(format!("{:?} is too long for a day-time interval", duration)))
+      } else {
+        // the milliseconds of less than a day fit in i32
+        match duration_to_time_unit(&(*duration - Duration::from_secs(days * SECONDS_PER_DAY)), &TimeUnit::Milli) {
+          Ok(millis) => Ok(day_time_interval(days as i32, millis as i32)),
+          Err(e) => Err(e)
+        }
+      }
+    },
+    &IntervalUnit::YearMonth => Err(ArrowError::invalid(String::from("a duration cannot be a year-month interval")))
+  }
+}
+
+pub fn interval_to_duration(value: i64, unit: &IntervalUnit) -> Result<Duration, ArrowError> {
+  match unit {
+    &IntervalUnit::DayTime => {
+      let (days, millis) = day_time_interval_parts(value);
+      if days < 0 || millis < 0 {
+        Err(ArrowError::invalid(format!("negative interval of [{}] days and [{}] milliseconds cannot be a duration", days, millis)))
+      } else {
+        Ok(Duration::from_secs(days as u64 * SECONDS_PER_DAY) + units_to_duration(millis as u64, &TimeUnit::Milli))
+      }
+    },
+    &IntervalUnit::YearMonth => Err(ArrowError::invalid(String::from("a year-month interval cannot be a duration")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use common::temporal::*;
+  use common::ty::{TimeUnit, DateUnit, IntervalUnit};
+  use std::time::{Duration, UNIX_EPOCH};
+
+  #[test]
+  fn test_duration() {
+    let duration = Duration::new(3, 123_456_789);
+    assert_eq!(3, duration_to_time_unit(&duration, &TimeUnit::Second).unwrap());
+    assert_eq!(3_123, duration_to_time_unit(&duration, &TimeUnit::Milli).unwrap());
+    assert_eq!(3_123_456, duration_to_time_unit(&duration, &TimeUnit::Micro).unwrap());
+    assert_eq!(3_123_456_789, duration_to_time_unit(&duration, &TimeUnit::Nano).unwrap());
+    assert!(duration_to_time_unit(&Duration::from_secs(u64::max_value()), &TimeUnit::Second).is_err());
+    assert!(duration_to_time_unit(&Duration::from_secs(1 << 40), &TimeUnit::Nano).is_err());
+
+    assert_eq!(Duration::new(3, 123_000_000), duration_from_time_unit(3_123, &TimeUnit::Milli).unwrap());
+    assert_eq!(duration, duration_from_time_unit(3_123_456_789, &TimeUnit::Nano).unwrap());
+    assert!(duration_from_time_unit(-1, &TimeUnit::Second).is_err());
+  }
+
+  #[test]
+  fn test_timestamp() {
+    let time = UNIX_EPOCH + Duration::new(1_500_000_000, 500_000_000);
+    assert_eq!(1_500_000_000, system_time_to_timestamp(&time, &TimeUnit::Second).unwrap());
+    assert_eq!(1_500_000_000_500, system_time_to_timestamp(&time, &TimeUnit::Milli).unwrap());
+    assert_eq!(time, system_time_from_timestamp(1_500_000_000_500, &TimeUnit::Milli));
+
+    // times before the epoch are rounded down
+    let time = UNIX_EPOCH - Duration::new(1, 500_000_000);
+    assert_eq!(-2, system_time_to_timestamp(&time, &TimeUnit::Second).unwrap());
+    assert_eq!(-1_500, system_time_to_timestamp(&time, &TimeUnit::Milli).unwrap());
+    assert_eq!(time, system_time_from_timestamp(-1_500, &TimeUnit::Milli));
+  }
+
+  #[test]
+  fn test_date() {
+    let time = UNIX_EPOCH + Duration::from_secs(3 * 86_400 + 100);
+    assert_eq!(3, system_time_to_date(&time, &DateUnit::Day).unwrap());
+    assert_eq!(259_300_000, system_time_to_date(&time, &DateUnit::Milli).unwrap());
+    assert_eq!(UNIX_EPOCH + Duration::from_secs(3 * 86_400), system_time_from_date(3, &DateUnit::Day).unwrap());
+
+    let time = UNIX_EPOCH - Duration::from_secs(100);
+    assert_eq!(-1, system_time_to_date(&time, &DateUnit::Day).unwrap());
+    assert_eq!(UNIX_EPOCH - Duration::from_secs(86_400), system_time_from_date(-1, &DateUnit::Day).unwrap());
+    assert!(system_time_from_date(i64::max_value() / 1_000, &DateUnit::Day).is_err());
+
+    // date32 values are range-checked
+    let time = UNIX_EPOCH + Duration::from_secs(30 * 86_400);
+    assert_eq!(30, system_time_to_date32(&time, &DateUnit::Day).unwrap());
+    assert!(system_time_to_date32(&time, &DateUnit::Milli).is_err());
+  }
+
+  #[test]
+  fn test_time_of_day() {
+    assert_eq!(45_296, time_of_day(&Duration::from_secs(45_296), &TimeUnit::Second).unwrap());
+    assert_eq!(45_296_000_000, time_of_day(&Duration::from_secs(45_296), &TimeUnit::Micro).unwrap());
+    assert!(time_of_day(&Duration::from_secs(86_400), &TimeUnit::Second).is_err());
+  }
+
+  #[test]
+  fn test_interval() {
+    let value = day_time_interval(-3, 1_000);
+    assert_eq!((-3, 1_000), day_time_interval_parts(value));
+
+    let duration = Duration::new(2 * 86_400 + 5, 7_000_000);
+    let value = interval_from_duration(&duration, &IntervalUnit::DayTime).unwrap();
+    assert_eq!((2, 5_007), day_time_interval_parts(value));
+    assert_eq!(duration, interval_to_duration(value, &IntervalUnit::DayTime).unwrap());
+
+    assert!(interval_from_duration(&duration, &IntervalUnit::YearMonth).is_err());
+    assert!(interval_to_duration(12, &IntervalUnit::YearMonth).is_err());
+    assert!(interval_to_duration(day_time_interval(-1, 0), &IntervalUnit::DayTime).is_err());
+  }
+}
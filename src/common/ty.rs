@@ -15,7 +15,7 @@ use std::fmt::{Debug, Formatter, Error};
 /// nested type consisting of other data types, or another data type (e.g. a
 /// timestamp encoded as an int64)
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub enum Ty<'a> {
+pub enum Ty {
   // A degenerate NULL type represented as 0 bytes/bits
   NA,
 
@@ -95,25 +95,25 @@ pub enum Ty<'a> {
 
   // A list of some logical data type
   List {
-    value_type: Box<Ty<'a>>
+    value_type: Box<Ty>
   },
 
   // Struct of logical types
   Struct {
-    fields: Vec<Field<'a>>
+    fields: Vec<Field>
   },
 
   // Unions of logical types
   Union {
-    fields: Vec<Field<'a>>,
-    type_codes: Vec<u8>,
+    fields: Vec<Field>,
+    type_codes: Vec<i8>,
     mode: UnionMode
   },
 
   // Dictionary aka Category type
   Dictionary {
-    index_type: Box<Ty<'a>>,
-    dictionary: Box<Array<'a>>,
+    index_type: Box<Ty>,
+    dictionary: Box<Array>,
     ordered: bool
   }
 }
@@ -165,7 +165,7 @@ impl BufferDesc {
   pub fn type_buffer() -> BufferDesc {
     BufferDesc {
       ty: BufferType::Type,
-      bit_width: 32
+      bit_width: 8
     }
   }
 
@@ -175,183 +175,193 @@ impl BufferDesc {
       bit_width
     }
   }
+
+  #[inline]
+  pub fn buffer_type(&self) -> &BufferType {
+    &self.ty
+  }
+
+  #[inline]
+  pub fn bit_width(&self) -> i32 {
+    self.bit_width
+  }
 }
 
-impl <'a> Ty<'a> {
-  pub fn null() -> Ty<'a> {
+impl Ty {
+  pub fn null() -> Ty {
     Ty::NA
   }
 
-  pub fn bool() -> Ty<'a> {
+  pub fn bool() -> Ty {
     Ty::Bool
   }
 
-  pub fn uint8() -> Ty<'a> {
+  pub fn uint8() -> Ty {
     Ty::UInt8
   }
 
-  pub fn int8() -> Ty<'a> {
+  pub fn int8() -> Ty {
     Ty::Int8
   }
 
-  pub fn uint16() -> Ty<'a> {
+  pub fn uint16() -> Ty {
     Ty::UInt16
   }
 
-  pub fn int16() -> Ty<'a> {
+  pub fn int16() -> Ty {
     Ty::Int16
   }
 
-  pub fn uint32() -> Ty<'a> {
+  pub fn uint32() -> Ty {
     Ty::UInt32
   }
 
-  pub fn int32() -> Ty<'a> {
+  pub fn int32() -> Ty {
     Ty::Int32
   }
 
-  pub fn uint64() -> Ty<'a> {
+  pub fn uint64() -> Ty {
     Ty::UInt64
   }
 
-  pub fn int64() -> Ty<'a> {
+  pub fn int64() -> Ty {
     Ty::Int64
   }
 
-  pub fn halffloat() -> Ty<'a> {
+  pub fn halffloat() -> Ty {
     Ty::HalfFloat
   }
 
-  pub fn float() -> Ty<'a> {
+  pub fn float() -> Ty {
     Ty::Float
   }
 
-  pub fn double() -> Ty<'a> {
+  pub fn double() -> Ty {
     Ty::Double
   }
 
-  pub fn string() -> Ty<'a> {
+  pub fn string() -> Ty {
     Ty::String
   }
 
-  pub fn binary() -> Ty<'a> {
+  pub fn binary() -> Ty {
     Ty::Binary
   }
 
-  pub fn fixed_sized_binary(byte_width: i32) -> Ty<'a> {
+  pub fn fixed_sized_binary(byte_width: i32) -> Ty {
     Ty::FixedSizeBinary {
       byte_width
     }
   }
 
-  pub fn date64() -> Ty<'a> {
+  pub fn date64() -> Ty {
     Ty::Date64 {
       unit: DateUnit::Milli
     }
   }
 
-  pub fn date64_with_unit(unit: DateUnit) -> Ty<'a> {
+  pub fn date64_with_unit(unit: DateUnit) -> Ty {
     Ty::Date64 {
       unit
     }
   }
 
-  pub fn date32() -> Ty<'a> {
+  pub fn date32() -> Ty {
     Ty::Date32 {
       unit: DateUnit::Milli
     }
   }
 
-  pub fn date32_with_unit(unit: DateUnit) -> Ty<'a> {
+  pub fn date32_with_unit(unit: DateUnit) -> Ty {
     Ty::Date32 {
       unit
     }
   }
 
-  pub fn timestamp() -> Ty<'a> {
+  pub fn timestamp() -> Ty {
     Ty::Timestamp {
       unit: TimeUnit::Milli,
       timezone: String::new(),
     }
   }
 
-  pub fn timestamp_with_unit(unit: TimeUnit) -> Ty<'a> {
+  pub fn timestamp_with_unit(unit: TimeUnit) -> Ty {
     Ty::Timestamp {
       unit,
       timezone: String::new()
     }
   }
 
-  pub fn timestamp_with_timezone(timezone: String) -> Ty<'a> {
+  pub fn timestamp_with_timezone(timezone: String) -> Ty {
     Ty::Timestamp {
       unit: TimeUnit::Milli,
       timezone
     }
   }
 
-  pub fn timestamp_with_unit_and_timestamp(unit: TimeUnit, timezone: String) -> Ty<'a> {
+  pub fn timestamp_with_unit_and_timestamp(unit: TimeUnit, timezone: String) -> Ty {
     Ty::Timestamp {
       unit,
       timezone
     }
   }
 
-  pub fn time32() -> Ty<'a> {
+  pub fn time32() -> Ty {
     Ty::Time32 {
       unit: TimeUnit::Milli
     }
   }
 
-  pub fn time32_with_unit(unit: TimeUnit) -> Ty<'a> {
+  pub fn time32_with_unit(unit: TimeUnit) -> Ty {
     Ty::Time32 {
       unit
     }
   }
 
-  pub fn time64() -> Ty<'a> {
+  pub fn time64() -> Ty {
     Ty::Time64 {
       unit: TimeUnit::Milli
     }
   }
 
-  pub fn time64_with_unit(unit: TimeUnit) -> Ty<'a> {
+  pub fn time64_with_unit(unit: TimeUnit) -> Ty {
     Ty::Time64 {
       unit
     }
   }
 
-  pub fn interval() -> Ty<'a> {
+  pub fn interval() -> Ty {
     Ty::Interval {
       unit: IntervalUnit::YearMonth
     }
   }
 
-  pub fn interval_with_unit(unit: IntervalUnit) -> Ty<'a> {
+  pub fn interval_with_unit(unit: IntervalUnit) -> Ty {
     Ty::Interval {
       unit
     }
   }
 
-  pub fn decimal(precision: i32, scale: i32) -> Ty<'a> {
+  pub fn decimal(precision: i32, scale: i32) -> Ty {
     Ty::Decimal {
       precision,
       scale
     }
   }
 
-  pub fn list(value_type: Box<Ty<'a>>) -> Ty<'a> {
+  pub fn list(value_type: Box<Ty>) -> Ty {
     Ty::List {
       value_type
     }
   }
 
-  pub fn struct_type(fields: Vec<Field<'a>>) -> Ty<'a> {
+  pub fn struct_type(fields: Vec<Field>) -> Ty {
     Ty::Struct {
       fields
     }
   }
 
-  pub fn union(fields: Vec<Field<'a>>, type_codes: Vec<u8>) -> Ty<'a> {
+  pub fn union(fields: Vec<Field>, type_codes: Vec<i8>) -> Ty {
     Ty::Union {
       fields,
       type_codes,
@@ -359,7 +369,7 @@ impl <'a> Ty<'a> {
     }
   }
 
-  pub fn union_with_mode(fields: Vec<Field<'a>>, type_codes: Vec<u8>, mode: UnionMode) -> Ty<'a> {
+  pub fn union_with_mode(fields: Vec<Field>, type_codes: Vec<i8>, mode: UnionMode) -> Ty {
     Ty::Union {
       fields,
       type_codes,
@@ -367,7 +377,7 @@ impl <'a> Ty<'a> {
     }
   }
 
-  pub fn dictionary(index_type: Box<Ty<'a>>, dictionary: Box<Array<'a>>) -> Ty<'a> {
+  pub fn dictionary(index_type: Box<Ty>, dictionary: Box<Array>) -> Ty {
     if !index_type.is_integer() {
       panic!("index type [{:?}] is not an integer", index_type)
     }
@@ -379,7 +389,7 @@ impl <'a> Ty<'a> {
     }
   }
 
-  pub fn ordered_dictionary(index_type: Box<Ty<'a>>, dictionary: Box<Array<'a>>) -> Ty<'a> {
+  pub fn ordered_dictionary(index_type: Box<Ty>, dictionary: Box<Array>) -> Ty {
     if !index_type.is_integer() {
       panic!("index type [{:?}] is not an integer", index_type)
     }
@@ -413,10 +423,12 @@ impl <'a> Ty<'a> {
       &Ty::Date32 { ref unit } => 32,
       &Ty::Date64 { ref unit } => 64,
 
-      &Ty::Timestamp { ref unit, ref timezone } => 64,
-      &Ty::Time32 { ref unit } => 32,
-      &Ty::Time64 { ref unit } => 64,
-      &Ty::Interval { ref unit } => 64,
+      &Ty::Timestamp { .. } => 64,
+      &Ty::Time32 { .. } => 32,
+      &Ty::Time64 { .. } => 64,
+      // year-month intervals are months in i32, and day-time intervals are days and milliseconds
+      &Ty::Interval { unit: IntervalUnit::YearMonth } => 32,
+      &Ty::Interval { unit: IntervalUnit::DayTime } => 64,
 
       &Ty::Decimal { precision, scale } => 16 * 8,
 
@@ -452,10 +464,10 @@ impl <'a> Ty<'a> {
       &Ty::Date32 { ref unit } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 32)],
       &Ty::Date64 { ref unit } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
 
-      &Ty::Timestamp { ref unit, ref timezone } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
-      &Ty::Time32 { ref unit } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 32)],
-      &Ty::Time64 { ref unit } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
-      &Ty::Interval { ref unit } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
+      &Ty::Timestamp { .. } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
+      &Ty::Time32 { .. } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 32)],
+      &Ty::Time64 { .. } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 64)],
+      &Ty::Interval { .. } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, self.bit_width())],
 
       &Ty::Decimal { precision, scale } => vec![BufferDesc::validity_buffer(), BufferDesc::new(BufferType::Data, 16 * 8)],
 
@@ -573,6 +585,26 @@ impl <'a> Ty<'a> {
     }
   }
 
+  /// Compares types like `==`, but ignores the metadata of child fields of nested types if
+  /// `check_metadata` is false.
+  pub fn equals(&self, other: &Ty, check_metadata: bool) -> bool {
+    if check_metadata {
+      return self == other;
+    }
+
+    match (self, other) {
+      (&Ty::List { value_type: ref left }, &Ty::List { value_type: ref right }) => left.equals(right, false),
+      (&Ty::Struct { fields: ref left }, &Ty::Struct { fields: ref right }) => fields_equal(left, right),
+      (
+        &Ty::Union { fields: ref left_fields, type_codes: ref left_codes, mode: ref left_mode },
+        &Ty::Union { fields: ref right_fields, type_codes: ref right_codes, mode: ref right_mode }
+      ) => {
+        left_codes == right_codes && left_mode == right_mode && fields_equal(left_fields, right_fields)
+      },
+      _ => self == other
+    }
+  }
+
   pub fn date_unit(&self) -> &DateUnit {
     match self {
       &Ty::Date32 { ref unit } => unit,
@@ -611,14 +643,14 @@ impl <'a> Ty<'a> {
     }
   }
 
-  pub fn list_value_type(&self) -> &Box<Ty<'a>> {
+  pub fn list_value_type(&self) -> &Box<Ty> {
     match self {
       &Ty::List { ref value_type } => &value_type,
       _ => panic!("{:?} is not a list type", self)
     }
   }
 
-  pub fn union_type_codes(&self) -> &Vec<u8> {
+  pub fn union_type_codes(&self) -> &Vec<i8> {
     match self {
       &Ty::Union { ref fields, ref type_codes, ref mode } => type_codes,
       _ => panic!("{:?} is not an union type", self)
@@ -632,14 +664,14 @@ impl <'a> Ty<'a> {
     }
   }
 
-  pub fn dictionary_index_type(&self) -> &Box<Ty<'a>> {
+  pub fn dictionary_index_type(&self) -> &Box<Ty> {
     match self {
       &Ty::Dictionary { ref index_type, ref dictionary, ordered } => &index_type,
       _ => panic!("{:?} is not a dictionary type", self)
     }
   }
 
-  pub fn get_dictionary(&self) -> &Box<Array<'a>> {
+  pub fn get_dictionary(&self) -> &Box<Array> {
     match self {
       &Ty::Dictionary { ref index_type, ref dictionary, ordered } => &dictionary,
       _ => panic!("{:?} is not a dictionary type", self)
@@ -654,6 +686,10 @@ impl <'a> Ty<'a> {
   }
 }
 
+fn fields_equal(left: &Vec<Field>, right: &Vec<Field>) -> bool {
+  left.len() == right.len() && left.iter().zip(right.iter()).all(|(l, r)| l.equals(r, false))
+}
+
 //pub trait Cast {
 //  fn as_null(&self) -> &NullType {
 //    panic!("Cannot cast to null")
@@ -0,0 +1,571 @@
+//! A minimal FlatBuffers encoder and decoder for IPC metadata. Tables are written before the values
+//! they reference, so that every offset points forward as the format requires.
+
+use common::status::ArrowError;
+
+use std::str;
+
+/// A value of a table field.
+pub enum Value {
+  Bool(bool),
+  UByte(u8),
+  Short(i16),
+  Int(i32),
+  Long(i64),
+  String(String),
+  Table(Table),
+  Tables(Vec<Table>),
+  Ints(Vec<i32>),
+  // structs made of two longs, which are `FieldNode` and `Buffer`
+  LongPairs(Vec<(i64, i64)>)
+}
+
+impl Value {
+  // the size of the value in its table, which is also its alignment
+  fn inline_size(&self) -> usize {
+    match self {
+      &Value::Bool(_) | &Value::UByte(_) => 1,
+      &Value::Short(_) => 2,
+      &Value::Int(_) => 4,
+      &Value::Long(_) => 8,
+      _ => 4
+    }
+  }
+}
+
+pub struct Table {
+  fields: Vec<(usize, Value)>
+}
+
+impl Table {
+  pub fn new() -> Table {
+    Table {
+      fields: Vec::new()
+    }
+  }
+
+  /// Sets the field of `id`, which is the index of the field in its schema definition.
+  pub fn with(mut self, id: usize, value: Value) -> Table {
+    self.fields.push((id, value));
+    self
+  }
+}
+
+/// Encodes a flatbuffer whose root is `root`.
+pub fn encode(root: &Table) -> Vec<u8> {
+  let mut encoder = Encoder {
+    buf: vec![0; 4]
+  };
+  let root_pos = encoder.table(root);
+  encoder.patch_offset(0, root_pos);
+  encoder.buf
+}
+
+struct Encoder {
+  buf: Vec<u8>
+}
+
+impl Encoder {
+  fn pad_to(&mut self, align: usize) {
+    let len = self.buf.len();
+    self.buf.resize(round_up(len, align), 0);
+  }
+
+  fn put(&mut self, pos: usize, bytes: &[u8]) {
+    self.buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+  }
+
+  fn patch_offset(&mut self, pos: usize, target: usize) {
+    self.put(pos, &((target - pos) as u32).to_le_bytes());
+  }
+
+  fn table(&mut self, table: &Table) -> usize {
+    // place larger fields first to keep them aligned
+    let mut fields: Vec<&(usize, Value)> = table.fields.iter().collect();
+    fields.sort_by(|f1, f2| f2.1.inline_size().cmp(&f1.1.inline_size()));
+
+    let num_slots = table.fields.iter().map(|&(id, _)| id + 1).max().unwrap_or(0);
+    let mut slots = vec![0; num_slots];
+    // the table starts with the offset to its vtable
+    let mut table_size = 4;
+    for &&(id, ref value) in &fields {
+      table_size = round_up(table_size, value.inline_size());
+      slots[id] = table_size;
+      table_size += value.inline_size();
+    }
+
+    self.pad_to(2);
+    let vtable = self.buf.len();
+    self.buf.extend_from_slice(&((4 + 2 * num_slots) as u16).to_le_bytes());
+    self.buf.extend_from_slice(&(table_size as u16).to_le_bytes());
+    for slot in &slots {
+      self.buf.extend_from_slice(&(*slot as u16).to_le_bytes());
+    }
+
+    self.pad_to(8);
+    let start = self.buf.len();
+    self.buf.resize(start + table_size, 0);
+    self.put(start, &((start - vtable) as i32).to_le_bytes());
+    for &(id, ref value) in &table.fields {
+      let pos = start + slots[id];
+      match value {
+        &Value::Bool(v) => self.buf[pos] = v as u8,
+        &Value::UByte(v) => self.buf[pos] = v,
+        &Value::Short(v) => self.put(pos, &v.to_le_bytes()),
+        &Value::Int(v) => self.put(pos, &v.to_le_bytes()),
+        &Value::Long(v) => self.put(pos, &v.to_le_bytes()),
+        _ => {}
+      }
+    }
+
+    // referenced values follow the table
+    for &(id, ref value) in &table.fields {
+      if let Some(target) = self.referenced(value) {
+        self.patch_offset(start + slots[id], target);
+      }
+    }
+    start
+  }
+
+  fn referenced(&mut self, value: &Value) -> Option<usize> {
+    match value {
+      &Value::String(ref s) => {
+        self.pad_to(4);
+        let pos = self.buf.len();
+        self.buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+        Some(pos)
+      },
+      &Value::Table(ref table) => Some(self.table(table)),
+      &Value::Tables(ref tables) => {
+        self.pad_to(4);
+        let pos = self.buf.len();
+        self.buf.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+        self.buf.resize(pos + 4 + 4 * tables.len(), 0);
+        for (i, table) in tables.iter().enumerate() {
+          let target = self.table(table);
+          self.patch_offset(pos + 4 + 4 * i, target);
+        }
+        Some(pos)
+      },
+      &Value::Ints(ref ints) => {
+        self.pad_to(4);
+        let pos = self.buf.len();
+        self.buf.extend_from_slice(&(ints.len() as u32).to_le_bytes());
+        for v in ints {
+          self.buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Some(pos)
+      },
+      &Value::LongPairs(ref pairs) => {
+        // the structs after the length are aligned to 8 bytes
+        self.pad_to(4);
+        if self.buf.len() % 8 == 0 {
+          self.buf.extend_from_slice(&[0; 4]);
+        }
+        let pos = self.buf.len();
+        self.buf.extend_from_slice(&(pairs.len() as u32).to_le_bytes());
+        for &(v1, v2) in pairs {
+          self.buf.extend_from_slice(&v1.to_le_bytes());
+          self.buf.extend_from_slice(&v2.to_le_bytes());
+        }
+        Some(pos)
+      },
+      _ => None
+    }
+  }
+}
+
+#[inline]
+pub fn round_up(value: usize, factor: usize) -> usize {
+  (value + factor - 1) / factor * factor
+}
+
+/// A table in a flatbuffer. Reads are checked against the bounds of the buffer, so that malformed
+/// buffers fail with `StatusCode::Invalid` instead of panicking. Absent scalar fields have the
+/// given default values.
+#[derive(Clone, Copy)]
+pub struct TableRef<'a> {
+  buf: &'a [u8],
+  pos: usize,
+  vtable: usize,
+  vtable_len: usize
+}
+
+/// Returns the root table of `buf`.
+pub fn root<'a>(buf: &'a [u8]) -> Result<TableRef<'a>, ArrowError> {
+  match deref(buf, 0) {
+    Ok(pos) => TableRef::new(buf, pos),
+    Err(e) => Err(e)
+  }
+}
+
+fn malformed(message: &str) -> ArrowError {
+  ArrowError::invalid(format!("malformed flatbuffer: {}", message))
+}
+
+fn read_bytes(buf: &[u8], pos: usize, len: usize) -> Result<&[u8], ArrowError> {
+  match pos.checked_add(len) {
+    Some(end) if end <= buf.len() => Ok(&buf[pos..end]),
+    _ => Err(malformed(&format!("[{}] bytes at [{}] are out of the buffer of size [{}]", len, pos, buf.len())))
+  }
+}
+
+macro_rules! read_le {
+  ($name: ident, $ty: ty, $size: expr) => (
+    fn $name(buf: &[u8], pos: usize) -> Result<$ty, ArrowError> {
+      match read_bytes(buf, pos, $size) {
+        Ok(bytes) => {
+          let mut le = [0u8; $size];
+          le.copy_from_slice(bytes);
+          Ok(<$ty>::from_le_bytes(le))
+        },
+        Err(e) => Err(e)
+      }
+    }
+  );
+}
+
+read_le!(read_u16, u16, 2);
+read_le!(read_i16, i16, 2);
+read_le!(read_u32, u32, 4);
+read_le!(read_i32, i32, 4);
+read_le!(read_i64, i64, 8);
+
+// follows the offset at `pos`, which must point forward
+fn deref(buf: &[u8], pos: usize) -> Result<usize, ArrowError> {
+  match read_u32(buf, pos) {
+    Ok(0) => Err(malformed(&format!("offset at [{}] points to itself", pos))),
+    Ok(offset) => Ok(pos + offset as usize),
+    Err(e) => Err(e)
+  }
+}
+
+impl <'a> TableRef<'a> {
+  fn new(buf: &'a [u8], pos: usize) -> Result<TableRef<'a>, ArrowError> {
+    let vtable = match read_i32(buf, pos) {
+      Ok(offset) => pos as i64 - offset as i64,
+      Err(e) => return Err(e)
+    };
+    if vtable < 0 {
+      return Err(malformed(&format!("vtable of the table at [{}] is out of the buffer", pos)));
+    }
+    let vtable = vtable as usize;
+    match read_u16(buf, vtable) {
+      Ok(vtable_len) if vtable_len >= 4 => Ok(TableRef {
+        buf,
+        pos,
+        vtable,
+        vtable_len: vtable_len as usize
+      }),
+      Ok(vtable_len) => Err(malformed(&format!("vtable at [{}] has invalid length [{}]", vtable, vtable_len))),
+      Err(e) => Err(e)
+    }
+  }
+
+  // returns the position of the field, or None if it's absent
+  fn field(&self, id: usize) -> Result<Option<usize>, ArrowError> {
+    let slot = 4 + 2 * id;
+    if slot + 2 > self.vtable_len {
+      return Ok(None);
+    }
+    match read_u16(self.buf, self.vtable + slot) {
+      Ok(0) => Ok(None),
+      Ok(offset) => Ok(Some(self.pos + offset as usize)),
+      Err(e) => Err(e)
+    }
+  }
+
+  // returns the position of the value referenced by the field
+  fn referenced(&self, id: usize) -> Result<Option<usize>, ArrowError> {
+    match self.field(id) {
+      Ok(Some(pos)) => deref(self.buf, pos).map(Some),
+      Ok(None) => Ok(None),
+      Err(e) => Err(e)
+    }
+  }
+
+  // returns the position of the first element and the length of the vector referenced by the field
+  fn vector(&self, id: usize, element_size: usize) -> Result<Option<(usize, usize)>, ArrowError> {
+    let pos = match self.referenced(id) {
+      Ok(Some(pos)) => pos,
+      Ok(None) => return Ok(None),
+      Err(e) => return Err(e)
+    };
+    let len = match read_u32(self.buf, pos) {
+      Ok(len) => len as usize,
+      Err(e) => return Err(e)
+    };
+    match len.checked_mul(element_size) {
+      Some(size) => read_bytes(self.buf, pos + 4, size).map(|_| Some((pos + 4, len))),
+      None => Err(malformed(&format!("vector at [{}] is too long", pos)))
+    }
+  }
+
+  pub fn get_u8(&self, id: usize, default: u8) -> Result<u8, ArrowError> {
+    match self.field(id) {
+      Ok(Some(pos)) => read_bytes(self.buf, pos, 1).map(|bytes| bytes[0]),
+      Ok(None) => Ok(default),
+      Err(e) => Err(e)
+    }
+  }
+
+  pub fn get_bool(&self, id: usize, default: bool) -> Result<bool, ArrowError> {
+    self.get_u8(id, default as u8).map(|v| v != 0)
+  }
+
+  pub fn get_i16(&self, id: usize, default: i16) -> Result<i16, ArrowError> {
+    match self.field(id) {
+      Ok(Some(pos)) => read_i16(self.buf, pos),
+      Ok(None) => Ok(default),
+      Err(e) => Err(e)
+    }
+  }
+
+  pub fn get_i32(&self, id: usize, default: i32) -> Result<i32, ArrowError> {
+    match self.field(id) {
+      Ok(Some(pos)) => read_i32(self.buf, pos),
+      Ok(None) => Ok(default),
+      Err(e) => Err(e)
+    }
+  }
+
+  pub fn get_i64(&self, id: usize, default: i64) -> Result<i64, ArrowError> {
+    match self.field(id) {
+      Ok(Some(pos)) => read_i64(self.buf, pos),
+      Ok(None) => Ok(default),
+      Err(e) => Err(e)
+    }
+  }
+
+  pub fn get_str(&self, id: usize) -> Result<Option<&'a str>, ArrowError> {
+    let (pos, len) = match self.vector(id, 1) {
+      Ok(Some(vector)) => vector,
+      Ok(None) => return Ok(None),
+      Err(e) => return Err(e)
+    };
+    match str::from_utf8(&self.buf[pos..pos + len]) {
+      Ok(s) => Ok(Some(s)),
+      Err(_) => Err(malformed(&format!("string at [{}] is not valid UTF-8", pos)))
+    }
+  }
+
+  pub fn get_table(&self, id: usize) -> Result<Option<TableRef<'a>>, ArrowError> {
+    match self.referenced(id) {
+      Ok(Some(pos)) => TableRef::new(self.buf, pos).map(Some),
+      Ok(None) => Ok(None),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns the tables in the vector field, which is empty if the field is absent.
+  pub fn get_tables(&self, id: usize) -> Result<Vec<TableRef<'a>>, ArrowError> {
+    let (pos, len) = match self.vector(id, 4) {
+      Ok(Some(vector)) => vector,
+      Ok(None) => return Ok(Vec::new()),
+      Err(e) => return Err(e)
+    };
+    let mut tables = Vec::with_capacity(len);
+    for i in 0..len {
+      match deref(self.buf, pos + 4 * i).and_then(|table| TableRef::new(self.buf, table)) {
+        Ok(table) => tables.push(table),
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(tables)
+  }
+
+  pub fn get_ints(&self, id: usize) -> Result<Option<Vec<i32>>, ArrowError> {
+    match self.vector(id, 4) {
+      Ok(Some((pos, len))) => (0..len).map(|i| read_i32(self.buf, pos + 4 * i)).collect::<Result<Vec<i32>, ArrowError>>().map(Some),
+      Ok(None) => Ok(None),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns the structs of two longs in the vector field, which is empty if the field is absent.
+  pub fn get_long_pairs(&self, id: usize) -> Result<Vec<(i64, i64)>, ArrowError> {
+    let (pos, len) = match self.vector(id, 16) {
+      Ok(Some(vector)) => vector,
+      Ok(None) => return Ok(Vec::new()),
+      Err(e) => return Err(e)
+    };
+    let mut pairs = Vec::with_capacity(len);
+    for i in 0..len {
+      let element = pos + 16 * i;
+      match (read_i64(self.buf, element), read_i64(self.buf, element + 8)) {
+        (Ok(v1), Ok(v2)) => pairs.push((v1, v2)),
+        (Err(e), _) | (_, Err(e)) => return Err(e)
+      }
+    }
+    Ok(pairs)
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use ipc::flatbuf::{encode, Table, Value};
+
+  fn read(buf: &[u8], pos: usize, len: usize) -> i64 {
+    let mut value = 0i64;
+    for i in (0..len).rev() {
+      value = (value << 8) | buf[pos + i] as i64;
+    }
+    value
+  }
+
+  pub fn read_u8(buf: &[u8], pos: usize) -> u8 {
+    buf[pos]
+  }
+
+  pub fn read_i16(buf: &[u8], pos: usize) -> i16 {
+    read(buf, pos, 2) as i16
+  }
+
+  pub fn read_i32(buf: &[u8], pos: usize) -> i32 {
+    read(buf, pos, 4) as i32
+  }
+
+  pub fn read_i64(buf: &[u8], pos: usize) -> i64 {
+    read(buf, pos, 8)
+  }
+
+  /// Returns the position of the value referenced by the offset at `pos`.
+  pub fn deref(buf: &[u8], pos: usize) -> usize {
+    pos + read(buf, pos, 4) as usize
+  }
+
+  /// Returns the position of the field `id` of the table at `table`.
+  pub fn field(buf: &[u8], table: usize, id: usize) -> Option<usize> {
+    let vtable = (table as i64 - read_i32(buf, table) as i64) as usize;
+    if 4 + 2 * id >= read(buf, vtable, 2) as usize {
+      return None;
+    }
+    match read(buf, vtable + 4 + 2 * id, 2) {
+      0 => None,
+      offset => Some(table + offset as usize)
+    }
+  }
+
+  pub fn read_string(buf: &[u8], pos: usize) -> String {
+    let len = read_i32(buf, pos) as usize;
+    assert_eq!(0, buf[pos + 4 + len]);
+    String::from_utf8(buf[pos + 4..pos + 4 + len].to_vec()).unwrap()
+  }
+
+  #[test]
+  fn test_encode() {
+    let root = Table::new()
+      .with(0, Value::Short(4))
+      .with(1, Value::UByte(3))
+      .with(3, Value::Long(-1))
+      .with(4, Value::String(String::from("iron")))
+      .with(5, Value::Tables(vec![Table::new().with(0, Value::Bool(true)), Table::new()]))
+      .with(6, Value::Ints(vec![1, -2]))
+      .with(7, Value::LongPairs(vec![(10, 20), (30, 40)]))
+      .with(8, Value::Table(Table::new().with(1, Value::Int(7))));
+    let buf = encode(&root);
+
+    let table = deref(&buf, 0);
+    assert_eq!(0, table % 8);
+    assert_eq!(4, read_i16(&buf, field(&buf, table, 0).unwrap()));
+    assert_eq!(3, read_u8(&buf, field(&buf, table, 1).unwrap()));
+    assert_eq!(None, field(&buf, table, 2));
+    assert_eq!(None, field(&buf, table, 9));
+
+    let long = field(&buf, table, 3).unwrap();
+    assert_eq!(0, long % 8);
+    assert_eq!(-1, read_i64(&buf, long));
+    assert_eq!("iron", read_string(&buf, deref(&buf, field(&buf, table, 4).unwrap())));
+
+    let tables = deref(&buf, field(&buf, table, 5).unwrap());
+    assert_eq!(2, read_i32(&buf, tables));
+    let first = deref(&buf, tables + 4);
+    assert_eq!(1, read_u8(&buf, field(&buf, first, 0).unwrap()));
+    let second = deref(&buf, tables + 8);
+    assert_eq!(None, field(&buf, second, 0));
+
+    let ints = deref(&buf, field(&buf, table, 6).unwrap());
+    assert_eq!(2, read_i32(&buf, ints));
+    assert_eq!(-2, read_i32(&buf, ints + 8));
+
+    // structs are aligned to 8 bytes
+    let pairs = deref(&buf, field(&buf, table, 7).unwrap());
+    assert_eq!(2, read_i32(&buf, pairs));
+    assert_eq!(0, (pairs + 4) % 8);
+    assert_eq!(30, read_i64(&buf, pairs + 4 + 16));
+    assert_eq!(40, read_i64(&buf, pairs + 4 + 24));
+
+    let nested = deref(&buf, field(&buf, table, 8).unwrap());
+    assert_eq!(7, read_i32(&buf, field(&buf, nested, 1).unwrap()));
+  }
+
+  #[test]
+  fn test_decode() {
+    use ipc::flatbuf::root;
+
+    let encoded = encode(&Table::new()
+      .with(0, Value::Short(4))
+      .with(1, Value::Bool(true))
+      .with(3, Value::Long(-1))
+      .with(4, Value::String(String::from("iron")))
+      .with(5, Value::Tables(vec![Table::new().with(0, Value::Int(1)), Table::new()]))
+      .with(6, Value::Ints(vec![1, -2]))
+      .with(7, Value::LongPairs(vec![(10, 20), (30, 40)]))
+      .with(8, Value::Table(Table::new().with(1, Value::UByte(7)))));
+
+    let table = root(&encoded).unwrap();
+    assert_eq!(4, table.get_i16(0, 0).unwrap());
+    assert_eq!(true, table.get_bool(1, false).unwrap());
+    assert_eq!(9, table.get_i32(2, 9).unwrap());
+    assert_eq!(-1, table.get_i64(3, 0).unwrap());
+    assert_eq!(Some("iron"), table.get_str(4).unwrap());
+    assert_eq!(None, table.get_str(10).unwrap());
+
+    let tables = table.get_tables(5).unwrap();
+    assert_eq!(2, tables.len());
+    assert_eq!(1, tables[0].get_i32(0, 0).unwrap());
+    assert_eq!(0, tables[1].get_i32(0, 0).unwrap());
+    assert!(table.get_tables(10).unwrap().is_empty());
+
+    assert_eq!(Some(vec![1, -2]), table.get_ints(6).unwrap());
+    assert_eq!(vec![(10, 20), (30, 40)], table.get_long_pairs(7).unwrap());
+    assert_eq!(7, table.get_table(8).unwrap().unwrap().get_u8(1, 0).unwrap());
+    assert!(table.get_table(9).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_decode_malformed() {
+    use common::status::StatusCode;
+    use ipc::flatbuf::root;
+
+    let encoded = encode(&Table::new()
+      .with(0, Value::String(String::from("iron")))
+      .with(1, Value::LongPairs(vec![(10, 20)])));
+    let table = root(&encoded).unwrap();
+
+    // truncated buffers
+    for len in 0..encoded.len() {
+      let truncated = &encoded[..len];
+      let result = root(truncated).and_then(|table| {
+        table.get_str(0).and_then(|_| table.get_long_pairs(1))
+      });
+      assert!(result.is_err(), "truncated at [{}]", len);
+    }
+
+    // a vector longer than the buffer
+    let mut corrupted = encoded.clone();
+    let pos = table.referenced(1).unwrap().unwrap();
+    corrupted[pos + 3] = 0x7f;
+    let e = root(&corrupted).unwrap().get_long_pairs(1).unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+
+    // an offset pointing to itself
+    let mut corrupted = encoded.clone();
+    for i in 0..4 {
+      corrupted[i] = 0;
+    }
+    assert_eq!(&StatusCode::Invalid, root(&corrupted).err().unwrap().code());
+  }
+}
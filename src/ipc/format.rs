@@ -0,0 +1,144 @@
+//! Constants of the IPC format, which are defined by `Schema.fbs` and `Message.fbs` of the Arrow
+//! specification. Field ids are the positions of fields in their tables.
+
+pub const CONTINUATION_MARKER: u32 = 0xFFFFFFFF;
+
+// MetadataVersion
+pub const METADATA_VERSION_V5: i16 = 4;
+
+// MessageHeader
+pub const HEADER_SCHEMA: u8 = 1;
+pub const HEADER_DICTIONARY_BATCH: u8 = 2;
+pub const HEADER_RECORD_BATCH: u8 = 3;
+
+// Type
+pub const TYPE_NULL: u8 = 1;
+pub const TYPE_INT: u8 = 2;
+pub const TYPE_FLOATING_POINT: u8 = 3;
+pub const TYPE_BINARY: u8 = 4;
+pub const TYPE_UTF8: u8 = 5;
+pub const TYPE_BOOL: u8 = 6;
+pub const TYPE_DECIMAL: u8 = 7;
+pub const TYPE_DATE: u8 = 8;
+pub const TYPE_TIME: u8 = 9;
+pub const TYPE_TIMESTAMP: u8 = 10;
+pub const TYPE_INTERVAL: u8 = 11;
+pub const TYPE_LIST: u8 = 12;
+pub const TYPE_STRUCT: u8 = 13;
+pub const TYPE_UNION: u8 = 14;
+pub const TYPE_FIXED_SIZE_BINARY: u8 = 15;
+
+// Precision
+pub const PRECISION_HALF: i16 = 0;
+pub const PRECISION_SINGLE: i16 = 1;
+pub const PRECISION_DOUBLE: i16 = 2;
+
+// DateUnit
+pub const DATE_UNIT_DAY: i16 = 0;
+pub const DATE_UNIT_MILLISECOND: i16 = 1;
+
+// TimeUnit
+pub const TIME_UNIT_SECOND: i16 = 0;
+pub const TIME_UNIT_MILLISECOND: i16 = 1;
+pub const TIME_UNIT_MICROSECOND: i16 = 2;
+pub const TIME_UNIT_NANOSECOND: i16 = 3;
+
+// IntervalUnit
+pub const INTERVAL_UNIT_YEAR_MONTH: i16 = 0;
+pub const INTERVAL_UNIT_DAY_TIME: i16 = 1;
+
+// UnionMode
+pub const UNION_MODE_SPARSE: i16 = 0;
+pub const UNION_MODE_DENSE: i16 = 1;
+
+// Endianness
+pub const ENDIANNESS_LITTLE: i16 = 0;
+
+pub mod message {
+  pub const VERSION: usize = 0;
+  pub const HEADER_TYPE: usize = 1;
+  pub const HEADER: usize = 2;
+  pub const BODY_LENGTH: usize = 3;
+}
+
+pub mod schema {
+  pub const ENDIANNESS: usize = 0;
+  pub const FIELDS: usize = 1;
+  pub const CUSTOM_METADATA: usize = 2;
+}
+
+pub mod field {
+  pub const NAME: usize = 0;
+  pub const NULLABLE: usize = 1;
+  pub const TYPE_TYPE: usize = 2;
+  pub const TYPE: usize = 3;
+  pub const DICTIONARY: usize = 4;
+  pub const CHILDREN: usize = 5;
+  pub const CUSTOM_METADATA: usize = 6;
+}
+
+pub mod key_value {
+  pub const KEY: usize = 0;
+  pub const VALUE: usize = 1;
+}
+
+pub mod dictionary_encoding {
+  pub const ID: usize = 0;
+  pub const INDEX_TYPE: usize = 1;
+  pub const IS_ORDERED: usize = 2;
+}
+
+pub mod int {
+  pub const BIT_WIDTH: usize = 0;
+  pub const IS_SIGNED: usize = 1;
+}
+
+pub mod floating_point {
+  pub const PRECISION: usize = 0;
+}
+
+pub mod decimal {
+  pub const PRECISION: usize = 0;
+  pub const SCALE: usize = 1;
+  pub const BIT_WIDTH: usize = 2;
+}
+
+pub mod date {
+  pub const UNIT: usize = 0;
+}
+
+pub mod time {
+  pub const UNIT: usize = 0;
+  pub const BIT_WIDTH: usize = 1;
+}
+
+pub mod timestamp {
+  pub const UNIT: usize = 0;
+  pub const TIMEZONE: usize = 1;
+}
+
+pub mod interval {
+  pub const UNIT: usize = 0;
+}
+
+pub mod fixed_size_binary {
+  pub const BYTE_WIDTH: usize = 0;
+}
+
+pub mod union {
+  pub const MODE: usize = 0;
+  pub const TYPE_IDS: usize = 1;
+}
+
+pub mod record_batch {
+  pub const LENGTH: usize = 0;
+  pub const NODES: usize = 1;
+  pub const BUFFERS: usize = 2;
+  pub const COMPRESSION: usize = 3;
+}
+
+pub mod dictionary_batch {
+  pub const ID: usize = 0;
+  pub const DATA: usize = 1;
+  pub const IS_DELTA: usize = 2;
+}
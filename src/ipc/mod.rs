@@ -0,0 +1,6 @@
+//! Serialization of record batches in the Arrow IPC format.
+
+mod flatbuf;
+mod format;
+pub mod reader;
+pub mod writer;
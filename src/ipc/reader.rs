@@ -0,0 +1,1201 @@
+use array::{Array, ArrayData};
+use buffer::{Buffer, MutableBuffer, ResizableBuffer, PoolBuffer, SharedBuffer};
+use common::KeyValueMetadata;
+use common::bit_util;
+use common::field::Field;
+use common::schema::Schema;
+use common::status::ArrowError;
+use common::ty::{Ty, DateUnit, TimeUnit, IntervalUnit, UnionMode};
+use ipc::flatbuf::{self, TableRef};
+use ipc::format::*;
+use memory_pool::MemoryPool;
+use record_batch::RecordBatch;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read};
+use std::slice;
+use std::sync::Arc;
+
+// fields nested deeper than this are rejected, so that malformed schemas can't overflow the stack
+const MAX_NESTING_DEPTH: usize = 64;
+
+// the size of the first chunk of a message body
+const MIN_BODY_CHUNK: i64 = 1 << 16;
+
+/// Reads record batches in the Arrow IPC streaming format. The schema and the dictionaries of
+/// dictionary-encoded fields are read when the reader is created, and record batches are read
+/// until the end-of-stream marker or the end of the stream.
+///
+/// Message bodies are read into buffers allocated by the memory pool, and the buffers of arrays
+/// are slices of the bodies. Streams held in memory, such as mapped files, can be read by
+/// `from_buffer` without copying their bodies. Truncated streams fail with `StatusCode::IOError`, and malformed
+/// messages fail with `StatusCode::Invalid` once they are found, after which the reader doesn't
+/// return more batches.
+///
+/// Only metadata in version V5 is supported, and dictionaries can't be replaced or extended by
+/// delta dictionaries.
+pub struct StreamReader<R: Read> {
+  source: Source<R>,
+  schema: Arc<Schema>,
+  finished: bool
+}
+
+enum Source<R> {
+  Reader { reader: R, pool: Arc<MemoryPool> },
+  // bodies are slices of the buffer, and the position is the offset of the next message
+  Buffer { buffer: SharedBuffer, position: i64 }
+}
+
+struct Message {
+  header_type: u8,
+  metadata: Vec<u8>,
+  body: SharedBuffer
+}
+
+impl Message {
+  fn header<'a>(&'a self) -> Result<TableRef<'a>, ArrowError> {
+    match flatbuf::root(&self.metadata).and_then(|message| message.get_table(message::HEADER)) {
+      Ok(Some(header)) => Ok(header),
+      Ok(None) => Err(ArrowError::invalid(String::from("message doesn't have a header"))),
+      Err(e) => Err(e)
+    }
+  }
+}
+
+impl <R: Read> StreamReader<R> {
+  /// Creates a reader of `reader`, and reads the schema and dictionaries. Array buffers are
+  /// allocated by `pool`.
+  pub fn new(reader: R, pool: Arc<MemoryPool>) -> Result<StreamReader<R>, ArrowError> {
+    StreamReader::open(Source::Reader { reader, pool })
+  }
+
+  fn open(source: Source<R>) -> Result<StreamReader<R>, ArrowError> {
+    let mut stream = StreamReader {
+      source,
+      schema: Arc::new(Schema::new(Vec::new())),
+      finished: false
+    };
+
+    let message = match stream.read_message() {
+      Ok(Some(message)) => message,
+      Ok(None) => return Err(ArrowError::invalid(String::from("stream doesn't have a schema"))),
+      Err(e) => return Err(e)
+    };
+    if message.header_type != HEADER_SCHEMA {
+      return Err(ArrowError::invalid(format!("stream starts with a message of type [{}] instead of a schema", message.header_type)));
+    }
+    let (fields, metadata) = match message.header().and_then(|header| decode_schema(&header)) {
+      Ok(schema) => schema,
+      Err(e) => return Err(e)
+    };
+
+    let mut encoded_fields = HashMap::new();
+    for field in &fields {
+      field.collect_dictionaries(&mut encoded_fields);
+    }
+    let mut dictionaries = HashMap::new();
+    // nested dictionaries are usually written first, so missing dictionaries are read in the order
+    // of their ids
+    while let Some(id) = encoded_fields.keys().filter(|id| !dictionaries.contains_key(*id)).min().cloned() {
+      let message = match stream.read_message() {
+        Ok(Some(message)) => message,
+        Ok(None) => return Err(ArrowError::invalid(format!("stream ends before dictionary [{}]", id))),
+        Err(e) => return Err(e)
+      };
+      if message.header_type != HEADER_DICTIONARY_BATCH {
+        return Err(ArrowError::invalid(format!("message of type [{}] comes before dictionary [{}]", message.header_type, id)));
+      }
+      match decode_dictionary_batch(&message, &encoded_fields, &mut dictionaries) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+
+    let mut schema_fields = Vec::with_capacity(fields.len());
+    for field in &fields {
+      match field.to_field(&dictionaries) {
+        Ok(field) => schema_fields.push(field),
+        Err(e) => return Err(e)
+      }
+    }
+    stream.schema = Arc::new(match metadata {
+      Some(metadata) => Schema::new_with_metadata(schema_fields, metadata),
+      None => Schema::new(schema_fields)
+    });
+    Ok(stream)
+  }
+
+  #[inline]
+  pub fn schema(&self) -> &Arc<Schema> {
+    &self.schema
+  }
+
+  /// Reads the next record batch, or returns `None` at the end of the stream.
+  pub fn next_batch(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+    if self.finished {
+      return Ok(None);
+    }
+    let result = self.read_batch();
+    match result {
+      Ok(Some(_)) => {},
+      // the rest of the stream can't be read after an error
+      _ => self.finished = true
+    }
+    result
+  }
+
+  fn read_batch(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+    let message = match self.read_message() {
+      Ok(Some(message)) => message,
+      Ok(None) => return Ok(None),
+      Err(e) => return Err(e)
+    };
+    match message.header_type {
+      HEADER_RECORD_BATCH => {},
+      HEADER_DICTIONARY_BATCH => return Err(ArrowError::not_implemented(String::from("dictionaries cannot be replaced after record batches"))),
+      header_type => return Err(ArrowError::invalid(format!("message of type [{}] is not a record batch", header_type)))
+    }
+
+    let types: Vec<&Ty> = self.schema.fields().iter().map(|field| field.data_type()).collect();
+    let columns = match message.header().and_then(|header| decode_record_batch(&header, &message.body, &types)) {
+      Ok(columns) => columns,
+      Err(e) => return Err(e)
+    };
+    RecordBatch::new(self.schema.clone(), columns).map(Some)
+  }
+
+  // reads the next message, or returns None at the end of the stream
+  fn read_message(&mut self) -> Result<Option<Message>, ArrowError> {
+    match self.source {
+      Source::Reader { ref mut reader, ref pool } => read_message(reader, |reader, len| read_body(reader, pool, len)),
+      Source::Buffer { ref buffer, ref mut position } => {
+        let size = buffer.size();
+        let mut remaining: &[u8] = if *position < size {
+          unsafe { slice::from_raw_parts(buffer.data().offset(*position as isize), (size - *position) as usize) }
+        } else {
+          &[]
+        };
+        let result = read_message(&mut remaining, |remaining, len| {
+          if (remaining.len() as i64) < len {
+            return Err(truncated("message body", len, remaining.len() as i64));
+          }
+          let body = buffer.slice(size - remaining.len() as i64, len);
+          *remaining = &remaining[len as usize..];
+          Ok(body)
+        });
+        *position = size - remaining.len() as i64;
+        result
+      }
+    }
+  }
+}
+
+impl StreamReader<io::Empty> {
+  /// Creates a reader of a stream held in `buffer`, and reads the schema and dictionaries. The
+  /// buffers of arrays are slices of `buffer`, so a stream in a file wrapped into `MmapBuffer` is
+  /// read without copying it into memory.
+  pub fn from_buffer(buffer: SharedBuffer) -> Result<StreamReader<io::Empty>, ArrowError> {
+    StreamReader::open(Source::Buffer { buffer, position: 0 })
+  }
+}
+
+impl <R: Read> Iterator for StreamReader<R> {
+  type Item = Result<RecordBatch, ArrowError>;
+
+  fn next(&mut self) -> Option<Result<RecordBatch, ArrowError>> {
+    match self.next_batch() {
+      Ok(Some(batch)) => Some(Ok(batch)),
+      Ok(None) => None,
+      Err(e) => Some(Err(e))
+    }
+  }
+}
+
+// reads the next message, whose body is read by `read_body`, or returns None at the end of the
+// stream
+fn read_message<R, F>(reader: &mut R, read_body: F) -> Result<Option<Message>, ArrowError>
+  where R: Read, F: FnOnce(&mut R, i64) -> Result<SharedBuffer, ArrowError> {
+  let mut prefix = [0u8; 4];
+  match read_up_to(reader, &mut prefix) {
+    // streams may end without the end-of-stream marker
+    Ok(0) => return Ok(None),
+    Ok(4) => {},
+    Ok(read) => return Err(truncated("message length", 4, read as i64)),
+    Err(e) => return Err(e)
+  }
+  // the length isn't prefixed by the continuation marker in streams of old versions
+  if u32::from_le_bytes(prefix) == CONTINUATION_MARKER {
+    match read_exactly(reader, &mut prefix, "message length") {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+  }
+  let len = i32::from_le_bytes(prefix);
+  if len == 0 {
+    return Ok(None);
+  } else if len < 0 {
+    return Err(ArrowError::invalid(format!("message has negative length [{}]", len)));
+  }
+
+  let mut metadata = Vec::new();
+  match reader.by_ref().take(len as u64).read_to_end(&mut metadata) {
+    Ok(read) if read == len as usize => {},
+    Ok(read) => return Err(truncated("message metadata", len as i64, read as i64)),
+    Err(e) => return Err(ArrowError::io_error(format!("cannot read the stream: {}", e)))
+  }
+
+  let (header_type, body_len) = match decode_message(&metadata) {
+    Ok(message) => message,
+    Err(e) => return Err(e)
+  };
+  let body = match read_body(reader, body_len) {
+    Ok(body) => body,
+    Err(e) => return Err(e)
+  };
+  // buffers in the body are aligned to 8 bytes, which is required to read their values
+  if body_len > 0 && body.data() as usize % 8 != 0 {
+    return Err(ArrowError::invalid(String::from("message body is not aligned to 8 bytes")));
+  }
+  Ok(Some(Message {
+    header_type,
+    metadata,
+    body
+  }))
+}
+
+fn read_body<R: Read>(reader: &mut R, pool: &Arc<MemoryPool>, len: i64) -> Result<SharedBuffer, ArrowError> {
+  let mut body = PoolBuffer::new(pool.clone());
+  // the body grows as it's read, so that a malformed length doesn't allocate more memory than
+  // twice the size of the stream
+  let mut read = 0;
+  while read < len {
+    let chunk = cmp::min(len - read, cmp::max(read, MIN_BODY_CHUNK));
+    match body.resize(read + chunk) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    let bytes = unsafe { slice::from_raw_parts_mut(body.data_as_mut().offset(read as isize), chunk as usize) };
+    match read_up_to(reader, bytes) {
+      Ok(n) if n as i64 == chunk => read += chunk,
+      Ok(n) => return Err(truncated("message body", len, read + n as i64)),
+      Err(e) => return Err(e)
+    }
+  }
+  Ok(SharedBuffer::new(body))
+}
+
+// reads as many bytes as possible into `buf`, and returns the number of bytes read
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, ArrowError> {
+  let mut read = 0;
+  while read < buf.len() {
+    match reader.read(&mut buf[read..]) {
+      Ok(0) => break,
+      Ok(n) => read += n,
+      Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
+      Err(e) => return Err(ArrowError::io_error(format!("cannot read the stream: {}", e)))
+    }
+  }
+  Ok(read)
+}
+
+fn read_exactly<R: Read>(reader: &mut R, buf: &mut [u8], what: &str) -> Result<(), ArrowError> {
+  match read_up_to(reader, buf) {
+    Ok(read) if read == buf.len() => Ok(()),
+    Ok(read) => Err(truncated(what, buf.len() as i64, read as i64)),
+    Err(e) => Err(e)
+  }
+}
+
+fn truncated(what: &str, expected: i64, read: i64) -> ArrowError {
+  ArrowError::io_error(format!("stream is truncated: read [{}] of [{}] bytes of the {}", read, expected, what))
+}
+
+// returns the header type and the body length of a message
+fn decode_message(metadata: &[u8]) -> Result<(u8, i64), ArrowError> {
+  let message = match flatbuf::root(metadata) {
+    Ok(message) => message,
+    Err(e) => return Err(e)
+  };
+  match message.get_i16(message::VERSION, 0) {
+    Ok(METADATA_VERSION_V5) => {},
+    Ok(version) => return Err(ArrowError::not_implemented(format!("metadata version [{}] is not supported", version))),
+    Err(e) => return Err(e)
+  }
+  let header_type = match message.get_u8(message::HEADER_TYPE, 0) {
+    Ok(header_type) => header_type,
+    Err(e) => return Err(e)
+  };
+  match message.get_i64(message::BODY_LENGTH, 0) {
+    Ok(body_len) if body_len >= 0 => Ok((header_type, body_len)),
+    Ok(body_len) => Err(ArrowError::invalid(format!("message body has negative length [{}]", body_len))),
+    Err(e) => Err(e)
+  }
+}
+
+/// A field of the schema message. Dictionary-encoded fields get their types after their
+/// dictionaries are read.
+#[derive(Clone)]
+struct FieldDesc {
+  name: String,
+  nullable: bool,
+  // the type of the values, where the children of nested types are in `children`
+  ty: Ty,
+  children: Vec<FieldDesc>,
+  // the id, the index type, and the orderedness of the dictionary
+  dictionary: Option<(i64, Ty, bool)>,
+  metadata: Option<KeyValueMetadata>
+}
+
+impl FieldDesc {
+  fn collect_dictionaries(&self, encoded_fields: &mut HashMap<i64, FieldDesc>) {
+    if let Some((id, _, _)) = self.dictionary {
+      encoded_fields.entry(id).or_insert_with(|| self.clone());
+    }
+    for child in &self.children {
+      child.collect_dictionaries(encoded_fields);
+    }
+  }
+
+  fn value_type(&self, dictionaries: &HashMap<i64, Array>) -> Result<Ty, ArrowError> {
+    let mut children = Vec::with_capacity(self.children.len());
+    for child in &self.children {
+      match child.to_field(dictionaries) {
+        Ok(child) => children.push(child),
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(match &self.ty {
+      &Ty::List { .. } => Ty::List { value_type: Box::new(children[0].data_type().clone()) },
+      &Ty::Struct { .. } => Ty::Struct { fields: children },
+      &Ty::Union { ref type_codes, ref mode, .. } => Ty::Union {
+        fields: children,
+        type_codes: type_codes.clone(),
+        mode: mode.clone()
+      },
+      ty => ty.clone()
+    })
+  }
+
+  fn to_field(&self, dictionaries: &HashMap<i64, Array>) -> Result<Field, ArrowError> {
+    let ty = match self.dictionary {
+      Some((id, ref index_type, ordered)) => {
+        match dictionaries.get(&id) {
+          Some(dictionary) => Ty::Dictionary {
+            index_type: Box::new(index_type.clone()),
+            dictionary: Box::new(dictionary.clone()),
+            ordered
+          },
+          None => return Err(ArrowError::invalid(format!("dictionary [{}] of field [{}] is missing", id, self.name)))
+        }
+      },
+      None => match self.value_type(dictionaries) {
+        Ok(ty) => ty,
+        Err(e) => return Err(e)
+      }
+    };
+    let field = if self.nullable {
+      Field::new(self.name.clone(), ty)
+    } else {
+      Field::non_null(self.name.clone(), ty)
+    };
+    match self.metadata {
+      Some(ref metadata) => Ok(field.with_metadata(metadata.clone())),
+      None => Ok(field)
+    }
+  }
+}
+
+fn decode_schema(schema: &TableRef) -> Result<(Vec<FieldDesc>, Option<KeyValueMetadata>), ArrowError> {
+  match schema.get_i16(schema::ENDIANNESS, ENDIANNESS_LITTLE) {
+    Ok(ENDIANNESS_LITTLE) => {},
+    Ok(_) => return Err(ArrowError::not_implemented(String::from("big-endian streams are not supported"))),
+    Err(e) => return Err(e)
+  }
+  let fields = match decode_fields(schema, schema::FIELDS, 0) {
+    Ok(fields) => fields,
+    Err(e) => return Err(e)
+  };
+  match decode_metadata(schema, schema::CUSTOM_METADATA) {
+    Ok(metadata) => Ok((fields, metadata)),
+    Err(e) => Err(e)
+  }
+}
+
+fn decode_fields(table: &TableRef, id: usize, depth: usize) -> Result<Vec<FieldDesc>, ArrowError> {
+  if depth > MAX_NESTING_DEPTH {
+    return Err(ArrowError::invalid(format!("fields are nested deeper than [{}] levels", MAX_NESTING_DEPTH)));
+  }
+  let tables = match table.get_tables(id) {
+    Ok(tables) => tables,
+    Err(e) => return Err(e)
+  };
+  let mut fields = Vec::with_capacity(tables.len());
+  for table in &tables {
+    match decode_field(table, depth) {
+      Ok(field) => fields.push(field),
+      Err(e) => return Err(e)
+    }
+  }
+  Ok(fields)
+}
+
+fn decode_field(table: &TableRef, depth: usize) -> Result<FieldDesc, ArrowError> {
+  let name = match table.get_str(field::NAME) {
+    Ok(name) => String::from(name.unwrap_or("")),
+    Err(e) => return Err(e)
+  };
+  let nullable = match table.get_bool(field::NULLABLE, false) {
+    Ok(nullable) => nullable,
+    Err(e) => return Err(e)
+  };
+  let children = match decode_fields(table, field::CHILDREN, depth + 1) {
+    Ok(children) => children,
+    Err(e) => return Err(e)
+  };
+  let ty = match (table.get_u8(field::TYPE_TYPE, 0), table.get_table(field::TYPE)) {
+    (Ok(type_type), Ok(Some(type_table))) => decode_type(type_type, &type_table, &children),
+    (Ok(_), Ok(None)) => Err(ArrowError::invalid(format!("field [{}] doesn't have a type", name))),
+    (Err(e), _) | (_, Err(e)) => Err(e)
+  };
+  let ty = match ty {
+    Ok(ty) => ty,
+    Err(e) => return Err(e)
+  };
+  let dictionary = match table.get_table(field::DICTIONARY) {
+    Ok(Some(encoding)) => match decode_dictionary_encoding(&encoding) {
+      Ok(dictionary) => Some(dictionary),
+      Err(e) => return Err(e)
+    },
+    Ok(None) => None,
+    Err(e) => return Err(e)
+  };
+  let metadata = match decode_metadata(table, field::CUSTOM_METADATA) {
+    Ok(metadata) => metadata,
+    Err(e) => return Err(e)
+  };
+  Ok(FieldDesc {
+    name,
+    nullable,
+    ty,
+    children,
+    dictionary,
+    metadata
+  })
+}
+
+fn decode_dictionary_encoding(encoding: &TableRef) -> Result<(i64, Ty, bool), ArrowError> {
+  let id = match encoding.get_i64(dictionary_encoding::ID, 0) {
+    Ok(id) => id,
+    Err(e) => return Err(e)
+  };
+  // indices are 32-bit signed integers by default
+  let index_type = match encoding.get_table(dictionary_encoding::INDEX_TYPE) {
+    Ok(Some(int_type)) => decode_int_type(&int_type),
+    Ok(None) => Ok(Ty::Int32),
+    Err(e) => Err(e)
+  };
+  match (index_type, encoding.get_bool(dictionary_encoding::IS_ORDERED, false)) {
+    (Ok(index_type), Ok(ordered)) => Ok((id, index_type, ordered)),
+    (Err(e), _) | (_, Err(e)) => Err(e)
+  }
+}
+
+fn decode_metadata(table: &TableRef, id: usize) -> Result<Option<KeyValueMetadata>, ArrowError> {
+  let key_values = match table.get_tables(id) {
+    Ok(key_values) => key_values,
+    Err(e) => return Err(e)
+  };
+  if key_values.is_empty() {
+    return Ok(None);
+  }
+  let mut metadata = KeyValueMetadata::new();
+  for key_value in &key_values {
+    match (key_value.get_str(key_value::KEY), key_value.get_str(key_value::VALUE)) {
+      (Ok(key), Ok(value)) => metadata.append(String::from(key.unwrap_or("")), String::from(value.unwrap_or(""))),
+      (Err(e), _) | (_, Err(e)) => return Err(e)
+    }
+  }
+  Ok(Some(metadata))
+}
+
+fn decode_int_type(table: &TableRef) -> Result<Ty, ArrowError> {
+  match (table.get_i32(int::BIT_WIDTH, 0), table.get_bool(int::IS_SIGNED, false)) {
+    (Ok(8), Ok(true)) => Ok(Ty::Int8),
+    (Ok(8), Ok(false)) => Ok(Ty::UInt8),
+    (Ok(16), Ok(true)) => Ok(Ty::Int16),
+    (Ok(16), Ok(false)) => Ok(Ty::UInt16),
+    (Ok(32), Ok(true)) => Ok(Ty::Int32),
+    (Ok(32), Ok(false)) => Ok(Ty::UInt32),
+    (Ok(64), Ok(true)) => Ok(Ty::Int64),
+    (Ok(64), Ok(false)) => Ok(Ty::UInt64),
+    (Ok(bit_width), Ok(_)) => Err(ArrowError::invalid(format!("[{}] is not a valid bit width of integers", bit_width))),
+    (Err(e), _) | (_, Err(e)) => Err(e)
+  }
+}
+
+fn decode_time_unit(unit: Result<i16, ArrowError>) -> Result<TimeUnit, ArrowError> {
+  match unit {
+    Ok(TIME_UNIT_SECOND) => Ok(TimeUnit::Second),
+    Ok(TIME_UNIT_MILLISECOND) => Ok(TimeUnit::Milli),
+    Ok(TIME_UNIT_MICROSECOND) => Ok(TimeUnit::Micro),
+    Ok(TIME_UNIT_NANOSECOND) => Ok(TimeUnit::Nano),
+    Ok(unit) => Err(ArrowError::invalid(format!("[{}] is not a valid time unit", unit))),
+    Err(e) => Err(e)
+  }
+}
+
+// decodes the type of a field, where nested types are decoded without their children
+fn decode_type(type_type: u8, table: &TableRef, children: &[FieldDesc]) -> Result<Ty, ArrowError> {
+  match type_type {
+    TYPE_NULL => Ok(Ty::NA),
+    TYPE_BOOL => Ok(Ty::Bool),
+    TYPE_INT => decode_int_type(table),
+    TYPE_FLOATING_POINT => {
+      match table.get_i16(floating_point::PRECISION, PRECISION_HALF) {
+        Ok(PRECISION_HALF) => Ok(Ty::HalfFloat),
+        Ok(PRECISION_SINGLE) => Ok(Ty::Float),
+        Ok(PRECISION_DOUBLE) => Ok(Ty::Double),
+        Ok(precision) => Err(ArrowError::invalid(format!("[{}] is not a valid floating point precision", precision))),
+        Err(e) => Err(e)
+      }
+    },
+    TYPE_UTF8 => Ok(Ty::String),
+    TYPE_BINARY => Ok(Ty::Binary),
+    TYPE_FIXED_SIZE_BINARY => {
+      match table.get_i32(fixed_size_binary::BYTE_WIDTH, 0) {
+        // the bit width of the type must fit in i32
+        Ok(byte_width) if byte_width >= 0 && byte_width <= i32::max_value() / 8 => Ok(Ty::FixedSizeBinary { byte_width }),
+        Ok(byte_width) => Err(ArrowError::invalid(format!("[{}] is not a valid byte width of fixed-size binaries", byte_width))),
+        Err(e) => Err(e)
+      }
+    },
+    TYPE_DATE => {
+      match table.get_i16(date::UNIT, DATE_UNIT_MILLISECOND) {
+        Ok(DATE_UNIT_DAY) => Ok(Ty::Date32 { unit: DateUnit::Day }),
+        Ok(DATE_UNIT_MILLISECOND) => Ok(Ty::Date64 { unit: DateUnit::Milli }),
+        Ok(unit) => Err(ArrowError::invalid(format!("[{}] is not a valid date unit", unit))),
+        Err(e) => Err(e)
+      }
+    },
+    TYPE_TIMESTAMP => {
+      match (decode_time_unit(table.get_i16(timestamp::UNIT, TIME_UNIT_SECOND)), table.get_str(timestamp::TIMEZONE)) {
+        (Ok(unit), Ok(timezone)) => Ok(Ty::Timestamp { unit, timezone: String::from(timezone.unwrap_or("")) }),
+        (Err(e), _) | (_, Err(e)) => Err(e)
+      }
+    },
+    TYPE_TIME => {
+      match (decode_time_unit(table.get_i16(time::UNIT, TIME_UNIT_MILLISECOND)), table.get_i32(time::BIT_WIDTH, 32)) {
+        (Ok(TimeUnit::Second), Ok(32)) => Ok(Ty::Time32 { unit: TimeUnit::Second }),
+        (Ok(TimeUnit::Milli), Ok(32)) => Ok(Ty::Time32 { unit: TimeUnit::Milli }),
+        (Ok(TimeUnit::Micro), Ok(64)) => Ok(Ty::Time64 { unit: TimeUnit::Micro }),
+        (Ok(TimeUnit::Nano), Ok(64)) => Ok(Ty::Time64 { unit: TimeUnit::Nano }),
+        (Ok(unit), Ok(bit_width)) => Err(ArrowError::invalid(format!("{:?} is not a valid unit of [{}]-bit time", unit, bit_width))),
+        (Err(e), _) | (_, Err(e)) => Err(e)
+      }
+    },
+    TYPE_INTERVAL => {
+      match table.get_i16(interval::UNIT, INTERVAL_UNIT_YEAR_MONTH) {
+        Ok(INTERVAL_UNIT_DAY_TIME) => Ok(Ty::Interval { unit: IntervalUnit::DayTime }),
+        Ok(INTERVAL_UNIT_YEAR_MONTH) => Ok(Ty::Interval { unit: IntervalUnit::YearMonth }),
+        Ok(unit) => Err(ArrowError::invalid(format!("[{}] is not a valid interval unit", unit))),
+        Err(e) => Err(e)
+      }
+    },
+    TYPE_DECIMAL => {
+      let precision = table.get_i32(decimal::PRECISION, 0);
+      let scale = table.get_i32(decimal::SCALE, 0);
+      match (precision, scale, table.get_i32(decimal::BIT_WIDTH, 128)) {
+        (Ok(precision), Ok(scale), Ok(128)) => Ok(Ty::Decimal { precision, scale }),
+        (Ok(_), Ok(_), Ok(bit_width)) => Err(ArrowError::not_implemented(format!("[{}]-bit decimals are not supported", bit_width))),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(e)
+      }
+    },
+    TYPE_LIST => {
+      if children.len() == 1 {
+        // the value type is given by the child
+        Ok(Ty::List { value_type: Box::new(Ty::NA) })
+      } else {
+        Err(ArrowError::invalid(format!("list has [{}] children instead of 1", children.len())))
+      }
+    },
+    TYPE_STRUCT => Ok(Ty::Struct { fields: Vec::new() }),
+    TYPE_UNION => {
+      let mode = match table.get_i16(union::MODE, UNION_MODE_SPARSE) {
+        Ok(UNION_MODE_SPARSE) => UnionMode::SPARSE,
+        Ok(UNION_MODE_DENSE) => UnionMode::DENSE,
+        Ok(mode) => return Err(ArrowError::invalid(format!("[{}] is not a valid union mode", mode))),
+        Err(e) => return Err(e)
+      };
+      // type ids are the positions of the children by default
+      let type_ids = match table.get_ints(union::TYPE_IDS) {
+        Ok(Some(type_ids)) => type_ids,
+        Ok(None) => (0..children.len() as i32).collect(),
+        Err(e) => return Err(e)
+      };
+      if type_ids.len() != children.len() {
+        return Err(ArrowError::invalid(format!("union has [{}] type ids for [{}] children", type_ids.len(), children.len())));
+      }
+      if let Some(type_id) = type_ids.iter().find(|type_id| **type_id < 0 || **type_id > 127) {
+        return Err(ArrowError::invalid(format!("[{}] is not a valid union type id", type_id)));
+      }
+      Ok(Ty::Union {
+        fields: Vec::new(),
+        type_codes: type_ids.iter().map(|type_id| *type_id as i8).collect(),
+        mode
+      })
+    },
+    type_type => Err(ArrowError::not_implemented(format!("type [{}] is not supported", type_type)))
+  }
+}
+
+fn decode_dictionary_batch(message: &Message, encoded_fields: &HashMap<i64, FieldDesc>, dictionaries: &mut HashMap<i64, Array>) -> Result<(), ArrowError> {
+  let header = match message.header() {
+    Ok(header) => header,
+    Err(e) => return Err(e)
+  };
+  let id = match header.get_i64(dictionary_batch::ID, 0) {
+    Ok(id) => id,
+    Err(e) => return Err(e)
+  };
+  match header.get_bool(dictionary_batch::IS_DELTA, false) {
+    Ok(false) => {},
+    Ok(true) => return Err(ArrowError::not_implemented(format!("delta dictionary [{}] cannot be read", id))),
+    Err(e) => return Err(e)
+  }
+  if dictionaries.contains_key(&id) {
+    return Err(ArrowError::not_implemented(format!("dictionary [{}] cannot be replaced", id)));
+  }
+  let value_type = match encoded_fields.get(&id) {
+    Some(field) => field.value_type(dictionaries),
+    None => return Err(ArrowError::invalid(format!("dictionary [{}] isn't used by any field", id)))
+  };
+  let value_type = match value_type {
+    Ok(value_type) => value_type,
+    Err(e) => return Err(e)
+  };
+
+  let data = match header.get_table(dictionary_batch::DATA) {
+    Ok(Some(data)) => data,
+    Ok(None) => return Err(ArrowError::invalid(format!("dictionary [{}] doesn't have data", id))),
+    Err(e) => return Err(e)
+  };
+  match decode_record_batch(&data, &message.body, &[&value_type]) {
+    Ok(mut columns) => {
+      dictionaries.insert(id, columns.remove(0));
+      Ok(())
+    },
+    Err(e) => Err(e)
+  }
+}
+
+// decodes the columns of `types` from a record batch table
+fn decode_record_batch(batch: &TableRef, body: &SharedBuffer, types: &[&Ty]) -> Result<Vec<Array>, ArrowError> {
+  match batch.get_table(record_batch::COMPRESSION) {
+    Ok(None) => {},
+    Ok(Some(_)) => return Err(ArrowError::not_implemented(String::from("compressed record batches cannot be read"))),
+    Err(e) => return Err(e)
+  }
+  let len = match batch.get_i64(record_batch::LENGTH, 0) {
+    Ok(len) => len,
+    Err(e) => return Err(e)
+  };
+  let mut decoder = match (batch.get_long_pairs(record_batch::NODES), batch.get_long_pairs(record_batch::BUFFERS)) {
+    (Ok(nodes), Ok(buffers)) => BodyDecoder {
+      body,
+      nodes,
+      buffers,
+      next_node: 0,
+      next_buffer: 0
+    },
+    (Err(e), _) | (_, Err(e)) => return Err(e)
+  };
+
+  let mut columns = Vec::with_capacity(types.len());
+  for ty in types {
+    match decoder.array(ty, 0) {
+      Ok(data) => {
+        if data.len() != len {
+          return Err(ArrowError::invalid(format!("column of length [{}] is in a record batch of length [{}]", data.len(), len)));
+        }
+        columns.push(Array::new(data))
+      },
+      Err(e) => return Err(e)
+    }
+  }
+  if decoder.next_node < decoder.nodes.len() || decoder.next_buffer < decoder.buffers.len() {
+    return Err(ArrowError::invalid(format!("record batch has [{}] field nodes and [{}] buffers, which are more than its columns have",
+                                           decoder.nodes.len(), decoder.buffers.len())));
+  }
+  Ok(columns)
+}
+
+fn byte_size(len: i64, byte_width: i64) -> Result<i64, ArrowError> {
+  match len.checked_mul(byte_width) {
+    Some(size) => Ok(size),
+    None => Err(ArrowError::invalid(format!("[{}] values of [{}] bytes are too large", len, byte_width)))
+  }
+}
+
+#[inline]
+fn bitmap_size(len: i64) -> i64 {
+  len / 8 + if len % 8 == 0 { 0 } else { 1 }
+}
+
+/// Reconstructs arrays from the field nodes and buffers of a record batch, which are visited in
+/// the order of a depth-first traversal. Buffers are slices of the message body, and they are
+/// checked so that every value of the arrays is in the buffers.
+struct BodyDecoder<'a> {
+  body: &'a SharedBuffer,
+  nodes: Vec<(i64, i64)>,
+  buffers: Vec<(i64, i64)>,
+  next_node: usize,
+  next_buffer: usize
+}
+
+impl <'a> BodyDecoder<'a> {
+  // returns the length and the null count of the next array
+  fn node(&mut self, ty: &Ty) -> Result<(i64, i64), ArrowError> {
+    let (len, null_count) = match self.nodes.get(self.next_node) {
+      Some(&node) => node,
+      None => return Err(ArrowError::invalid(format!("record batch doesn't have a field node for {:?}", ty)))
+    };
+    self.next_node += 1;
+    if len < 0 || null_count < 0 || null_count > len {
+      return Err(ArrowError::invalid(format!("field node [{}] has invalid length [{}] and null count [{}]", self.next_node - 1, len, null_count)));
+    }
+    Ok((len, null_count))
+  }
+
+  // returns the next buffer, which must have at least `min_size` bytes
+  fn buffer(&mut self, min_size: i64) -> Result<SharedBuffer, ArrowError> {
+    let i = self.next_buffer;
+    let (offset, size) = match self.buffers.get(i) {
+      Some(&buffer) => buffer,
+      None => return Err(ArrowError::invalid(format!("record batch doesn't have buffer [{}]", i)))
+    };
+    self.next_buffer += 1;
+    let in_body = offset >= 0 && size >= 0 && match offset.checked_add(size) {
+      Some(end) => end <= self.body.size(),
+      None => false
+    };
+    if !in_body {
+      return Err(ArrowError::invalid(format!("buffer [{}] of [{}] bytes at [{}] is out of the body of size [{}]", i, size, offset, self.body.size())));
+    }
+    if offset % 8 != 0 {
+      return Err(ArrowError::invalid(format!("buffer [{}] at [{}] is not aligned to 8 bytes", i, offset)));
+    }
+    if size < min_size {
+      return Err(ArrowError::invalid(format!("buffer [{}] of [{}] bytes is smaller than [{}] bytes", i, size, min_size)));
+    }
+    Ok(self.body.slice(offset, size))
+  }
+
+  fn validity(&mut self, len: i64, null_count: i64) -> Result<Option<SharedBuffer>, ArrowError> {
+    if null_count == 0 {
+      // the bitmap may be omitted
+      return self.buffer(0).map(|_| None);
+    }
+    let bitmap = match self.buffer(bitmap_size(len)) {
+      Ok(bitmap) => bitmap,
+      Err(e) => return Err(e)
+    };
+    let nulls = len - bit_util::count_set_bits(bitmap.data(), 0, len);
+    if nulls != null_count {
+      return Err(ArrowError::invalid(format!("validity bitmap has [{}] nulls, but the null count is [{}]", nulls, null_count)));
+    }
+    Ok(Some(bitmap))
+  }
+
+  fn value_offsets(&mut self, len: i64) -> Result<SharedBuffer, ArrowError> {
+    match len {
+      0 => self.buffer(0),
+      len if len < i64::max_value() => byte_size(len + 1, 4).and_then(|size| self.buffer(size)),
+      len => Err(ArrowError::invalid(format!("[{}] value offsets are too large", len)))
+    }
+  }
+
+  fn children(&mut self, fields: &Vec<Field>, depth: usize) -> Result<Vec<Arc<ArrayData>>, ArrowError> {
+    let mut children = Vec::with_capacity(fields.len());
+    for field in fields {
+      match self.array(field.data_type(), depth + 1) {
+        Ok(child) => children.push(child),
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(children)
+  }
+
+  fn array(&mut self, ty: &Ty, depth: usize) -> Result<Arc<ArrayData>, ArrowError> {
+    if depth > MAX_NESTING_DEPTH {
+      return Err(ArrowError::invalid(format!("arrays are nested deeper than [{}] levels", MAX_NESTING_DEPTH)));
+    }
+    let (len, null_count) = match self.node(ty) {
+      Ok(node) => node,
+      Err(e) => return Err(e)
+    };
+    let data = match ty {
+      &Ty::NA => ArrayData::new(ty.clone(), len, 0, len, Vec::new(), Vec::new()),
+      &Ty::Bool => {
+        match (self.validity(len, null_count), self.buffer(bitmap_size(len))) {
+          (Ok(validity), Ok(values)) => ArrayData::new(ty.clone(), len, 0, null_count, vec![validity, Some(values)], Vec::new()),
+          (Err(e), _) | (_, Err(e)) => return Err(e)
+        }
+      },
+      &Ty::String | &Ty::Binary => {
+        let validity = self.validity(len, null_count);
+        let value_offsets = self.value_offsets(len);
+        let (validity, value_offsets, values) = match (validity, value_offsets, self.buffer(0)) {
+          (Ok(validity), Ok(value_offsets), Ok(values)) => (validity, value_offsets, values),
+          (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return Err(e)
+        };
+        // value offsets and strings are validated by the array data
+        ArrayData::new(ty.clone(), len, 0, null_count, vec![validity, Some(value_offsets), Some(values)], Vec::new())
+      },
+      &Ty::List { ref value_type } => {
+        let validity = self.validity(len, null_count);
+        let (validity, value_offsets) = match (validity, self.value_offsets(len)) {
+          (Ok(validity), Ok(value_offsets)) => (validity, value_offsets),
+          (Err(e), _) | (_, Err(e)) => return Err(e)
+        };
+        let values = match self.array(value_type, depth + 1) {
+          Ok(values) => values,
+          Err(e) => return Err(e)
+        };
+        ArrayData::new(ty.clone(), len, 0, null_count, vec![validity, Some(value_offsets)], vec![values])
+      },
+      &Ty::Struct { ref fields } => {
+        let validity = match self.validity(len, null_count) {
+          Ok(validity) => validity,
+          Err(e) => return Err(e)
+        };
+        let children = match self.children(fields, depth) {
+          Ok(children) => children,
+          Err(e) => return Err(e)
+        };
+        if let Some(child) = children.iter().find(|child| child.len() < len) {
+          return Err(ArrowError::invalid(format!("field of length [{}] is in a struct of length [{}]", child.len(), len)));
+        }
+        ArrayData::new(ty.clone(), len, 0, null_count, vec![validity], children)
+      },
+      &Ty::Union { ref fields, ref mode, .. } => {
+        // unions don't have validity bitmaps
+        if null_count != 0 {
+          return Err(ArrowError::invalid(format!("union has null count [{}]", null_count)));
+        }
+        let type_ids = match self.buffer(len) {
+          Ok(type_ids) => type_ids,
+          Err(e) => return Err(e)
+        };
+        let value_offsets = match mode {
+          &UnionMode::DENSE => match byte_size(len, 4).and_then(|size| self.buffer(size)) {
+            Ok(value_offsets) => Some(value_offsets),
+            Err(e) => return Err(e)
+          },
+          &UnionMode::SPARSE => None
+        };
+        let children = match self.children(fields, depth) {
+          Ok(children) => children,
+          Err(e) => return Err(e)
+        };
+        // type ids and value offsets are validated by the array data
+        let mut buffers = vec![None, Some(type_ids)];
+        match value_offsets {
+          Some(value_offsets) => buffers.push(Some(value_offsets)),
+          None => {}
+        }
+        ArrayData::new(ty.clone(), len, 0, 0, buffers, children)
+      },
+      // fixed-width values and dictionary indices
+      ty => {
+        let values_size = byte_size(len, (ty.bit_width() / 8) as i64);
+        let validity = self.validity(len, null_count);
+        let (validity, values) = match (validity, values_size.and_then(|size| self.buffer(size))) {
+          (Ok(validity), Ok(values)) => (validity, values),
+          (Err(e), _) | (_, Err(e)) => return Err(e)
+        };
+        // dictionary indices are validated by the array data
+        ArrayData::new(ty.clone(), len, 0, null_count, vec![validity, Some(values)], Vec::new())
+      }
+    };
+    match data {
+      Ok(data) => Ok(Arc::new(data)),
+      Err(e) => Err(e)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use array::{Array, ArrowValue, ArrowSlice, DictionaryArray, StructArray, UnionArray};
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use common::KeyValueMetadata;
+  use common::field::Field;
+  use common::schema::Schema;
+  use common::status::{ArrowError, StatusCode};
+  use common::ty::{Ty, DateUnit, TimeUnit, IntervalUnit};
+  use ipc::reader::StreamReader;
+  use ipc::writer::StreamWriter;
+  use memory_pool::{DefaultMemoryPool, MemoryPool};
+  use record_batch::RecordBatch;
+  use std::sync::Arc;
+
+  fn fixed_width(ty: Ty, pool: &Arc<DefaultMemoryPool>) -> ArrayBuilder {
+    ArrayBuilder::new_fixed_width(ty, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()
+  }
+
+  fn string(pool: &Arc<DefaultMemoryPool>) -> ArrayBuilder {
+    ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()))
+  }
+
+  fn batch() -> RecordBatch {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut ids = fixed_width(Ty::Int32, &pool);
+    let mut names = string(&pool);
+    let mut categories = ArrayBuilder::dictionary(fixed_width(Ty::Int8, &pool), string(&pool)).unwrap();
+    let mut flags = fixed_width(Ty::Bool, &pool);
+    let mut lists = ArrayBuilder::list(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), fixed_width(Ty::Int64, &pool));
+    let point_fields = vec![Field::new(String::from("x"), Ty::Int8), Field::new(String::from("y"), Ty::Double)];
+    let mut points = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), point_fields, vec![fixed_width(Ty::Int8, &pool), fixed_width(Ty::Double, &pool)]).unwrap();
+    let sparse_fields = vec![Field::new(String::from("i"), Ty::Int64), Field::new(String::from("s"), Ty::String)];
+    let mut sparse = ArrayBuilder::sparse_union(PoolBuffer::new(pool.clone()), sparse_fields, vec![5, 7], vec![fixed_width(Ty::Int64, &pool), string(&pool)]).unwrap();
+    let dense_fields = vec![Field::new(String::from("i"), Ty::Int32), Field::new(String::from("b"), Ty::UInt8)];
+    let mut dense = ArrayBuilder::dense_union(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), dense_fields, vec![0, 1], vec![fixed_width(Ty::Int32, &pool), fixed_width(Ty::UInt8, &pool)]).unwrap();
+    let mut bytes = fixed_width(Ty::fixed_sized_binary(4), &pool);
+    let mut dates = fixed_width(Ty::date32(), &pool);
+    let mut timestamps = fixed_width(Ty::timestamp_with_unit_and_timestamp(TimeUnit::Micro, String::from("UTC")), &pool);
+    let mut intervals = fixed_width(Ty::Interval { unit: IntervalUnit::YearMonth }, &pool);
+
+    for i in 0..20 {
+      ids.append(i as i32).unwrap();
+      if i % 3 == 0 {
+        names.append_null().unwrap();
+        flags.append_null().unwrap();
+      } else {
+        names.append(format!("name{}", i).as_str()).unwrap();
+        flags.append(i % 2 == 0).unwrap();
+      }
+      categories.append(if i % 2 == 0 { "even" } else { "odd" }).unwrap();
+
+      if i % 4 == 0 {
+        lists.append_null().unwrap();
+      } else {
+        for j in 0..i % 4 {
+          lists.value_builder_mut().append((i * 10 + j) as i64).unwrap();
+        }
+        lists.append_list().unwrap();
+      }
+
+      points.field_builder_mut(0).append(i as i8).unwrap();
+      if i % 5 == 0 {
+        points.field_builder_mut(1).append_null().unwrap();
+      } else {
+        points.field_builder_mut(1).append(i as f64 / 2.0).unwrap();
+      }
+      points.append_struct().unwrap();
+
+      if i % 7 == 6 {
+        // nulls of unions are the nulls of their fields
+        sparse.append_null().unwrap();
+        dense.append_null().unwrap();
+      } else if i % 2 == 0 {
+        sparse.field_builder_mut(0).append(i as i64).unwrap();
+        sparse.field_builder_mut(1).append_null().unwrap();
+        sparse.append_union(5).unwrap();
+        dense.field_builder_mut(0).append(i as i32).unwrap();
+        dense.append_union(0).unwrap();
+      } else {
+        sparse.field_builder_mut(0).append_null().unwrap();
+        sparse.field_builder_mut(1).append(format!("{}", i).as_str()).unwrap();
+        sparse.append_union(7).unwrap();
+        dense.field_builder_mut(1).append(i as u8).unwrap();
+        dense.append_union(1).unwrap();
+      }
+
+      bytes.append(&[i as u8; 4][..]).unwrap();
+      dates.append(17000 + i as i32).unwrap();
+      timestamps.append(i as i64 * 1000).unwrap();
+      intervals.append(i as i32 - 10).unwrap();
+    }
+
+    let columns = vec![
+      Array::from(ids), Array::from(names), Array::from(categories), Array::from(flags), Array::from(lists),
+      Array::from(points), Array::from(sparse), Array::from(dense), Array::from(bytes), Array::from(dates),
+      Array::from(timestamps), Array::from(ArrayBuilder::null(20)), Array::from(intervals)
+    ];
+    let names = ["id", "name", "category", "flag", "list", "point", "sparse", "dense", "bytes", "date", "timestamp", "nulls", "interval"];
+    let mut fields: Vec<Field> = names.iter().zip(columns.iter())
+      .map(|(name, column)| Field::new(String::from(*name), column.ty().clone()))
+      .collect();
+    fields[0] = Field::non_null(String::from("id"), Ty::Int32)
+      .with_metadata(KeyValueMetadata::with_kvs(vec![String::from("key")], vec![String::from("id")]));
+    let metadata = KeyValueMetadata::with_kvs(vec![String::from("origin")], vec![String::from("test")]);
+    RecordBatch::new(Arc::new(Schema::new_with_metadata(fields, metadata)), columns).unwrap()
+  }
+
+  fn write(schema: &Arc<Schema>, batches: &[RecordBatch]) -> Vec<u8> {
+    let mut writer = StreamWriter::new(Vec::new(), schema.clone()).unwrap();
+    for batch in batches {
+      writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap()
+  }
+
+  fn read(stream: &[u8]) -> Result<(Arc<Schema>, Vec<RecordBatch>), ArrowError> {
+    let mut reader = match StreamReader::new(stream, Arc::new(DefaultMemoryPool::new())) {
+      Ok(reader) => reader,
+      Err(e) => return Err(e)
+    };
+    let batches: Result<Vec<RecordBatch>, ArrowError> = reader.by_ref().collect();
+    batches.map(|batches| (reader.schema().clone(), batches))
+  }
+
+  #[test]
+  fn test_read_stream() {
+    let batch = batch();
+    let stream = write(batch.schema(), &[batch.clone(), batch.slice(3, 10)]);
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut reader = StreamReader::new(&stream[..], pool.clone()).unwrap();
+    let schema = reader.schema().clone();
+    assert_eq!(13, schema.num_fields());
+    assert_eq!(batch.schema().metadata(), schema.metadata());
+    assert!(schema.field(0).equals(batch.schema().field(0), true));
+    assert!(!schema.field(0).nullable());
+    for i in [1, 3, 4, 5, 6, 7, 8, 10, 11, 12].iter() {
+      assert_eq!(batch.schema().field(*i), schema.field(*i));
+    }
+    // the unit of dates is implied by their width
+    assert_eq!(&Ty::Date32 { unit: DateUnit::Day }, schema.field(9).data_type());
+
+    let batches: Vec<RecordBatch> = reader.by_ref().map(|batch| batch.unwrap()).collect();
+    assert!(reader.next_batch().unwrap().is_none());
+    assert_eq!(2, batches.len());
+    assert_eq!(20, batches[0].num_rows());
+    assert_eq!(10, batches[1].num_rows());
+    // buffers are allocated by the pool
+    assert!(pool.bytes_allocated() > 0);
+
+    let columns = batches[1].columns();
+    assert_eq!(&(3..13).collect::<Vec<i32>>()[..], ArrowSlice::<i32>::values(&columns[0]));
+    assert!(columns[1].is_null(0));
+    assert_eq!("name4", ArrowValue::<&str>::value(&columns[1], 1));
+    let categories = columns[2].decode(Arc::new(DefaultMemoryPool::new())).unwrap();
+    assert_eq!("odd", ArrowValue::<&str>::value(&categories, 0));
+    assert_eq!("even", ArrowValue::<&str>::value(&categories, 1));
+    assert!(columns[3].is_null(0));
+    assert_eq!(true, ArrowValue::<bool>::value(&columns[3], 1));
+    assert_eq!(&[30, 31, 32], ArrowSlice::<i64>::values(&ArrowValue::<Array>::value(&columns[4], 0)));
+    assert!(columns[4].is_null(1));
+    assert_eq!(&[3, 4, 5], &ArrowSlice::<i8>::values(&columns[5].field(0))[..3]);
+    assert!(columns[5].field(1).is_null(2));
+    assert_eq!(7, columns[6].type_id(0));
+    assert_eq!("3", ArrowValue::<&str>::value(&columns[6].union_field(1), 0));
+    let (child_id, i) = columns[7].child_slot(1);
+    assert_eq!(4, ArrowValue::<i32>::value(&columns[7].union_field(child_id), i));
+    let (child_id, i) = columns[6].child_slot(3);
+    assert!(columns[6].union_field(child_id).is_null(i));
+    let (child_id, i) = columns[7].child_slot(3);
+    assert!(columns[7].union_field(child_id).is_null(i));
+    assert_eq!(17003, ArrowValue::<i32>::value(&columns[9], 0));
+    assert_eq!(10, columns[11].null_count());
+    assert_eq!(&(-7..3).collect::<Vec<i32>>()[..], ArrowSlice::<i32>::values(&columns[12]));
+
+    // the batches are written back to the same stream
+    assert_eq!(stream, write(&schema, &batches));
+
+    drop(batches);
+    drop(reader);
+    drop(schema);
+    assert_eq!(0, pool.bytes_allocated());
+  }
+
+  #[test]
+  fn test_read_mapped_stream() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use buffer::{Buffer, MmapBuffer, SharedBuffer};
+
+    let batch = batch();
+    let stream = write(batch.schema(), &[batch.clone(), batch.slice(3, 10)]);
+    let path = env::temp_dir().join(format!("iron-arrow-test-mapped-stream-{}", std::process::id()));
+    File::create(&path).unwrap().write_all(&stream).unwrap();
+    let mapping = SharedBuffer::new(unsafe { MmapBuffer::from_path(&path) }.unwrap());
+    fs::remove_file(&path).unwrap();
+
+    let mut reader = StreamReader::from_buffer(mapping.clone()).unwrap();
+    let batches: Vec<RecordBatch> = reader.by_ref().map(|batch| batch.unwrap()).collect();
+    assert_eq!(2, batches.len());
+    assert_eq!(&batches, &read(&stream).unwrap().1);
+    // the buffers of arrays point into the mapping
+    let start = mapping.data() as usize;
+    for buffer in batches[0].column(0).data().buffers().iter().filter_map(|buffer| buffer.as_ref()) {
+      assert!(buffer.data() as usize >= start && buffer.data() as usize + buffer.size() as usize <= start + stream.len());
+    }
+
+    // truncated bodies aren't sliced out of the buffer
+    let truncated = mapping.slice(0, stream.len() as i64 / 2);
+    let result: Result<Vec<RecordBatch>, ArrowError> = StreamReader::from_buffer(truncated).and_then(|reader| reader.collect());
+    assert_eq!(&StatusCode::IOError, result.unwrap_err().code());
+
+    // the batches outlive the reader and the mapping
+    drop(reader);
+    drop(mapping);
+    assert_eq!(stream, write(batches[0].schema(), &batches));
+  }
+
+  #[test]
+  fn test_read_truncated_stream() {
+    let batch = batch();
+    let stream = write(batch.schema(), &[batch.clone(), batch.clone()]);
+    for len in 0..stream.len() {
+      match read(&stream[..len]) {
+        // streams may end without the end-of-stream marker
+        Ok((_, batches)) => assert!(batches.len() < 2 || len == stream.len() - 8),
+        Err(e) => assert!(e.code() == &StatusCode::IOError || e.code() == &StatusCode::Invalid, "{:?}", e)
+      }
+    }
+
+    assert_eq!(&StatusCode::Invalid, read(&[]).unwrap_err().code());
+    assert_eq!(&StatusCode::IOError, read(&stream[..stream.len() / 2]).unwrap_err().code());
+    assert_eq!(&StatusCode::IOError, read(&stream[..stream.len() - 5]).unwrap_err().code());
+    assert_eq!(2, read(&stream[..stream.len() - 8]).unwrap().1.len());
+  }
+
+  #[test]
+  fn test_read_malformed_stream() {
+    let batch = batch();
+    let stream = write(batch.schema(), &[batch.clone()]);
+
+    // no byte of the stream can make the reader panic
+    let mut codes = Vec::new();
+    for i in 0..stream.len() {
+      let mut corrupted = stream.clone();
+      corrupted[i] ^= 0xff;
+      if let Err(e) = read(&corrupted) {
+        if !codes.contains(e.code()) {
+          codes.push(match e.code() {
+            &StatusCode::Invalid => StatusCode::Invalid,
+            &StatusCode::IOError => StatusCode::IOError,
+            &StatusCode::NotImplemented => StatusCode::NotImplemented,
+            code => panic!("unexpected error {:?}", code)
+          });
+        }
+      }
+    }
+    assert!(codes.contains(&StatusCode::Invalid));
+    assert!(codes.contains(&StatusCode::IOError));
+
+    // streams start with a schema
+    let schema_len = 8 + i32::from_le_bytes([stream[4], stream[5], stream[6], stream[7]]) as usize;
+    assert_eq!(&StatusCode::Invalid, read(&stream[schema_len..]).unwrap_err().code());
+
+    // strings are valid UTF-8
+    let mut corrupted = stream.clone();
+    let pos = corrupted.windows(6).position(|window| window == b"name19").unwrap();
+    corrupted[pos] = 0xff;
+    assert_eq!(&StatusCode::Invalid, read(&corrupted).unwrap_err().code());
+  }
+}
@@ -0,0 +1,738 @@
+use array::{Array, VariableWidthArray, ListArray, StructArray, UnionArray};
+use buffer::{Buffer, SharedBuffer};
+use common::KeyValueMetadata;
+use common::bit_util;
+use common::field::Field;
+use common::schema::Schema;
+use common::status::ArrowError;
+use common::ty::{Ty, TimeUnit, IntervalUnit, UnionMode};
+use ipc::flatbuf::{self, Table, Value};
+use ipc::format::*;
+use record_batch::RecordBatch;
+use table;
+
+use std::io::Write;
+use std::slice;
+use std::sync::Arc;
+
+/// Writes record batches in the Arrow IPC streaming format. The stream starts with the schema and
+/// the dictionaries of dictionary-encoded fields, which are followed by record batches and the
+/// end-of-stream marker written by `finish()`.
+///
+/// Metadata is written in version V5, where union arrays don't have validity bitmaps and their
+/// nulls are the nulls of their fields. The units of `Date32` and `Date64` are implied by their
+/// widths, which are days and milliseconds respectively.
+pub struct StreamWriter<W: Write> {
+  writer: W,
+  schema: Arc<Schema>
+}
+
+impl <W: Write> StreamWriter<W> {
+  /// Creates a writer of batches of `schema`, and writes the schema and dictionaries.
+  pub fn new(writer: W, schema: Arc<Schema>) -> Result<StreamWriter<W>, ArrowError> {
+    let mut encoder = SchemaEncoder {
+      dictionaries: Vec::new()
+    };
+    let header = match encoder.schema(&schema) {
+      Ok(header) => header,
+      Err(e) => return Err(e)
+    };
+
+    let mut stream = StreamWriter {
+      writer,
+      schema
+    };
+    match stream.write_message(HEADER_SCHEMA, header, &BodyEncoder::new()) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    for (id, dictionary) in encoder.dictionaries.iter().enumerate() {
+      match stream.write_dictionary(id as i64, dictionary) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(stream)
+  }
+
+  #[inline]
+  pub fn schema(&self) -> &Arc<Schema> {
+    &self.schema
+  }
+
+  /// Writes `batch`, whose schema must be the same as the schema of the stream except metadata.
+  pub fn write(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+    if !batch.schema().equals(&self.schema, false) {
+      return Err(ArrowError::invalid(format!("schema {:?} is different from the stream schema {:?}", batch.schema(), self.schema)));
+    }
+
+    let mut body = BodyEncoder::new();
+    for column in batch.columns() {
+      match body.array(column) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    let header = body.record_batch(batch.num_rows());
+    self.write_message(HEADER_RECORD_BATCH, header, &body)
+  }
+
+  pub fn write_table(&mut self, table: &table::Table) -> Result<(), ArrowError> {
+    for batch in table.batches() {
+      match self.write(batch) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes the end-of-stream marker, and returns the underlying writer.
+  pub fn finish(mut self) -> Result<W, ArrowError> {
+    let mut eos = Vec::with_capacity(8);
+    eos.extend_from_slice(&CONTINUATION_MARKER.to_le_bytes());
+    eos.extend_from_slice(&0i32.to_le_bytes());
+    match write_all(&mut self.writer, &eos) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    match self.writer.flush() {
+      Ok(_) => Ok(self.writer),
+      Err(e) => Err(ArrowError::io_error(format!("cannot flush the stream: {}", e)))
+    }
+  }
+
+  fn write_dictionary(&mut self, id: i64, dictionary: &Array) -> Result<(), ArrowError> {
+    let mut body = BodyEncoder::new();
+    match body.array(dictionary) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    let header = Table::new()
+      .with(dictionary_batch::ID, Value::Long(id))
+      .with(dictionary_batch::DATA, Value::Table(body.record_batch(dictionary.len())))
+      .with(dictionary_batch::IS_DELTA, Value::Bool(false));
+    self.write_message(HEADER_DICTIONARY_BATCH, header, &body)
+  }
+
+  fn write_message(&mut self, header_type: u8, header: Table, body: &BodyEncoder) -> Result<(), ArrowError> {
+    let message = Table::new()
+      .with(message::VERSION, Value::Short(METADATA_VERSION_V5))
+      .with(message::HEADER_TYPE, Value::UByte(header_type))
+      .with(message::HEADER, Value::Table(header))
+      .with(message::BODY_LENGTH, Value::Long(body.len));
+    let mut metadata = flatbuf::encode(&message);
+    // the metadata is padded so that the body starts at a multiple of 8 bytes
+    let padded_len = flatbuf::round_up(metadata.len() + 8, 8) - 8;
+    metadata.resize(padded_len, 0);
+
+    let mut prefix = Vec::with_capacity(8);
+    prefix.extend_from_slice(&CONTINUATION_MARKER.to_le_bytes());
+    prefix.extend_from_slice(&(padded_len as i32).to_le_bytes());
+    match write_all(&mut self.writer, &prefix) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+    match write_all(&mut self.writer, &metadata) {
+      Ok(_) => {},
+      Err(e) => return Err(e)
+    }
+
+    let padding = [0u8; 8];
+    for part in &body.parts {
+      let bytes = part.bytes();
+      match write_all(&mut self.writer, bytes) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+      match write_all(&mut self.writer, &padding[..flatbuf::round_up(bytes.len(), 8) - bytes.len()]) {
+        Ok(_) => {},
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(())
+  }
+}
+
+fn write_all<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), ArrowError> {
+  match writer.write_all(bytes) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(ArrowError::io_error(format!("cannot write the stream: {}", e)))
+  }
+}
+
+struct SchemaEncoder {
+  // dictionaries in the order of their ids
+  dictionaries: Vec<Array>
+}
+
+impl SchemaEncoder {
+  fn schema(&mut self, schema: &Schema) -> Result<Table, ArrowError> {
+    let fields = match self.fields(schema.fields()) {
+      Ok(fields) => fields,
+      Err(e) => return Err(e)
+    };
+    let mut table = Table::new()
+      .with(schema::ENDIANNESS, Value::Short(ENDIANNESS_LITTLE))
+      .with(schema::FIELDS, Value::Tables(fields));
+    if let &Some(ref metadata) = schema.metadata() {
+      table = table.with(schema::CUSTOM_METADATA, key_values(metadata));
+    }
+    Ok(table)
+  }
+
+  fn fields(&mut self, fields: &Vec<Field>) -> Result<Vec<Table>, ArrowError> {
+    let mut tables = Vec::with_capacity(fields.len());
+    for field in fields {
+      match self.field(field) {
+        Ok(table) => tables.push(table),
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(tables)
+  }
+
+  fn field(&mut self, field: &Field) -> Result<Table, ArrowError> {
+    // dictionary-encoded fields have the type of their dictionaries
+    let ty = match field.data_type() {
+      &Ty::Dictionary { ref dictionary, .. } => dictionary.ty(),
+      ty => ty
+    };
+    let (type_type, type_table) = match encode_type(ty) {
+      Ok(encoded) => encoded,
+      Err(e) => return Err(e)
+    };
+    let children = match ty {
+      &Ty::Struct { ref fields } | &Ty::Union { ref fields, .. } => fields.clone(),
+      &Ty::List { ref value_type } => vec![Field::new(String::from("item"), value_type.as_ref().clone())],
+      _ => Vec::new()
+    };
+    // dictionaries nested in the children get smaller ids, so that they are written first
+    let children = match self.fields(&children) {
+      Ok(children) => children,
+      Err(e) => return Err(e)
+    };
+
+    let mut table = Table::new()
+      .with(field::NAME, Value::String(field.name().clone()))
+      .with(field::NULLABLE, Value::Bool(field.nullable()))
+      .with(field::TYPE_TYPE, Value::UByte(type_type))
+      .with(field::TYPE, Value::Table(type_table))
+      .with(field::CHILDREN, Value::Tables(children));
+    if let &Ty::Dictionary { ref index_type, ref dictionary, ordered } = field.data_type() {
+      if !index_type.is_integer() {
+        return Err(ArrowError::invalid(format!("dictionary index type {:?} is not an integer type", index_type)));
+      }
+      let encoding = Table::new()
+        .with(dictionary_encoding::ID, Value::Long(self.dictionaries.len() as i64))
+        .with(dictionary_encoding::INDEX_TYPE, Value::Table(int_type(index_type)))
+        .with(dictionary_encoding::IS_ORDERED, Value::Bool(ordered));
+      self.dictionaries.push(dictionary.as_ref().clone());
+      table = table.with(field::DICTIONARY, Value::Table(encoding));
+    }
+    if let &Some(ref metadata) = field.metadata() {
+      table = table.with(field::CUSTOM_METADATA, key_values(metadata));
+    }
+    Ok(table)
+  }
+}
+
+fn key_values(metadata: &KeyValueMetadata) -> Value {
+  Value::Tables((0..metadata.len()).map(|i| {
+    Table::new()
+      .with(key_value::KEY, Value::String(metadata.key(i).clone()))
+      .with(key_value::VALUE, Value::String(metadata.value(i).clone()))
+  }).collect())
+}
+
+fn int_type(ty: &Ty) -> Table {
+  Table::new()
+    .with(int::BIT_WIDTH, Value::Int(ty.bit_width()))
+    .with(int::IS_SIGNED, Value::Bool(ty.is_signed()))
+}
+
+fn time_unit(unit: &TimeUnit) -> Value {
+  Value::Short(match unit {
+    &TimeUnit::Second => TIME_UNIT_SECOND,
+    &TimeUnit::Milli => TIME_UNIT_MILLISECOND,
+    &TimeUnit::Micro => TIME_UNIT_MICROSECOND,
+    &TimeUnit::Nano => TIME_UNIT_NANOSECOND
+  })
+}
+
+fn encode_type(ty: &Ty) -> Result<(u8, Table), ArrowError> {
+  let table = Table::new();
+  match ty {
+    &Ty::NA => Ok((TYPE_NULL, table)),
+    &Ty::Bool => Ok((TYPE_BOOL, table)),
+    &Ty::UInt8 | &Ty::Int8 | &Ty::UInt16 | &Ty::Int16 |
+    &Ty::UInt32 | &Ty::Int32 | &Ty::UInt64 | &Ty::Int64 => Ok((TYPE_INT, int_type(ty))),
+    &Ty::HalfFloat => Ok((TYPE_FLOATING_POINT, table.with(floating_point::PRECISION, Value::Short(PRECISION_HALF)))),
+    &Ty::Float => Ok((TYPE_FLOATING_POINT, table.with(floating_point::PRECISION, Value::Short(PRECISION_SINGLE)))),
+    &Ty::Double => Ok((TYPE_FLOATING_POINT, table.with(floating_point::PRECISION, Value::Short(PRECISION_DOUBLE)))),
+    &Ty::String => Ok((TYPE_UTF8, table)),
+    &Ty::Binary => Ok((TYPE_BINARY, table)),
+    &Ty::FixedSizeBinary { byte_width } => {
+      Ok((TYPE_FIXED_SIZE_BINARY, table.with(fixed_size_binary::BYTE_WIDTH, Value::Int(byte_width))))
+    },
+    &Ty::Date32 { .. } => Ok((TYPE_DATE, table.with(date::UNIT, Value::Short(DATE_UNIT_DAY)))),
+    &Ty::Date64 { .. } => Ok((TYPE_DATE, table.with(date::UNIT, Value::Short(DATE_UNIT_MILLISECOND)))),
+    &Ty::Timestamp { ref unit, ref timezone } => {
+      let table = table.with(timestamp::UNIT, time_unit(unit));
+      if timezone.is_empty() {
+        Ok((TYPE_TIMESTAMP, table))
+      } else {
+        Ok((TYPE_TIMESTAMP, table.with(timestamp::TIMEZONE, Value::String(timezone.clone()))))
+      }
+    },
+    &Ty::Time32 { ref unit } => {
+      match unit {
+        &TimeUnit::Second | &TimeUnit::Milli => {
+          Ok((TYPE_TIME, table.with(time::UNIT, time_unit(unit)).with(time::BIT_WIDTH, Value::Int(32))))
+        },
+        _ => Err(ArrowError::invalid(format!("{:?} is not a valid unit of time32", unit)))
+      }
+    },
+    &Ty::Time64 { ref unit } => {
+      match unit {
+        &TimeUnit::Micro | &TimeUnit::Nano => {
+          Ok((TYPE_TIME, table.with(time::UNIT, time_unit(unit)).with(time::BIT_WIDTH, Value::Int(64))))
+        },
+        _ => Err(ArrowError::invalid(format!("{:?} is not a valid unit of time64", unit)))
+      }
+    },
+    &Ty::Interval { ref unit } => {
+      match unit {
+        &IntervalUnit::DayTime => Ok((TYPE_INTERVAL, table.with(interval::UNIT, Value::Short(INTERVAL_UNIT_DAY_TIME)))),
+        &IntervalUnit::YearMonth => Ok((TYPE_INTERVAL, table.with(interval::UNIT, Value::Short(INTERVAL_UNIT_YEAR_MONTH))))
+      }
+    },
+    &Ty::Decimal { precision, scale } => {
+      Ok((TYPE_DECIMAL, table
+        .with(decimal::PRECISION, Value::Int(precision))
+        .with(decimal::SCALE, Value::Int(scale))
+        .with(decimal::BIT_WIDTH, Value::Int(128))))
+    },
+    &Ty::List { .. } => Ok((TYPE_LIST, table)),
+    &Ty::Struct { .. } => Ok((TYPE_STRUCT, table)),
+    &Ty::Union { ref type_codes, ref mode, .. } => {
+      let mode = match mode {
+        &UnionMode::SPARSE => UNION_MODE_SPARSE,
+        &UnionMode::DENSE => UNION_MODE_DENSE
+      };
+      Ok((TYPE_UNION, table
+        .with(union::MODE, Value::Short(mode))
+        .with(union::TYPE_IDS, Value::Ints(type_codes.iter().map(|code| *code as i32).collect()))))
+    },
+    &Ty::Dictionary { .. } => Err(ArrowError::invalid(format!("dictionaries of dictionaries are not supported: {:?}", ty)))
+  }
+}
+
+enum BodyPart {
+  Shared(SharedBuffer),
+  Owned(Vec<u8>)
+}
+
+impl BodyPart {
+  fn bytes(&self) -> &[u8] {
+    match self {
+      &BodyPart::Shared(ref buffer) => {
+        if buffer.size() == 0 {
+          &[]
+        } else {
+          unsafe { slice::from_raw_parts(buffer.data(), buffer.size() as usize) }
+        }
+      },
+      &BodyPart::Owned(ref bytes) => bytes
+    }
+  }
+}
+
+/// Collects the field nodes and buffers of arrays in the order of a depth-first traversal. Sliced
+/// arrays are written without the values out of their slices.
+struct BodyEncoder {
+  nodes: Vec<(i64, i64)>,
+  buffers: Vec<(i64, i64)>,
+  parts: Vec<BodyPart>,
+  len: i64
+}
+
+impl BodyEncoder {
+  fn new() -> BodyEncoder {
+    BodyEncoder {
+      nodes: Vec::new(),
+      buffers: Vec::new(),
+      parts: Vec::new(),
+      len: 0
+    }
+  }
+
+  fn record_batch(&self, length: i64) -> Table {
+    Table::new()
+      .with(record_batch::LENGTH, Value::Long(length))
+      .with(record_batch::NODES, Value::LongPairs(self.nodes.clone()))
+      .with(record_batch::BUFFERS, Value::LongPairs(self.buffers.clone()))
+  }
+
+  fn add_buffer(&mut self, part: BodyPart) {
+    let size = part.bytes().len() as i64;
+    self.buffers.push((self.len, size));
+    // every buffer starts at a multiple of 8 bytes
+    self.len += flatbuf::round_up(size as usize, 8) as i64;
+    self.parts.push(part);
+  }
+
+  // buffers shorter than the values of an array are rejected instead of being sliced
+  fn add_slice(&mut self, buffer: &SharedBuffer, offset: i64, len: i64) -> Result<(), ArrowError> {
+    if len == 0 {
+      self.add_buffer(BodyPart::Owned(Vec::new()));
+    } else if offset < 0 || offset + len > buffer.size() {
+      return Err(ArrowError::invalid(
+        format!("buffer of [{}] bytes doesn't have [{}] bytes at [{}]", buffer.size(), len, offset)
+      ));
+    } else {
+      self.add_buffer(BodyPart::Shared(buffer.slice(offset, len)));
+    }
+    Ok(())
+  }
+
+  fn add_bitmap(&mut self, bitmap: &SharedBuffer, offset: i64, len: i64) -> Result<(), ArrowError> {
+    if offset % 8 == 0 {
+      self.add_slice(bitmap, offset / 8, bit_util::bytes_for_bits(len))
+    } else if bit_util::bytes_for_bits(offset + len) > bitmap.size() {
+      Err(ArrowError::invalid(
+        format!("bitmap of [{}] bytes doesn't have [{}] bits at [{}]", bitmap.size(), len, offset)
+      ))
+    } else {
+      let mut bits = vec![0u8; bit_util::bytes_for_bits(len) as usize];
+      for i in 0..len {
+        if bit_util::get_bit(bitmap.data(), offset + i) {
+          bit_util::set_bit(bits.as_mut_ptr(), i);
+        }
+      }
+      self.add_buffer(BodyPart::Owned(bits));
+      Ok(())
+    }
+  }
+
+  fn add_validity(&mut self, array: &Array) -> Result<(), ArrowError> {
+    match array.null_bitmap_buffer() {
+      Some(bitmap) if array.null_count() > 0 => self.add_bitmap(bitmap, array.offset(), array.len()),
+      _ => {
+        self.add_buffer(BodyPart::Owned(Vec::new()));
+        Ok(())
+      }
+    }
+  }
+
+  // offsets of the values in the array, which start from 0
+  fn add_value_offsets(&mut self, array: &Array) -> Result<(), ArrowError> {
+    let value_offsets = array.value_offsets();
+    if value_offsets[0] == 0 && array.data().buffer(1).size() > 0 {
+      self.add_slice(array.data().buffer(1), array.offset() * 4, (array.len() + 1) * 4)
+    } else {
+      let mut bytes = Vec::with_capacity(value_offsets.len() * 4);
+      for offset in value_offsets {
+        bytes.extend_from_slice(&(offset - value_offsets[0]).to_le_bytes());
+      }
+      self.add_buffer(BodyPart::Owned(bytes));
+      Ok(())
+    }
+  }
+
+  fn array(&mut self, array: &Array) -> Result<(), ArrowError> {
+    let offset = array.offset();
+    let len = array.len();
+    match array.ty() {
+      &Ty::NA => {
+        self.nodes.push((len, len));
+        Ok(())
+      },
+      &Ty::Bool => {
+        self.nodes.push((len, array.null_count()));
+        self.add_validity(array)
+          .and_then(|_| self.add_bitmap(array.data().buffer(1), offset, len))
+      },
+      &Ty::String | &Ty::Binary => {
+        self.nodes.push((len, array.null_count()));
+        let value_offsets = array.value_offsets();
+        let start = value_offsets[0] as i64;
+        self.add_validity(array)
+          .and_then(|_| self.add_value_offsets(array))
+          .and_then(|_| self.add_slice(array.data().buffer(2), start, value_offsets[len as usize] as i64 - start))
+      },
+      &Ty::List { .. } => {
+        self.nodes.push((len, array.null_count()));
+        let value_offsets = array.value_offsets();
+        let start = value_offsets[0] as i64;
+        self.add_validity(array)
+          .and_then(|_| self.add_value_offsets(array))
+          .and_then(|_| self.array(&array.list_values().slice(start, value_offsets[len as usize] as i64 - start)))
+      },
+      &Ty::Struct { .. } => {
+        self.nodes.push((len, array.null_count()));
+        match self.add_validity(array) {
+          Ok(_) => {},
+          Err(e) => return Err(e)
+        }
+        for i in 0..array.num_fields() {
+          match self.array(&array.field(i)) {
+            Ok(_) => {},
+            Err(e) => return Err(e)
+          }
+        }
+        Ok(())
+      },
+      &Ty::Union { ref fields, ref mode, .. } => {
+        // unions don't have validity bitmaps
+        self.nodes.push((len, 0));
+        let result = if mode == &UnionMode::DENSE {
+          self.add_slice(array.data().buffer(1), offset, len)
+            .and_then(|_| self.add_slice(array.data().buffer(2), offset * 4, len * 4))
+        } else {
+          self.add_slice(array.data().buffer(1), offset, len)
+        };
+        match result {
+          Ok(_) => {},
+          Err(e) => return Err(e)
+        }
+        for i in 0..fields.len() {
+          match self.array(&array.union_field(i)) {
+            Ok(_) => {},
+            Err(e) => return Err(e)
+          }
+        }
+        Ok(())
+      },
+      // fixed-width values and dictionary indices
+      ty => {
+        let byte_width = (ty.bit_width() / 8) as i64;
+        self.nodes.push((len, array.null_count()));
+        self.add_validity(array)
+          .and_then(|_| self.add_slice(array.data().buffer(1), offset * byte_width, len * byte_width))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use array::Array;
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use common::field::Field;
+  use common::schema::Schema;
+  use common::status::StatusCode;
+  use common::ty::{Ty, IntervalUnit};
+  use ipc::flatbuf::tests::*;
+  use ipc::format::*;
+  use ipc::writer::StreamWriter;
+  use memory_pool::DefaultMemoryPool;
+  use record_batch::RecordBatch;
+  use std::sync::Arc;
+
+  struct Message {
+    header_type: u8,
+    metadata: Vec<u8>,
+    // the position of the header table in the metadata
+    header: usize,
+    body: Vec<u8>
+  }
+
+  fn messages(stream: &[u8]) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut pos = 0;
+    loop {
+      assert_eq!(CONTINUATION_MARKER as i32, read_i32(stream, pos));
+      let len = read_i32(stream, pos + 4) as usize;
+      pos += 8;
+      if len == 0 {
+        assert_eq!(stream.len(), pos);
+        return messages;
+      }
+      assert_eq!(0, len % 8);
+
+      let metadata = stream[pos..pos + len].to_vec();
+      let message = deref(&metadata, 0);
+      assert_eq!(METADATA_VERSION_V5, read_i16(&metadata, field(&metadata, message, message::VERSION).unwrap()));
+      let header_type = read_u8(&metadata, field(&metadata, message, message::HEADER_TYPE).unwrap());
+      let header = deref(&metadata, field(&metadata, message, message::HEADER).unwrap());
+      let body_len = read_i64(&metadata, field(&metadata, message, message::BODY_LENGTH).unwrap()) as usize;
+      assert_eq!(0, body_len % 8);
+      pos += len;
+
+      messages.push(Message {
+        header_type,
+        metadata,
+        header,
+        body: stream[pos..pos + body_len].to_vec()
+      });
+      pos += body_len;
+    }
+  }
+
+  // returns the field nodes and buffers of a record batch table
+  fn nodes_and_buffers(metadata: &[u8], batch: usize) -> (Vec<(i64, i64)>, Vec<(i64, i64)>) {
+    let read_pairs = |id: usize| {
+      let pairs = deref(metadata, field(metadata, batch, id).unwrap());
+      (0..read_i32(metadata, pairs) as usize)
+        .map(|i| (read_i64(metadata, pairs + 4 + 16 * i), read_i64(metadata, pairs + 12 + 16 * i)))
+        .collect::<Vec<(i64, i64)>>()
+    };
+    (read_pairs(record_batch::NODES), read_pairs(record_batch::BUFFERS))
+  }
+
+  #[test]
+  fn test_write_stream() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut ids = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut names = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    let mut categories = ArrayBuilder::dictionary(
+      ArrayBuilder::new_fixed_width(Ty::Int8, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap(),
+      ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()))
+    ).unwrap();
+    for i in 0..20 {
+      ids.append(i as i32).unwrap();
+      if i % 3 == 0 {
+        names.append_null().unwrap();
+      } else {
+        names.append(format!("name{}", i).as_str()).unwrap();
+      }
+      categories.append(if i % 2 == 0 { "even" } else { "odd" }).unwrap();
+    }
+    let columns = vec![Array::from(ids), Array::from(names), Array::from(categories)];
+    let schema = Arc::new(Schema::new(vec![
+      Field::non_null(String::from("id"), Ty::Int32),
+      Field::new(String::from("name"), Ty::String),
+      Field::new(String::from("category"), columns[2].ty().clone())
+    ]));
+    let batch = RecordBatch::new(schema.clone(), columns).unwrap();
+
+    let mut writer = StreamWriter::new(Vec::new(), schema.clone()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.write(&batch.slice(3, 10)).unwrap();
+    let stream = writer.finish().unwrap();
+
+    let messages = messages(&stream);
+    let header_types: Vec<u8> = messages.iter().map(|message| message.header_type).collect();
+    assert_eq!(vec![HEADER_SCHEMA, HEADER_DICTIONARY_BATCH, HEADER_RECORD_BATCH, HEADER_RECORD_BATCH], header_types);
+
+    // schema
+    let metadata = &messages[0].metadata;
+    let fields = deref(metadata, field(metadata, messages[0].header, schema::FIELDS).unwrap());
+    assert_eq!(3, read_i32(metadata, fields));
+    let category = deref(metadata, fields + 12);
+    assert_eq!("category", read_string(metadata, deref(metadata, field(metadata, category, field::NAME).unwrap())));
+    assert_eq!(TYPE_UTF8, read_u8(metadata, field(metadata, category, field::TYPE_TYPE).unwrap()));
+    let encoding = deref(metadata, field(metadata, category, field::DICTIONARY).unwrap());
+    assert_eq!(0, read_i64(metadata, field(metadata, encoding, dictionary_encoding::ID).unwrap()));
+
+    // dictionary of 2 strings
+    let metadata = &messages[1].metadata;
+    let data = deref(metadata, field(metadata, messages[1].header, dictionary_batch::DATA).unwrap());
+    assert_eq!(2, read_i64(metadata, field(metadata, data, record_batch::LENGTH).unwrap()));
+    let (_, buffers) = nodes_and_buffers(metadata, data);
+    assert_eq!(b"evenodd", &messages[1].body[buffers[2].0 as usize..(buffers[2].0 + buffers[2].1) as usize]);
+
+    let metadata = &messages[2].metadata;
+    let (nodes, buffers) = nodes_and_buffers(metadata, messages[2].header);
+    assert_eq!(vec![(20, 0), (20, 7), (20, 0)], nodes);
+    // validity, values / validity, offsets, data / validity, indices
+    assert_eq!(7, buffers.len());
+    assert_eq!((0, 0), buffers[0]);
+    assert_eq!(80, buffers[1].1);
+    for &(offset, _) in &buffers {
+      assert_eq!(0, offset % 8);
+    }
+
+    // the sliced batch is written without values out of the slice
+    let metadata = &messages[3].metadata;
+    let (nodes, buffers) = nodes_and_buffers(metadata, messages[3].header);
+    assert_eq!(10, read_i64(metadata, field(metadata, messages[3].header, record_batch::LENGTH).unwrap()));
+    assert_eq!(vec![(10, 0), (10, 4), (10, 0)], nodes);
+    let body = &messages[3].body;
+    assert_eq!(3, read_i32(body, buffers[1].0 as usize));
+    let offsets = buffers[3].0 as usize;
+    assert_eq!(0, read_i32(body, offsets));
+    assert_eq!(0, read_i32(body, offsets + 4));
+    assert_eq!(5, read_i32(body, offsets + 8));
+    assert_eq!(b"name4", &body[buffers[4].0 as usize..buffers[4].0 as usize + 5]);
+    assert_eq!(10, buffers[6].1);
+  }
+
+  #[test]
+  fn test_write_year_month_intervals() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let ty = Ty::Interval { unit: IntervalUnit::YearMonth };
+    let mut intervals = ArrayBuilder::new_fixed_width(ty.clone(), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for months in &[1i32, 14, -3] {
+      intervals.append(*months).unwrap();
+    }
+    let schema = Arc::new(Schema::new(vec![Field::new(String::from("interval"), ty)]));
+    let batch = RecordBatch::new(schema.clone(), vec![Array::from(intervals)]).unwrap();
+
+    let mut writer = StreamWriter::new(Vec::new(), schema).unwrap();
+    writer.write(&batch).unwrap();
+    let stream = writer.finish().unwrap();
+
+    let messages = messages(&stream);
+    let (nodes, buffers) = nodes_and_buffers(&messages[1].metadata, messages[1].header);
+    assert_eq!(vec![(3, 0)], nodes);
+    // year-month intervals are months in 32 bits
+    assert_eq!(12, buffers[1].1);
+    assert_eq!(14, read_i32(&messages[1].body, buffers[1].0 as usize + 4));
+  }
+
+  #[test]
+  fn test_write_trailing_nulls() {
+    use array::ArrayData;
+    use buffer::{ForeignBuffer, SharedBuffer};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut ints = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut bools = ArrayBuilder::new_fixed_width(Ty::Bool, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    ints.append(1).unwrap();
+    bools.append(true).unwrap();
+    for _ in 0..1000 {
+      ints.append_null().unwrap();
+      bools.append_null().unwrap();
+    }
+    let schema = Arc::new(Schema::new(vec![
+      Field::new(String::from("int"), Ty::Int32),
+      Field::new(String::from("bool"), Ty::Bool)
+    ]));
+    let batch = RecordBatch::new(schema.clone(), vec![Array::from(ints), Array::from(bools)]).unwrap();
+
+    let mut writer = StreamWriter::new(Vec::new(), schema.clone()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.write(&batch.slice(990, 11)).unwrap();
+    let stream = writer.finish().unwrap();
+
+    let messages = messages(&stream);
+    let (nodes, buffers) = nodes_and_buffers(&messages[1].metadata, messages[1].header);
+    assert_eq!(vec![(1001, 1000), (1001, 1000)], nodes);
+    assert_eq!(4004, buffers[1].1);
+    assert_eq!(126, buffers[3].1);
+    assert_eq!(1, read_i32(&messages[1].body, buffers[1].0 as usize));
+    assert_eq!(0, read_i32(&messages[1].body, buffers[1].0 as usize + 4000));
+    let (nodes, buffers) = nodes_and_buffers(&messages[2].metadata, messages[2].header);
+    assert_eq!(vec![(11, 11), (11, 11)], nodes);
+    assert_eq!(44, buffers[1].1);
+
+    // buffers shorter than their values are rejected
+    let values = SharedBuffer::new(ForeignBuffer::from_vec(vec![1i32]));
+    let data = unsafe { ArrayData::new_unchecked(Ty::Int32, 1001, 0, 0, vec![None, Some(values)], Vec::new()) };
+    let batch = RecordBatch::new(schema.clone(), vec![Array::new(Arc::new(data)), batch.column(1).clone()]).unwrap();
+    let mut writer = StreamWriter::new(Vec::new(), schema).unwrap();
+    assert_eq!(&StatusCode::Invalid, writer.write(&batch).unwrap_err().code());
+  }
+
+  #[test]
+  fn test_write_invalid_stream() {
+    let schema = Arc::new(Schema::new(vec![Field::new(String::from("nulls"), Ty::NA)]));
+    let mut writer = StreamWriter::new(Vec::new(), schema).unwrap();
+    let other_schema = Arc::new(Schema::new(vec![Field::new(String::from("other"), Ty::NA)]));
+    let batch = RecordBatch::new(other_schema, vec![Array::from(ArrayBuilder::null(3))]).unwrap();
+    assert_eq!(&StatusCode::Invalid, writer.write(&batch).unwrap_err().code());
+  }
+}
@@ -1,9 +1,3 @@
-#![feature(integer_atomics)]
-#![feature(box_syntax, box_patterns)]
-#![feature(pointer_methods)]
-#![feature(core_intrinsics)]
-#![feature(const_size_of)]
-
 extern crate libc;
 extern crate num;
 extern crate rand;
@@ -13,25 +7,28 @@ pub mod array;
 pub mod memory_pool;
 pub mod buffer;
 pub mod builder;
+pub mod chunked_array;
+pub mod record_batch;
+pub mod table;
+pub mod ipc;
 
 #[cfg(test)]
 mod tests {
   use std::sync::Arc;
-  use std::cell::RefCell;
-  use common::status::ArrowError;
+  use common::status::{ArrowError, StatusCode};
   use memory_pool::{DefaultMemoryPool, MemoryPool};
 
   #[test]
   fn test_drop_empty_pool_buffer() {
     use buffer::PoolBuffer;
-    let mut buffer = PoolBuffer::new(Arc::new(RefCell::new(DefaultMemoryPool::new())));
+    let mut buffer = PoolBuffer::new(Arc::new(DefaultMemoryPool::new()));
   }
 
   #[test]
   fn test_pool_buffer() {
     use buffer::{Buffer, MutableBuffer, ResizableBuffer, PoolBuffer};
 
-    let mut buffer = PoolBuffer::new(Arc::new(RefCell::new(DefaultMemoryPool::new())));
+    let mut buffer = PoolBuffer::new(Arc::new(DefaultMemoryPool::new()));
     buffer.reserve(100).unwrap();
     assert_eq!(128, buffer.capacity());
     assert_eq!(0, buffer.size());
@@ -45,7 +42,7 @@ mod tests {
   fn test_buffer_builder() {
     use buffer::{Buffer, MutableBuffer, ResizableBuffer, PoolBuffer, BufferBuilder, TypedBufferBuilder};
 
-    let mut buffer_builder = BufferBuilder::new(Arc::new(RefCell::new(DefaultMemoryPool::new())));
+    let mut buffer_builder = BufferBuilder::new(Arc::new(DefaultMemoryPool::new()));
     for i in 0..100 {
       buffer_builder.append_typed_val(i + 10);
     }
@@ -55,16 +52,234 @@ mod tests {
     assert_eq!(512, buffer.capacity());
   }
 
-//  #[test]
-//  fn test_array_data() {
-//    use common::ty::DataType;
-//    use array::ArrayData;
-//    use buffer::PoolBuffer;
-//
-//    let mut pool = Arc::new(RefCell::new(DefaultMemoryPool::new()));
-//    let data = ArrayData::new(DataType::int32(), 100, 0, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
-//
-//    assert_eq!(&DataType::int32(), data.data_type());
-//    assert_eq!(100, data.len());
-//  }
+  #[test]
+  fn test_shared_buffer() {
+    use buffer::{Buffer, BufferBuilder, TypedBufferBuilder, SharedBuffer};
+
+    let mut buffer_builder = BufferBuilder::new(Arc::new(DefaultMemoryPool::new()));
+    for i in 0..100 {
+      buffer_builder.append_typed_val(i as u8).unwrap();
+    }
+    let buffer = SharedBuffer::new(buffer_builder.finish());
+    assert_eq!(100, buffer.size());
+    assert_eq!(128, buffer.capacity());
+    assert!(buffer.parent().is_none());
+
+    let slice = buffer.slice(10, 20);
+    assert_eq!(20, slice.size());
+    assert_eq!(20, slice.capacity());
+    assert!(slice.parent().is_some());
+    assert!(slice.shares_memory_with(&buffer));
+    assert_eq!(10, unsafe { *slice.data() });
+
+    // the slice keeps the memory alive after its parent is dropped
+    drop(buffer);
+    let slice = slice.slice(5, 5);
+    assert_eq!(15, unsafe { *slice.data() });
+    // a slice of a slice shares the parent of the slice
+    assert_eq!(Some(0), slice.parent().map(|parent| unsafe { *parent.data() }));
+    assert_eq!(Some(100), slice.parent().map(|parent| parent.size()));
+    assert!(slice.parent().and_then(|parent| parent.parent()).is_none());
+
+    let cloned = slice.clone();
+    assert_eq!(slice, cloned);
+    assert!(cloned.shares_memory_with(&slice));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_shared_buffer_slice_out_of_range() {
+    use buffer::{PoolBuffer, SharedBuffer};
+
+    let buffer = SharedBuffer::new(PoolBuffer::new(Arc::new(DefaultMemoryPool::new())));
+    buffer.slice(0, 1);
+  }
+
+  #[test]
+  fn test_foreign_buffer() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use common::ty::Ty;
+    use array::{Array, ArrayData, ArrowSlice};
+    use buffer::{Buffer, ForeignBuffer, SharedBuffer};
+
+    let mut values: Vec<i64> = Vec::with_capacity(200);
+    values.extend(0..100);
+    let data = values.as_ptr() as *const u8;
+    let buffer = ForeignBuffer::from_vec(values);
+    assert_eq!(data, buffer.data());
+    assert_eq!(800, buffer.size());
+    assert_eq!(1600, buffer.capacity());
+
+    // vectors can be converted into those of other types of the same alignment
+    let buffer = match buffer.into_vec::<i32>() {
+      Ok(_) => panic!("i32 is not aligned to 8 bytes"),
+      Err(buffer) => buffer
+    };
+    let values = buffer.into_vec::<u64>().unwrap();
+    assert_eq!(200, values.capacity());
+    assert_eq!(99, values[99]);
+
+    let buffer = SharedBuffer::new(ForeignBuffer::from_vec(values));
+    let array = Array::new(Arc::new(ArrayData::new(Ty::UInt64, 100, 0, 0, vec![None, Some(buffer.clone())], Vec::new()).unwrap()));
+    assert_eq!(&(0..100).collect::<Vec<u64>>()[..], ArrowSlice::<u64>::values(&array));
+
+    // buffers shared by others and slices can't be converted
+    let buffer = buffer.into_vec::<u64>().unwrap_err();
+    assert!(buffer.slice(0, 8).into_vec::<u64>().is_err());
+    drop(array);
+    assert_eq!(data, buffer.into_vec::<u64>().unwrap().as_ptr() as *const u8);
+
+    let buffer = ForeignBuffer::from_boxed_slice(vec![1u8, 2, 3].into_boxed_slice());
+    assert_eq!(3, buffer.capacity());
+    assert_eq!(vec![1, 2, 3], buffer.into_vec::<u8>().unwrap());
+
+    static BYTES: [u8; 4] = [1, 2, 3, 4];
+    let buffer = SharedBuffer::new(ForeignBuffer::from_static(&BYTES));
+    assert_eq!(BYTES.as_ptr(), buffer.data());
+    assert!(buffer.into_vec::<u8>().is_err());
+
+    static RELEASED: AtomicBool = AtomicBool::new(false);
+    let buffer = SharedBuffer::new(unsafe {
+      ForeignBuffer::from_raw_parts(BYTES.as_ptr(), 4, Box::new(|| RELEASED.store(true, Ordering::SeqCst)))
+    });
+    let slice = buffer.slice(1, 2);
+    drop(buffer);
+    assert!(!RELEASED.load(Ordering::SeqCst));
+    assert_eq!(2, unsafe { *slice.data() });
+    drop(slice);
+    assert!(RELEASED.load(Ordering::SeqCst));
+  }
+
+  #[test]
+  fn test_mmap_buffer() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use common::ty::Ty;
+    use array::{Array, ArrayData, ArrowValue, ArrowSlice};
+    use buffer::{Buffer, MmapBuffer, SharedBuffer};
+
+    let path = env::temp_dir().join(format!("iron-arrow-test-mmap-buffer-{}", std::process::id()));
+    {
+      let mut file = File::create(&path).unwrap();
+      for i in 0..10000i32 {
+        file.write_all(&unsafe { std::mem::transmute::<i32, [u8; 4]>(i) }).unwrap();
+      }
+    }
+
+    // the region doesn't need to start at a page boundary
+    let file = File::open(&path).unwrap();
+    let buffer = SharedBuffer::new(unsafe { MmapBuffer::new(&file, 4 * 1000, 4 * 100) }.unwrap());
+    drop(file);
+    assert_eq!(400, buffer.size());
+    assert_eq!(400, buffer.capacity());
+
+    let data = ArrayData::new(Ty::Int32, 50, 0, 0, vec![None, Some(buffer.slice(4 * 50, 4 * 50))], Vec::new()).unwrap();
+    drop(buffer);
+    let array = Array::new(Arc::new(data));
+    assert_eq!(&(1050..1100).collect::<Vec<i32>>()[..], ArrowSlice::<i32>::values(&array));
+
+    let whole = unsafe { MmapBuffer::from_path(&path) }.unwrap();
+    assert_eq!(40000, whole.size());
+    assert_eq!(9999, unsafe { *(whole.data() as *const i32).offset(9999) });
+
+    let empty = unsafe { MmapBuffer::new(&File::open(&path).unwrap(), 40000, 0) }.unwrap();
+    assert_eq!(0, empty.size());
+    assert!(!empty.data().is_null());
+    assert_eq!(0, empty.data() as usize % 8);
+
+    let e = unsafe { MmapBuffer::new(&File::open(&path).unwrap(), 39000, 2000) }.unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+    let e = unsafe { MmapBuffer::new(&File::open(&path).unwrap(), 8, i64::max_value()) }.unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+    // the region must be aligned to read values from it
+    let e = unsafe { MmapBuffer::new(&File::open(&path).unwrap(), 4 * 1001, 4 * 100) }.unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+    let e = unsafe { MmapBuffer::from_path(path.with_extension("missing")) }.unwrap_err();
+    assert_eq!(&StatusCode::IOError, e.code());
+
+    fs::remove_file(&path).unwrap();
+    // the mapping is still readable after the file is removed
+    assert_eq!(1050, ArrowValue::<i32>::value(&array, 0));
+  }
+
+  #[test]
+  fn test_array_data() {
+    use common::ty::Ty;
+    use array::{Array, ArrayData, ArrowValue, ArrowSlice, UNKNOWN_NULL_COUNT};
+    use buffer::{BufferBuilder, TypedBufferBuilder, SharedBuffer};
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let mut buffer_builder = BufferBuilder::new(pool.clone());
+    for i in 0..100 {
+      buffer_builder.append_typed_val(i as i32).unwrap();
+    }
+    let mut null_bitmap_builder = BufferBuilder::new(pool.clone());
+    for _ in 0..13 {
+      // every 8th value is null
+      null_bitmap_builder.append_typed_val(0xfeu8).unwrap();
+    }
+
+    let buffers = vec![Some(SharedBuffer::new(null_bitmap_builder.finish())), Some(SharedBuffer::new(buffer_builder.finish()))];
+    let data = ArrayData::new(Ty::Int32, 100, 0, UNKNOWN_NULL_COUNT, buffers, Vec::new()).unwrap();
+    assert_eq!(&Ty::Int32, data.ty());
+    assert_eq!(100, data.len());
+    assert_eq!(13, data.null_count());
+
+    let array = Array::new(Arc::new(data));
+    assert!(array.is_null(8));
+    assert_eq!(42, ArrowValue::<i32>::value(&array, 42));
+
+    // clones share the data
+    let cloned = array.clone();
+    assert!(Arc::ptr_eq(array.data(), cloned.data()));
+
+    // arrays without a validity buffer don't have nulls
+    let buffers = vec![None, array.data().buffers()[1].clone()];
+    let array = Array::new(Arc::new(ArrayData::new(Ty::Int32, 50, 10, UNKNOWN_NULL_COUNT, buffers, Vec::new()).unwrap()));
+    assert_eq!(0, array.null_count());
+    assert!(array.is_valid(0));
+    assert_eq!(10, ArrowValue::<i32>::value(&array, 0));
+
+    // arrays can be built from a slice of a buffer
+    let values = array.data().buffers()[1].as_ref().unwrap().slice(40 * 4, 10 * 4);
+    let array = Array::new(Arc::new(ArrayData::new(Ty::Int32, 10, 0, 0, vec![None, Some(values)], Vec::new()).unwrap()));
+    assert_eq!(&(40..50).collect::<Vec<i32>>()[..], ArrowSlice::<i32>::values(&array));
+  }
+
+  #[test]
+  fn test_build_arrays_in_parallel() {
+    use std::thread;
+    use common::ty::Ty;
+    use array::{Array, ArrowValue, ArrowSlice};
+    use buffer::PoolBuffer;
+    use builder::{ArrayBuilder, Append};
+
+    let pool: Arc<MemoryPool> = Arc::new(DefaultMemoryPool::new());
+    let handles: Vec<_> = (0..4).map(|i| {
+      let pool = pool.clone();
+      thread::spawn(move || {
+        let mut builder = ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool)).unwrap();
+        for j in 0..1000 {
+          builder.append((i * 1000 + j) as i64).unwrap();
+        }
+        Array::from(builder)
+      })
+    }).collect();
+    let arrays: Vec<Array> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+
+    assert!(pool.bytes_allocated() > 0);
+    for (i, array) in arrays.iter().enumerate() {
+      assert_eq!(1000, array.len());
+      assert_eq!((i * 1000) as i64, ArrowValue::<i64>::value(array, 0));
+    }
+
+    // arrays are shared by threads without copying
+    let array = arrays[3].clone();
+    let sum = thread::spawn(move || ArrowSlice::<i64>::values(&array).iter().sum::<i64>()).join().unwrap();
+    assert_eq!((3000..4000).sum::<i64>(), sum);
+
+    drop(arrays);
+    assert_eq!(0, pool.bytes_allocated());
+  }
 }
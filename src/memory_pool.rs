@@ -2,16 +2,24 @@ use common::status::{ArrowError, StatusCode};
 
 use std::cmp;
 use std::mem;
-use std::sync::{Arc, Mutex};
+use std::ptr;
+use std::thread;
+use std::sync::{Arc, Weak, Mutex, MutexGuard};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::backtrace::Backtrace;
+use std::alloc::{self, Layout};
+use std::fmt::{Debug, Formatter, Error};
 use std::sync::atomic::{AtomicI64, Ordering};
 use libc;
 
-pub trait MemoryPool {
-  fn allocate(&mut self, size: i64) -> Result<*const u8, ArrowError>;
+/// Memory pools are shared by the buffers allocated from them, which can be sent to other threads.
+/// Implementations must be safe to call from multiple threads at the same time.
+pub trait MemoryPool: Send + Sync {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError>;
 
-  fn reallocate(&mut self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError>;
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError>;
 
-  fn free(&mut self, page: *const u8, size: i64);
+  fn free(&self, page: *const u8, size: i64);
 
   fn bytes_allocated(&self) -> i64;
 
@@ -33,74 +41,505 @@ impl DefaultMemoryPool {
       max_memory: AtomicI64::new(0)
     }
   }
+
+  fn update_max_memory(&self) {
+    let _locked = self.lock.lock().unwrap();
+    let cur_alloc = self.bytes_allocated.load(Ordering::Relaxed);
+    if self.max_memory.load(Ordering::Relaxed) < cur_alloc {
+      self.max_memory.store(cur_alloc, Ordering::Relaxed);
+    }
+  }
 }
 
 impl MemoryPool for DefaultMemoryPool {
-  fn allocate(&mut self, size: i64) -> Result<*const u8, ArrowError> {
-    match allocate_aligned(size) {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    match allocate_page(size) {
       Ok(page) => {
 //        println!("allocated memory of {} at {:?}", size, page);
         self.bytes_allocated.fetch_add(size, Ordering::Relaxed);
+        self.update_max_memory();
+
+        Ok(page)
+      },
+      Err(e) => Err(e)
+    }
+  }
 
-        {
-          let _locked = self.lock.lock().unwrap();
-          let cur_max = self.max_memory.get_mut();
-          let cur_alloc = self.bytes_allocated.load(Ordering::Relaxed);
+  /// Resizes the page without copying if possible. Mapped pages are remapped, heap pages are kept
+  /// as they are when they shrink or when their allocation has room for the new size. Otherwise,
+  /// the values are copied into a new page.
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    let result = if old_size == 0 {
+      // the page is not allocated yet
+      allocate_page(new_size)
+    } else if is_mapped(old_size) && is_mapped(new_size) {
+      remap_pages(page, old_size, new_size)
+    } else if !is_mapped(old_size) && !is_mapped(new_size) && new_size <= old_size {
+      Ok(page)
+    } else if !is_mapped(old_size) && !is_mapped(new_size) {
+      realloc_page(page, old_size, new_size)
+    } else {
+      copy_page(page, old_size, new_size)
+    };
 
-          if *cur_max < cur_alloc {
-            *cur_max = cur_alloc;
-          }
+    match result {
+      Ok(new_page) => {
+        self.bytes_allocated.fetch_add(new_size - old_size, Ordering::Relaxed);
+        self.update_max_memory();
+        Ok(new_page)
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn free(&self, page: *const u8, size: i64) {
+    // TODO
+    if self.bytes_allocated() < size {
+      panic!("allocated bytes[{}] is less than free size[{}]", self.bytes_allocated(), size);
+    } else {
+//      println!("try freeing memory of {} from {:?}", size, page);
+      free_page(page, size);
+      self.bytes_allocated.fetch_sub(size, Ordering::Relaxed);
+    }
+  }
+
+  fn bytes_allocated(&self) -> i64 {
+    self.bytes_allocated.load(Ordering::Relaxed)
+  }
+
+  fn max_memory(&self) -> i64 {
+    self.max_memory.load(Ordering::Relaxed)
+  }
+}
+
+/// A memory pool which allocates from Rust's global allocator, so that it uses whatever allocator
+/// is registered with `#[global_allocator]`. Pages are aligned to `alignment()` bytes, and
+/// reallocating a page keeps its alignment.
+#[derive(Debug)]
+pub struct GlobalAllocMemoryPool {
+  alignment: usize,
+  bytes_allocated: AtomicI64,
+  max_memory: AtomicI64
+}
+
+impl GlobalAllocMemoryPool {
+  pub fn new() -> GlobalAllocMemoryPool {
+    GlobalAllocMemoryPool::with_alignment(ALIGNMENT)
+  }
+
+  /// Panics if `alignment` is not a power of two.
+  pub fn with_alignment(alignment: usize) -> GlobalAllocMemoryPool {
+    if !alignment.is_power_of_two() {
+      panic!("alignment[{}] is not a power of two", alignment);
+    }
+    GlobalAllocMemoryPool {
+      alignment,
+      bytes_allocated: AtomicI64::new(0),
+      max_memory: AtomicI64::new(0)
+    }
+  }
+
+  #[inline]
+  pub fn alignment(&self) -> usize {
+    self.alignment
+  }
+
+  fn layout(&self, size: i64) -> Result<Layout, ArrowError> {
+    match Layout::from_size_align(size as usize, self.alignment) {
+      Ok(layout) => Ok(layout),
+      Err(_) => Err(ArrowError::invalid(format!("invalid layout of size {} and alignment {}", size, self.alignment)))
+    }
+  }
+
+  // the global allocator doesn't allow empty allocations
+  #[inline]
+  fn empty_page(&self) -> *const u8 {
+    self.alignment as *const u8
+  }
+}
+
+impl MemoryPool for GlobalAllocMemoryPool {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    if size == 0 {
+      return Ok(self.empty_page());
+    }
+    match self.layout(size) {
+      Ok(layout) => {
+        let page = unsafe { alloc::alloc(layout) };
+        if page.is_null() {
+          Err(ArrowError::out_of_memory(format!("allocation of size {} failed", size)))
+        } else {
+          let cur_alloc = self.bytes_allocated.fetch_add(size, Ordering::Relaxed);
+          self.max_memory.fetch_max(cur_alloc + size, Ordering::Relaxed);
+          Ok(page)
         }
+      },
+      Err(e) => Err(e)
+    }
+  }
 
-        Ok(page)
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    if old_size == 0 {
+      return self.allocate(new_size);
+    }
+    if new_size == 0 {
+      self.free(page, old_size);
+      return Ok(self.empty_page());
+    }
+    match self.layout(old_size) {
+      Ok(layout) => {
+        let new_page = unsafe { alloc::realloc(page as *mut u8, layout, new_size as usize) };
+        if new_page.is_null() {
+          Err(ArrowError::out_of_memory(format!("reallocation of size {} failed", new_size)))
+        } else {
+          let cur_alloc = self.bytes_allocated.fetch_add(new_size - old_size, Ordering::Relaxed);
+          self.max_memory.fetch_max(cur_alloc + new_size - old_size, Ordering::Relaxed);
+          Ok(new_page)
+        }
       },
       Err(e) => Err(e)
     }
   }
 
-  fn reallocate(&mut self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
-    match allocate_aligned(new_size) {
-      Ok(new_page) => {
-        unsafe {
-          let p_new_page = mem::transmute::<*const u8, *mut libc::c_void>(new_page);
-          let p_old_page = mem::transmute::<*const u8, *mut libc::c_void>(page);
-          if old_size > 0 {
-            let copy_len = cmp::min(new_size, old_size) as usize;
-            libc::memcpy(p_new_page, p_old_page, copy_len);
-            libc::free(p_old_page);
-          }
-          self.bytes_allocated.fetch_add(new_size - old_size, Ordering::Relaxed);
+  fn free(&self, page: *const u8, size: i64) {
+    if self.bytes_allocated() < size {
+      panic!("allocated bytes[{}] is less than free size[{}]", self.bytes_allocated(), size);
+    }
+    if size > 0 {
+      match self.layout(size) {
+        Ok(layout) => unsafe { alloc::dealloc(page as *mut u8, layout) },
+        Err(e) => panic!("cannot free: {:?}", e)
+      }
+      self.bytes_allocated.fetch_sub(size, Ordering::Relaxed);
+    }
+  }
+
+  fn bytes_allocated(&self) -> i64 {
+    self.bytes_allocated.load(Ordering::Relaxed)
+  }
 
-          {
-            let _locked = self.lock.lock().unwrap();
-            let cur_max = self.max_memory.get_mut();
-            let cur_alloc = self.bytes_allocated.load(Ordering::Relaxed);
+  fn max_memory(&self) -> i64 {
+    self.max_memory.load(Ordering::Relaxed)
+  }
+}
 
-            if *cur_max < cur_alloc {
-              *cur_max = cur_alloc;
+/// A memory pool which maps anonymous pages for every allocation. It is meant for very large
+/// columns, which grow without copying by remapping their pages. Every allocation takes at least
+/// a page, so `DefaultMemoryPool` is a better fit for small ones.
+///
+/// Pages are aligned to the system page size or to `alignment()` bytes if it's larger. Pools created
+/// by `with_huge_pages()` align pages to huge pages, and advise the kernel to back them with
+/// transparent huge pages.
+#[derive(Debug)]
+pub struct MmapMemoryPool {
+  alignment: usize,
+  huge_pages: bool,
+  bytes_allocated: AtomicI64,
+  max_memory: AtomicI64
+}
+
+const HUGE_PAGE_SIZE: usize = 1 << 21;
+
+impl MmapMemoryPool {
+  pub fn new() -> MmapMemoryPool {
+    MmapMemoryPool::with_alignment(page_size())
+  }
+
+  /// Panics if `alignment` is not a power of two.
+  pub fn with_alignment(alignment: usize) -> MmapMemoryPool {
+    if !alignment.is_power_of_two() {
+      panic!("alignment[{}] is not a power of two", alignment);
+    }
+    MmapMemoryPool {
+      alignment: cmp::max(alignment, page_size()),
+      huge_pages: false,
+      bytes_allocated: AtomicI64::new(0),
+      max_memory: AtomicI64::new(0)
+    }
+  }
+
+  pub fn with_huge_pages() -> MmapMemoryPool {
+    let mut pool = MmapMemoryPool::with_alignment(HUGE_PAGE_SIZE);
+    pool.huge_pages = true;
+    pool
+  }
+
+  #[inline]
+  pub fn alignment(&self) -> usize {
+    self.alignment
+  }
+
+  #[inline]
+  pub fn uses_huge_pages(&self) -> bool {
+    self.huge_pages
+  }
+
+  // mmap doesn't allow empty mappings
+  #[inline]
+  fn empty_page(&self) -> *const u8 {
+    self.alignment as *const u8
+  }
+
+  fn map(&self, size: i64) -> Result<*const u8, ArrowError> {
+    let result = if self.alignment <= page_size() {
+      map_pages(size)
+    } else {
+      // map enough pages to find an aligned range in them, and unmap the rest
+      let len = round_up(size as usize, page_size());
+      match map_pages((len + self.alignment) as i64) {
+        Ok(page) => {
+          let start = page as usize;
+          let aligned = round_up(start, self.alignment);
+          unsafe {
+            if aligned > start {
+              libc::munmap(start as *mut libc::c_void, aligned - start);
             }
+            libc::munmap((aligned + len) as *mut libc::c_void, self.alignment - (aligned - start));
           }
+          Ok(aligned as *const u8)
+        },
+        Err(e) => Err(e)
+      }
+    };
+    match result {
+      Ok(page) => {
+        self.advise(page, size);
+        Ok(page)
+      },
+      Err(e) => Err(e)
+    }
+  }
 
-          Ok(new_page)
+  #[cfg(target_os = "linux")]
+  fn advise(&self, page: *const u8, size: i64) {
+    if self.huge_pages {
+      // this is only a hint, so failures are ignored
+      unsafe { libc::madvise(page as *mut libc::c_void, size as usize, libc::MADV_HUGEPAGE); }
+    }
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn advise(&self, _page: *const u8, _size: i64) {
+  }
+
+  #[cfg(target_os = "linux")]
+  fn remap(&self, page: *const u8, old_size: i64, new_size: i64) -> Option<*const u8> {
+    // moved pages are only guaranteed to be aligned to the page size
+    let flags = if self.alignment <= page_size() { libc::MREMAP_MAYMOVE } else { 0 };
+    let new_page = unsafe {
+      libc::mremap(page as *mut libc::c_void, old_size as usize, new_size as usize, flags)
+    };
+    if new_page == libc::MAP_FAILED {
+      None
+    } else {
+      self.advise(new_page as *const u8, new_size);
+      Some(new_page as *const u8)
+    }
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn remap(&self, _page: *const u8, _old_size: i64, _new_size: i64) -> Option<*const u8> {
+    None
+  }
+}
+
+impl MemoryPool for MmapMemoryPool {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    if size == 0 {
+      return Ok(self.empty_page());
+    }
+    match self.map(size) {
+      Ok(page) => {
+        let cur_alloc = self.bytes_allocated.fetch_add(size, Ordering::Relaxed);
+        self.max_memory.fetch_max(cur_alloc + size, Ordering::Relaxed);
+        Ok(page)
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    if old_size == 0 {
+      return self.allocate(new_size);
+    }
+    if new_size == 0 {
+      self.free(page, old_size);
+      return Ok(self.empty_page());
+    }
+    let result = match self.remap(page, old_size, new_size) {
+      Some(new_page) => Ok(new_page),
+      None => {
+        match self.map(new_size) {
+          Ok(new_page) => {
+            unsafe {
+              libc::memcpy(new_page as *mut libc::c_void, page as *const libc::c_void, cmp::min(old_size, new_size) as usize);
+              libc::munmap(page as *mut libc::c_void, old_size as usize);
+            }
+            Ok(new_page)
+          },
+          Err(e) => Err(e)
         }
+      }
+    };
+    match result {
+      Ok(new_page) => {
+        let cur_alloc = self.bytes_allocated.fetch_add(new_size - old_size, Ordering::Relaxed);
+        self.max_memory.fetch_max(cur_alloc + new_size - old_size, Ordering::Relaxed);
+        Ok(new_page)
       },
       Err(e) => Err(e)
     }
   }
 
-  fn free(&mut self, page: *const u8, size: i64) {
-    // TODO
+  fn free(&self, page: *const u8, size: i64) {
     if self.bytes_allocated() < size {
       panic!("allocated bytes[{}] is less than free size[{}]", self.bytes_allocated(), size);
-    } else {
-//      println!("try freeing memory of {} from {:?}", size, page);
-      unsafe {
-        libc::free(mem::transmute::<*const u8, *mut libc::c_void>(page));
-        self.bytes_allocated.fetch_sub(size, Ordering::Relaxed);
+    }
+    if size > 0 {
+      unsafe { libc::munmap(page as *mut libc::c_void, size as usize); }
+      self.bytes_allocated.fetch_sub(size, Ordering::Relaxed);
+    }
+  }
+
+  fn bytes_allocated(&self) -> i64 {
+    self.bytes_allocated.load(Ordering::Relaxed)
+  }
+
+  fn max_memory(&self) -> i64 {
+    self.max_memory.load(Ordering::Relaxed)
+  }
+}
+
+/// A memory pool which allocates from its parent pool while keeping its own usage within `limit`
+/// bytes. Allocations over the limit fail with `StatusCode::OutOfMemory`.
+///
+/// Each consumer of memory, such as a query operator, can have its own named child pool whose
+/// usage is reported and capped individually. Allocations of a child pool count toward the limits
+/// of all its ancestors.
+pub struct LimitedMemoryPool {
+  name: String,
+  parent: Arc<MemoryPool>,
+  limit: i64,
+  bytes_allocated: AtomicI64,
+  max_memory: AtomicI64,
+  children: Mutex<Vec<Weak<LimitedMemoryPool>>>
+}
+
+impl LimitedMemoryPool {
+  pub fn new(name: &str, parent: Arc<MemoryPool>, limit: i64) -> Arc<LimitedMemoryPool> {
+    Arc::new(LimitedMemoryPool {
+      name: String::from(name),
+      parent,
+      limit,
+      bytes_allocated: AtomicI64::new(0),
+      max_memory: AtomicI64::new(0),
+      children: Mutex::new(Vec::new())
+    })
+  }
+
+  /// Creates a child pool of `parent`. The child can be dropped while its memory is still in use,
+  /// but it's not reported as a child of `parent` anymore.
+  pub fn child(parent: &Arc<LimitedMemoryPool>, name: &str, limit: i64) -> Arc<LimitedMemoryPool> {
+    let child = LimitedMemoryPool::new(name, parent.clone(), limit);
+    let mut children = parent.children.lock().unwrap();
+    children.retain(|child| child.upgrade().is_some());
+    children.push(Arc::downgrade(&child));
+    child
+  }
+
+  #[inline]
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  #[inline]
+  pub fn limit(&self) -> i64 {
+    self.limit
+  }
+
+  /// Returns the number of bytes which can be allocated from this pool without exceeding its own
+  /// limit. Allocations can still fail if an ancestor pool is out of memory.
+  #[inline]
+  pub fn available(&self) -> i64 {
+    self.limit - self.bytes_allocated()
+  }
+
+  pub fn children(&self) -> Vec<Arc<LimitedMemoryPool>> {
+    self.children.lock().unwrap().iter().filter_map(|child| child.upgrade()).collect()
+  }
+
+  fn reserve(&self, size: i64) -> Result<(), ArrowError> {
+    let mut cur_alloc = self.bytes_allocated.load(Ordering::Relaxed);
+    loop {
+      if cur_alloc + size > self.limit {
+        return Err(ArrowError::out_of_memory(
+          format!("[{}] cannot allocate [{}] bytes: [{}] bytes are in use out of the limit [{}]", self.name, size, cur_alloc, self.limit)
+        ));
+      }
+      match self.bytes_allocated.compare_exchange_weak(cur_alloc, cur_alloc + size, Ordering::Relaxed, Ordering::Relaxed) {
+        Ok(_) => {
+          self.max_memory.fetch_max(cur_alloc + size, Ordering::Relaxed);
+          return Ok(());
+        },
+        Err(actual) => cur_alloc = actual
       }
     }
   }
 
+  #[inline]
+  fn release(&self, size: i64) {
+    self.bytes_allocated.fetch_sub(size, Ordering::Relaxed);
+  }
+}
+
+impl MemoryPool for LimitedMemoryPool {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    match self.reserve(size) {
+      Ok(_) => {
+        match self.parent.allocate(size) {
+          Ok(page) => Ok(page),
+          Err(e) => {
+            self.release(size);
+            Err(e)
+          }
+        }
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    let delta = new_size - old_size;
+    let reserved = if delta > 0 { self.reserve(delta) } else { Ok(()) };
+    match reserved {
+      Ok(_) => {
+        match self.parent.reallocate(old_size, new_size, page) {
+          Ok(new_page) => {
+            if delta < 0 {
+              self.release(-delta);
+            }
+            Ok(new_page)
+          },
+          Err(e) => {
+            if delta > 0 {
+              self.release(delta);
+            }
+            Err(e)
+          }
+        }
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn free(&self, page: *const u8, size: i64) {
+    if self.bytes_allocated() < size {
+      panic!("allocated bytes[{}] of [{}] is less than free size[{}]", self.bytes_allocated(), self.name, size);
+    }
+    self.parent.free(page, size);
+    self.release(size);
+  }
+
   fn bytes_allocated(&self) -> i64 {
     self.bytes_allocated.load(Ordering::Relaxed)
   }
@@ -110,8 +549,342 @@ impl MemoryPool for DefaultMemoryPool {
   }
 }
 
+impl Debug for LimitedMemoryPool {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+    f.debug_struct("LimitedMemoryPool")
+      .field("name", &self.name)
+      .field("limit", &self.limit)
+      .field("bytes_allocated", &self.bytes_allocated())
+      .field("max_memory", &self.max_memory())
+      .field("children", &self.children())
+      .finish()
+  }
+}
+
+/// A memory pool which checks how memory is allocated from its parent pool. Freeing or
+/// reallocating memory which is not allocated from this pool, freeing the same memory twice, and
+/// passing a size different from the allocated size all panic.
+///
+/// `leak_report()` reports memory which is still allocated, and dropping the pool with such memory
+/// panics in debug builds. Backtraces of allocations are included in the report if the pool is
+/// created by `with_backtraces()`.
+pub struct DebugMemoryPool {
+  parent: Arc<MemoryPool>,
+  capture_backtraces: bool,
+  allocations: Mutex<Allocations>
+}
+
+struct Allocations {
+  live: HashMap<usize, Allocation>,
+  // the last pages freed and not allocated again since then, which tell double frees from frees
+  // of unknown pages
+  freed: HashSet<usize>,
+  freed_order: VecDeque<usize>,
+  bytes_allocated: i64,
+  max_memory: i64
+}
+
+// the number of freed pages remembered by a debug pool
+const MAX_FREED_PAGES: usize = 1024;
+
+struct Allocation {
+  size: i64,
+  backtrace: Option<Backtrace>
+}
+
+impl Allocations {
+  fn insert(&mut self, page: *const u8, allocation: Allocation) {
+    self.bytes_allocated = self.bytes_allocated + allocation.size;
+    self.max_memory = cmp::max(self.max_memory, self.bytes_allocated);
+    if self.freed.remove(&(page as usize)) {
+      self.freed_order.retain(|&freed| freed != page as usize);
+    }
+    self.live.insert(page as usize, allocation);
+  }
+
+  /// Removes `page` after checking that it's allocated with `size` bytes, and returns its
+  /// allocation.
+  fn remove(&mut self, page: *const u8, size: i64) -> Result<Allocation, String> {
+    match self.live.get(&(page as usize)) {
+      Some(allocation) if allocation.size != size => {
+        return Err(format!("{:?} is allocated with [{}] bytes, but [{}] bytes are given", page, allocation.size, size));
+      },
+      Some(_) => {},
+      None => {
+        return if self.freed.contains(&(page as usize)) {
+          Err(format!("{:?} is already freed", page))
+        } else {
+          Err(format!("{:?} is not allocated from this pool", page))
+        };
+      }
+    }
+
+    let allocation = self.live.remove(&(page as usize)).unwrap();
+    self.bytes_allocated = self.bytes_allocated - allocation.size;
+    if self.freed.insert(page as usize) {
+      self.freed_order.push_back(page as usize);
+      if self.freed_order.len() > MAX_FREED_PAGES {
+        match self.freed_order.pop_front() {
+          Some(oldest) => { self.freed.remove(&oldest); },
+          None => {}
+        }
+      }
+    }
+    Ok(allocation)
+  }
+}
+
+impl DebugMemoryPool {
+  pub fn new(parent: Arc<MemoryPool>) -> DebugMemoryPool {
+    DebugMemoryPool::with_options(parent, false)
+  }
+
+  /// Creates a pool which captures the backtrace of every allocation. This is slow, but the leak
+  /// report tells where leaked memory is allocated.
+  pub fn with_backtraces(parent: Arc<MemoryPool>) -> DebugMemoryPool {
+    DebugMemoryPool::with_options(parent, true)
+  }
+
+  fn with_options(parent: Arc<MemoryPool>, capture_backtraces: bool) -> DebugMemoryPool {
+    DebugMemoryPool {
+      parent,
+      capture_backtraces,
+      allocations: Mutex::new(Allocations {
+        live: HashMap::new(),
+        freed: HashSet::new(),
+        freed_order: VecDeque::new(),
+        bytes_allocated: 0,
+        max_memory: 0
+      })
+    }
+  }
+
+  pub fn num_live_allocations(&self) -> usize {
+    self.lock().live.len()
+  }
+
+  /// Returns the report of the memory which is not freed yet, or `None` if every allocation is
+  /// freed.
+  pub fn leak_report(&self) -> Option<String> {
+    let allocations = self.lock();
+    if allocations.live.is_empty() {
+      None
+    } else {
+      let mut report = format!("[{}] bytes in [{}] allocations are not freed", allocations.bytes_allocated, allocations.live.len());
+      for (page, allocation) in allocations.live.iter() {
+        report.push_str(&format!("\n  {:?} of [{}] bytes", *page as *const u8, allocation.size));
+        match allocation.backtrace {
+          Some(ref backtrace) => report.push_str(&format!(" allocated at\n{}", backtrace)),
+          None => {}
+        }
+      }
+      Some(report)
+    }
+  }
+
+  // the lock is never held while panicking, but a poisoned lock must not hide the report
+  fn lock<'a>(&'a self) -> MutexGuard<'a, Allocations> {
+    match self.allocations.lock() {
+      Ok(guard) => guard,
+      Err(poisoned) => poisoned.into_inner()
+    }
+  }
+
+  fn new_allocation(&self, size: i64) -> Allocation {
+    Allocation {
+      size,
+      backtrace: if self.capture_backtraces { Some(Backtrace::force_capture()) } else { None }
+    }
+  }
+}
+
+impl MemoryPool for DebugMemoryPool {
+  fn allocate(&self, size: i64) -> Result<*const u8, ArrowError> {
+    match self.parent.allocate(size) {
+      Ok(page) => {
+        let allocation = self.new_allocation(size);
+        self.lock().insert(page, allocation);
+        Ok(page)
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn reallocate(&self, old_size: i64, new_size: i64, page: *const u8) -> Result<*const u8, ArrowError> {
+    // the page is removed before it's passed to the parent, so that no other thread can free or
+    // reallocate it at the same time. Pages of size 0 are not allocated yet.
+    let old_allocation = if old_size > 0 {
+      let removed = self.lock().remove(page, old_size);
+      match removed {
+        Ok(allocation) => Some(allocation),
+        Err(message) => panic!("cannot reallocate: {}", message)
+      }
+    } else {
+      None
+    };
+
+    match self.parent.reallocate(old_size, new_size, page) {
+      Ok(new_page) => {
+        let allocation = self.new_allocation(new_size);
+        self.lock().insert(new_page, allocation);
+        Ok(new_page)
+      },
+      Err(e) => {
+        // the page is still allocated if it can't be reallocated
+        match old_allocation {
+          Some(allocation) => self.lock().insert(page, allocation),
+          None => {}
+        }
+        Err(e)
+      }
+    }
+  }
+
+  fn free(&self, page: *const u8, size: i64) {
+    let removed = self.lock().remove(page, size);
+    match removed {
+      Ok(_) => self.parent.free(page, size),
+      Err(message) => panic!("cannot free: {}", message)
+    }
+  }
+
+  fn bytes_allocated(&self) -> i64 {
+    self.lock().bytes_allocated
+  }
+
+  fn max_memory(&self) -> i64 {
+    self.lock().max_memory
+  }
+}
+
+impl Drop for DebugMemoryPool {
+  fn drop(&mut self) {
+    // a panic while panicking aborts, which would hide the original panic
+    if cfg!(debug_assertions) && !thread::panicking() {
+      match self.leak_report() {
+        Some(report) => panic!("memory leak detected: {}", report),
+        None => {}
+      }
+    }
+  }
+}
+
 const ALIGNMENT: usize = 64;
 
+// pages of this size or larger are mapped directly, so that they can be resized by remapping
+// instead of copying
+const MMAP_THRESHOLD: i64 = 1 << 17;
+
+#[inline]
+fn is_mapped(size: i64) -> bool {
+  size >= MMAP_THRESHOLD
+}
+
+fn allocate_page(size: i64) -> Result<*const u8, ArrowError> {
+  if is_mapped(size) {
+    map_pages(size)
+  } else {
+    allocate_aligned(size)
+  }
+}
+
+fn free_page(page: *const u8, size: i64) {
+  unsafe {
+    if is_mapped(size) {
+      libc::munmap(mem::transmute::<*const u8, *mut libc::c_void>(page), size as usize);
+    } else {
+      libc::free(mem::transmute::<*const u8, *mut libc::c_void>(page));
+    }
+  }
+}
+
+fn copy_page(page: *const u8, old_size: i64, new_size: i64) -> Result<*const u8, ArrowError> {
+  match allocate_page(new_size) {
+    Ok(new_page) => {
+      unsafe {
+        libc::memcpy(
+          mem::transmute::<*const u8, *mut libc::c_void>(new_page),
+          mem::transmute::<*const u8, *const libc::c_void>(page),
+          cmp::min(old_size, new_size) as usize
+        );
+      }
+      free_page(page, old_size);
+      Ok(new_page)
+    },
+    Err(e) => Err(e)
+  }
+}
+
+// malloc may reserve more memory than requested, so heap pages are grown in place if their
+// allocation is large enough. Otherwise, they are copied into a new page before the old one is
+// freed, so that the old page is still valid if the allocation fails.
+#[cfg(target_os = "linux")]
+fn realloc_page(page: *const u8, old_size: i64, new_size: i64) -> Result<*const u8, ArrowError> {
+  let usable_size = unsafe { libc::malloc_usable_size(page as *mut libc::c_void) };
+  if usable_size >= new_size as usize {
+    Ok(page)
+  } else {
+    copy_page(page, old_size, new_size)
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn realloc_page(page: *const u8, old_size: i64, new_size: i64) -> Result<*const u8, ArrowError> {
+  copy_page(page, old_size, new_size)
+}
+
+// mapped pages are aligned to the page size, which is a multiple of ALIGNMENT
+fn map_pages(size: i64) -> Result<*const u8, ArrowError> {
+  unsafe {
+    let page = libc::mmap(
+      ptr::null_mut(),
+      size as usize,
+      libc::PROT_READ | libc::PROT_WRITE,
+      libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+      -1,
+      0
+    );
+    if page == libc::MAP_FAILED {
+      Err(ArrowError::out_of_memory(format!("mmap of size {} failed", size)))
+    } else {
+      Ok(mem::transmute::<*mut libc::c_void, *const u8>(page))
+    }
+  }
+}
+
+fn page_size() -> usize {
+  unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+// `to` must be a power of two
+#[inline]
+fn round_up(size: usize, to: usize) -> usize {
+  (size + to - 1) & !(to - 1)
+}
+
+#[cfg(target_os = "linux")]
+fn remap_pages(page: *const u8, old_size: i64, new_size: i64) -> Result<*const u8, ArrowError> {
+  unsafe {
+    let new_page = libc::mremap(
+      mem::transmute::<*const u8, *mut libc::c_void>(page),
+      old_size as usize,
+      new_size as usize,
+      libc::MREMAP_MAYMOVE
+    );
+    // the old page is still valid if remapping fails
+    if new_page == libc::MAP_FAILED {
+      Err(ArrowError::out_of_memory(format!("mremap of size {} failed", new_size)))
+    } else {
+      Ok(mem::transmute::<*mut libc::c_void, *const u8>(new_page))
+    }
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn remap_pages(page: *const u8, old_size: i64, new_size: i64) -> Result<*const u8, ArrowError> {
+  copy_page(page, old_size, new_size)
+}
+
 fn allocate_aligned(size: i64) -> Result<*const u8, ArrowError> {
   unsafe {
     let mut page: *mut libc::c_void = mem::uninitialized();
@@ -128,11 +901,14 @@ fn allocate_aligned(size: i64) -> Result<*const u8, ArrowError> {
 
 #[cfg(test)]
 mod tests {
-  use memory_pool::{DefaultMemoryPool, MemoryPool};
+  use memory_pool::{DefaultMemoryPool, GlobalAllocMemoryPool, MmapMemoryPool, LimitedMemoryPool, DebugMemoryPool, MemoryPool};
+  use memory_pool::MAX_FREED_PAGES;
+  use common::status::StatusCode;
+  use std::sync::Arc;
 
   #[test]
   fn test_allocate() {
-    let mut pool = DefaultMemoryPool::new();
+    let pool = DefaultMemoryPool::new();
     match pool.allocate(100) {
       Ok(page) => {
         assert_eq!(100, pool.bytes_allocated());
@@ -148,7 +924,7 @@ mod tests {
 
   #[test]
   fn test_allocate2() {
-    let mut pool = DefaultMemoryPool::new();
+    let pool = DefaultMemoryPool::new();
     let mut expected: Vec<(*const u8, i64)> = Vec::new();
 
     let mut next_len = 10;
@@ -176,7 +952,7 @@ mod tests {
 
   #[test]
   fn test_reallocate() {
-    let mut pool = DefaultMemoryPool::new();
+    let pool = DefaultMemoryPool::new();
     let page = match pool.allocate(100) {
       Ok(page) => page,
       Err(e) => panic!("{}", e.message())
@@ -202,4 +978,371 @@ mod tests {
     assert_eq!(0, pool.bytes_allocated());
     assert_eq!(200, pool.max_memory());
   }
+
+  fn fill(page: *const u8, len: i64) {
+    for i in 0..len {
+      unsafe { *(page as *mut u8).offset(i as isize) = (i % 251) as u8; }
+    }
+  }
+
+  fn check_filled(page: *const u8, len: i64) {
+    for i in 0..len {
+      assert_eq!((i % 251) as u8, unsafe { *page.offset(i as isize) });
+    }
+  }
+
+  #[test]
+  fn test_reallocate_heap_pages() {
+    let pool = DefaultMemoryPool::new();
+    let mut size = 64;
+    let mut page = pool.allocate(size).unwrap();
+    fill(page, size);
+    // heap pages stay aligned whether they are grown in place or copied
+    while size < 1 << 16 {
+      page = pool.reallocate(size, size * 2, page).unwrap();
+      assert_eq!(0, page as usize % 64);
+      check_filled(page, size);
+      size *= 2;
+      fill(page, size);
+    }
+    assert_eq!(size, pool.bytes_allocated());
+    pool.free(page, size);
+    assert_eq!(0, pool.bytes_allocated());
+  }
+
+  #[test]
+  #[cfg(target_os = "linux")]
+  fn test_reallocate_heap_page_in_place() {
+    let pool = DefaultMemoryPool::new();
+    let page = pool.allocate(100).unwrap();
+    fill(page, 100);
+    // a heap page grows in place up to the usable size of its allocation
+    let usable_size = unsafe { libc::malloc_usable_size(page as *mut libc::c_void) } as i64;
+    let grown = pool.reallocate(100, usable_size, page).unwrap();
+    assert_eq!(page, grown);
+    check_filled(grown, 100);
+    assert_eq!(usable_size, pool.bytes_allocated());
+    pool.free(grown, usable_size);
+    assert_eq!(0, pool.bytes_allocated());
+  }
+
+  #[test]
+  fn test_reallocate_large_pages() {
+    let pool = DefaultMemoryPool::new();
+    let small = 1 << 10;
+    let large = 1 << 18;
+
+    let page = pool.allocate(small).unwrap();
+    fill(page, small);
+
+    // a heap page shrinks in place
+    let shrunk = pool.reallocate(small, small / 2, page).unwrap();
+    assert_eq!(page, shrunk);
+    assert_eq!(small / 2, pool.bytes_allocated());
+    check_filled(shrunk, small / 2);
+
+    // from a heap page to a mapped page
+    let page = pool.reallocate(small / 2, large, shrunk).unwrap();
+    assert_eq!(0, page as usize % 64);
+    check_filled(page, small / 2);
+    fill(page, large);
+
+    // between mapped pages
+    let page = pool.reallocate(large, large * 4, page).unwrap();
+    assert_eq!(large * 4, pool.bytes_allocated());
+    check_filled(page, large);
+    let page = pool.reallocate(large * 4, large, page).unwrap();
+    check_filled(page, large);
+
+    // from a mapped page to a heap page
+    let page = pool.reallocate(large, small, page).unwrap();
+    check_filled(page, small);
+
+    pool.free(page, small);
+    assert_eq!(0, pool.bytes_allocated());
+    assert_eq!(large * 4, pool.max_memory());
+  }
+
+  #[test]
+  fn test_allocate_concurrently() {
+    use std::thread;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let handles: Vec<_> = (0..8).map(|_| {
+      let pool = pool.clone();
+      thread::spawn(move || {
+        let pages: Vec<*const u8> = (0..100).map(|_| pool.allocate(64).unwrap()).collect();
+        for page in pages {
+          pool.free(page, 64);
+        }
+      })
+    }).collect();
+    for handle in handles {
+      handle.join().unwrap();
+    }
+
+    assert_eq!(0, pool.bytes_allocated());
+    assert!(pool.max_memory() >= 6400);
+  }
+
+  #[test]
+  fn test_global_alloc_pool() {
+    let pool = GlobalAllocMemoryPool::with_alignment(256);
+    let page = pool.allocate(1000).unwrap();
+    assert_eq!(0, page as usize % 256);
+    fill(page, 1000);
+
+    let page = pool.reallocate(1000, 100000, page).unwrap();
+    assert_eq!(0, page as usize % 256);
+    check_filled(page, 1000);
+    assert_eq!(100000, pool.bytes_allocated());
+
+    let page = pool.reallocate(100000, 500, page).unwrap();
+    check_filled(page, 500);
+    pool.free(page, 500);
+    assert_eq!(0, pool.bytes_allocated());
+    assert_eq!(100000, pool.max_memory());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_global_alloc_pool_invalid_alignment() {
+    GlobalAllocMemoryPool::with_alignment(48);
+  }
+
+  #[test]
+  fn test_mmap_pool() {
+    let pool = MmapMemoryPool::new();
+    let page = pool.allocate(1000).unwrap();
+    assert_eq!(0, page as usize % pool.alignment());
+    fill(page, 1000);
+
+    let page = pool.reallocate(1000, 1 << 20, page).unwrap();
+    check_filled(page, 1000);
+    fill(page, 1 << 20);
+    let page = pool.reallocate(1 << 20, 5000, page).unwrap();
+    check_filled(page, 5000);
+    assert_eq!(5000, pool.bytes_allocated());
+
+    pool.free(page, 5000);
+    assert_eq!(0, pool.bytes_allocated());
+    assert_eq!(1 << 20, pool.max_memory());
+  }
+
+  #[test]
+  fn test_mmap_pool_with_huge_pages() {
+    let pool = MmapMemoryPool::with_huge_pages();
+    assert!(pool.uses_huge_pages());
+    assert_eq!(1 << 21, pool.alignment());
+
+    let pages: Vec<*const u8> = (0..4).map(|_| pool.allocate(3 << 20).unwrap()).collect();
+    for page in &pages {
+      assert_eq!(0, *page as usize % (1 << 21));
+      fill(*page, 3 << 20);
+    }
+
+    // reallocated pages stay aligned
+    let page = pool.reallocate(3 << 20, 10 << 20, pages[0]).unwrap();
+    assert_eq!(0, page as usize % (1 << 21));
+    check_filled(page, 3 << 20);
+
+    pool.free(page, 10 << 20);
+    for page in &pages[1..] {
+      check_filled(*page, 3 << 20);
+      pool.free(*page, 3 << 20);
+    }
+    assert_eq!(0, pool.bytes_allocated());
+  }
+
+  #[test]
+  fn test_buffers_from_allocator_pools() {
+    use buffer::{Buffer, BufferBuilder, TypedBufferBuilder};
+
+    let pools: Vec<Arc<MemoryPool>> = vec![Arc::new(GlobalAllocMemoryPool::new()), Arc::new(MmapMemoryPool::new())];
+    for pool in pools {
+      let mut builder = BufferBuilder::new(pool.clone());
+      for i in 0..10000 {
+        builder.append_typed_val(i as i64).unwrap();
+      }
+      let buffer = builder.finish();
+      assert_eq!(80000, buffer.size());
+      assert_eq!(9999, unsafe { *(buffer.data() as *const i64).offset(9999) });
+
+      drop(buffer);
+      assert_eq!(0, pool.bytes_allocated());
+    }
+  }
+
+  #[test]
+  fn test_limited_pool() {
+    let root = Arc::new(DefaultMemoryPool::new());
+    let pool = LimitedMemoryPool::new("query", root.clone(), 1000);
+
+    let page = pool.allocate(600).unwrap();
+    assert_eq!(600, pool.bytes_allocated());
+    assert_eq!(600, root.bytes_allocated());
+    assert_eq!(400, pool.available());
+
+    let e = pool.allocate(500).unwrap_err();
+    assert_eq!(&StatusCode::OutOfMemory, e.code());
+    assert_eq!(600, pool.bytes_allocated());
+    assert_eq!(600, root.bytes_allocated());
+
+    let e = pool.reallocate(600, 1100, page).unwrap_err();
+    assert_eq!(&StatusCode::OutOfMemory, e.code());
+    let page = pool.reallocate(600, 1000, page).unwrap();
+    assert_eq!(1000, pool.bytes_allocated());
+    let page = pool.reallocate(1000, 100, page).unwrap();
+    assert_eq!(100, pool.bytes_allocated());
+    assert_eq!(1000, pool.max_memory());
+
+    pool.free(page, 100);
+    assert_eq!(0, pool.bytes_allocated());
+    assert_eq!(0, root.bytes_allocated());
+  }
+
+  #[test]
+  fn test_limited_child_pools() {
+    let root = Arc::new(DefaultMemoryPool::new());
+    let query = LimitedMemoryPool::new("query", root.clone(), 1000);
+    let scan = LimitedMemoryPool::child(&query, "scan", 800);
+    let sort = LimitedMemoryPool::child(&query, "sort", 800);
+
+    let scan_page = scan.allocate(700).unwrap();
+    // the child has enough memory, but the parent doesn't
+    let e = sort.allocate(400).unwrap_err();
+    assert_eq!(&StatusCode::OutOfMemory, e.code());
+    assert_eq!(0, sort.bytes_allocated());
+    let sort_page = sort.allocate(300).unwrap();
+
+    assert_eq!(700, scan.bytes_allocated());
+    assert_eq!(300, sort.bytes_allocated());
+    assert_eq!(1000, query.bytes_allocated());
+    assert_eq!(1000, root.bytes_allocated());
+
+    let usage: Vec<(String, i64)> = query.children().iter()
+      .map(|child| (String::from(child.name()), child.bytes_allocated()))
+      .collect();
+    assert_eq!(vec![(String::from("scan"), 700), (String::from("sort"), 300)], usage);
+
+    scan.free(scan_page, 700);
+    sort.free(sort_page, 300);
+    assert_eq!(0, query.bytes_allocated());
+    assert_eq!(1000, query.max_memory());
+
+    drop(scan);
+    assert_eq!(1, query.children().len());
+    assert_eq!("sort", query.children()[0].name());
+  }
+
+  #[test]
+  fn test_buffers_from_limited_pool() {
+    use buffer::{PoolBuffer, ResizableBuffer};
+
+    let pool = LimitedMemoryPool::new("buffers", Arc::new(DefaultMemoryPool::new()), 256);
+    let mut buffer = PoolBuffer::new(pool.clone());
+    buffer.resize(200).unwrap();
+    assert_eq!(256, pool.bytes_allocated());
+    let e = buffer.resize(300).unwrap_err();
+    assert_eq!(&StatusCode::OutOfMemory, e.code());
+    assert_eq!(200, buffer.size());
+
+    drop(buffer);
+    assert_eq!(0, pool.bytes_allocated());
+  }
+
+  #[test]
+  fn test_debug_pool() {
+    let root = Arc::new(DefaultMemoryPool::new());
+    let pool = DebugMemoryPool::new(root.clone());
+
+    let p1 = pool.allocate(100).unwrap();
+    let p2 = pool.allocate(200).unwrap();
+    assert_eq!(2, pool.num_live_allocations());
+    assert_eq!(300, pool.bytes_allocated());
+
+    let p1 = pool.reallocate(100, 400, p1).unwrap();
+    assert_eq!(600, pool.bytes_allocated());
+    assert_eq!(600, pool.max_memory());
+
+    pool.free(p2, 200);
+    let report = pool.leak_report().unwrap();
+    assert!(report.starts_with("[400] bytes in [1] allocations are not freed"));
+    assert!(report.contains(&format!("{:?} of [400] bytes", p1)));
+
+    pool.free(p1, 400);
+    assert_eq!(0, pool.num_live_allocations());
+    assert!(pool.leak_report().is_none());
+    assert_eq!(0, root.bytes_allocated());
+  }
+
+  #[test]
+  fn test_debug_pool_backtraces() {
+    let pool = DebugMemoryPool::with_backtraces(Arc::new(DefaultMemoryPool::new()));
+    let page = pool.allocate(64).unwrap();
+    assert!(pool.leak_report().unwrap().contains("allocated at"));
+    pool.free(page, 64);
+  }
+
+  #[test]
+  fn test_debug_pool_buffer_leak() {
+    use buffer::{PoolBuffer, ResizableBuffer};
+
+    let pool = Arc::new(DebugMemoryPool::new(Arc::new(DefaultMemoryPool::new())));
+    let mut buffer = PoolBuffer::new(pool.clone());
+    buffer.resize(100).unwrap();
+    buffer.resize(1000).unwrap();
+    assert_eq!(1, pool.num_live_allocations());
+
+    let page = buffer.data();
+    ::std::mem::forget(buffer);
+    assert!(pool.leak_report().unwrap().contains(&format!("{:?} of [1024] bytes", page)));
+    pool.free(page, 1024);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "memory leak detected")]
+  fn test_debug_pool_leak_on_drop() {
+    let pool = DebugMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+    pool.allocate(100).unwrap();
+  }
+
+  #[test]
+  fn test_debug_pool_forgets_old_frees() {
+    let pool = DebugMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+    for _ in 0..2 * MAX_FREED_PAGES {
+      let page = pool.allocate(100).unwrap();
+      pool.free(page, 100);
+    }
+    let allocations = pool.lock();
+    assert!(allocations.freed.len() <= MAX_FREED_PAGES);
+    assert_eq!(allocations.freed.len(), allocations.freed_order.len());
+  }
+
+  #[test]
+  #[should_panic(expected = "is already freed")]
+  fn test_debug_pool_double_free() {
+    let pool = DebugMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+    let page = pool.allocate(100).unwrap();
+    pool.free(page, 100);
+    pool.free(page, 100);
+  }
+
+  #[test]
+  #[should_panic(expected = "is allocated with [100] bytes, but [50] bytes are given")]
+  fn test_debug_pool_size_mismatch() {
+    let pool = DebugMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+    let page = pool.allocate(100).unwrap();
+    pool.reallocate(50, 200, page).unwrap();
+  }
+
+  #[test]
+  #[should_panic(expected = "is not allocated from this pool")]
+  fn test_debug_pool_unknown_page() {
+    let root = Arc::new(DefaultMemoryPool::new());
+    let pool = DebugMemoryPool::new(root.clone());
+    let page = root.allocate(100).unwrap();
+    pool.free(page, 100);
+  }
 }
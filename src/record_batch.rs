@@ -0,0 +1,203 @@
+use array::Array;
+use common::schema::Schema;
+use common::status::ArrowError;
+
+use std::sync::Arc;
+
+/// Equal-length columns described by a schema. The columns are validated against the schema when
+/// the batch is created.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct RecordBatch {
+  schema: Arc<Schema>,
+  num_rows: i64,
+  columns: Vec<Array>
+}
+
+impl RecordBatch {
+  /// Creates a batch of `columns`. This fails with `StatusCode::Invalid` if the number of columns
+  /// differs from the number of fields, a column type differs from its field type, a column of a
+  /// non-nullable field has nulls, or columns have different lengths.
+  pub fn new(schema: Arc<Schema>, columns: Vec<Array>) -> Result<RecordBatch, ArrowError> {
+    let num_rows = columns.first().map_or(0, |column| column.len());
+    match RecordBatch::validate(&schema, num_rows, &columns) {
+      Ok(_) => Ok(RecordBatch {
+        schema,
+        num_rows,
+        columns
+      }),
+      Err(e) => Err(e)
+    }
+  }
+
+  fn validate(schema: &Schema, num_rows: i64, columns: &Vec<Array>) -> Result<(), ArrowError> {
+    if schema.num_fields() != columns.len() {
+      return Err(ArrowError::invalid(
+        format!("number of columns [{}] is different from number of fields [{}]", columns.len(), schema.num_fields())
+      ));
+    }
+    for (field, column) in schema.fields().iter().zip(columns.iter()) {
+      if field.data_type() != column.ty() {
+        return Err(ArrowError::invalid(
+          format!("column [{}] is of type {:?}, but {:?} is expected", field.name(), column.ty(), field.data_type())
+        ));
+      }
+      if column.len() != num_rows {
+        return Err(ArrowError::invalid(
+          format!("column [{}] has [{}] rows, but [{}] rows are expected", field.name(), column.len(), num_rows)
+        ));
+      }
+      if !field.nullable() && column.null_count() > 0 {
+        return Err(ArrowError::invalid(format!("non-nullable column [{}] has nulls", field.name())));
+      }
+    }
+    Ok(())
+  }
+
+  #[inline]
+  pub fn schema(&self) -> &Arc<Schema> {
+    &self.schema
+  }
+
+  #[inline]
+  pub fn num_rows(&self) -> i64 {
+    self.num_rows
+  }
+
+  #[inline]
+  pub fn num_columns(&self) -> usize {
+    self.columns.len()
+  }
+
+  #[inline]
+  pub fn column(&self, i: usize) -> &Array {
+    &self.columns[i]
+  }
+
+  #[inline]
+  pub fn columns(&self) -> &Vec<Array> {
+    &self.columns
+  }
+
+  pub fn column_by_name(&self, name: &str) -> Result<&Array, ArrowError> {
+    match self.schema.index_of(name) {
+      Ok(i) => Ok(&self.columns[i]),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns a batch of the columns at `indices` in the given order.
+  pub fn project(&self, indices: &[usize]) -> Result<RecordBatch, ArrowError> {
+    match self.schema.project(indices) {
+      Ok(schema) => Ok(RecordBatch {
+        schema: Arc::new(schema),
+        num_rows: self.num_rows,
+        columns: indices.iter().map(|&i| self.columns[i].clone()).collect()
+      }),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns a zero-copy slice of `length` rows starting from `offset`.
+  pub fn slice(&self, offset: i64, length: i64) -> RecordBatch {
+    if offset < 0 || length < 0 || offset + length > self.num_rows {
+      panic!("slice [{}, {}) is out of the batch of [{}] rows", offset, offset + length, self.num_rows)
+    }
+
+    RecordBatch {
+      schema: self.schema.clone(),
+      num_rows: length,
+      columns: self.columns.iter().map(|column| column.slice(offset, length)).collect()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use array::{Array, ArrowSlice};
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use common::field::Field;
+  use common::schema::Schema;
+  use common::status::StatusCode;
+  use common::ty::Ty;
+  use memory_pool::DefaultMemoryPool;
+  use record_batch::RecordBatch;
+  use std::sync::Arc;
+
+  fn int_array(pool: &Arc<DefaultMemoryPool>, values: &[Option<i32>]) -> Array {
+    let mut builder = ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    for value in values {
+      match *value {
+        Some(value) => builder.append(value).unwrap(),
+        None => builder.append_null().unwrap()
+      }
+    }
+    Array::from(builder)
+  }
+
+  #[test]
+  fn test_record_batch() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = Arc::new(Schema::new(vec![
+      Field::non_null(String::from("id"), Ty::Int32),
+      Field::new(String::from("value"), Ty::Int32)
+    ]));
+    let ids = int_array(&pool, &[Some(1), Some(2), Some(3), Some(4)]);
+    let values = int_array(&pool, &[Some(10), None, Some(30), None]);
+
+    let batch = RecordBatch::new(schema.clone(), vec![ids.clone(), values.clone()]).unwrap();
+    assert_eq!(4, batch.num_rows());
+    assert_eq!(2, batch.num_columns());
+    assert_eq!(&values, batch.column_by_name("value").unwrap());
+    assert_eq!(&StatusCode::KeyError, batch.column_by_name("name").unwrap_err().code());
+
+    let projected = batch.project(&[1]).unwrap();
+    assert_eq!(1, projected.num_columns());
+    assert_eq!("value", projected.schema().field(0).name());
+    assert_eq!(4, projected.num_rows());
+
+    let slice = batch.slice(1, 2);
+    assert_eq!(2, slice.num_rows());
+    assert_eq!(&[2, 3], ArrowSlice::<i32>::values(slice.column(0)));
+    assert_eq!(1, slice.column(1).null_count());
+  }
+
+  #[test]
+  fn test_invalid_record_batch() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = Arc::new(Schema::new(vec![
+      Field::non_null(String::from("id"), Ty::Int32),
+      Field::new(String::from("value"), Ty::Int64)
+    ]));
+    let ids = int_array(&pool, &[Some(1), Some(2)]);
+
+    let e = RecordBatch::new(schema.clone(), vec![ids.clone()]).unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+
+    // type mismatch
+    let e = RecordBatch::new(schema.clone(), vec![ids.clone(), ids.clone()]).unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+
+    let schema = Arc::new(Schema::new(vec![
+      Field::non_null(String::from("id"), Ty::Int32),
+      Field::new(String::from("value"), Ty::Int32)
+    ]));
+    let e = RecordBatch::new(schema.clone(), vec![ids.clone(), int_array(&pool, &[Some(1)])]).unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+
+    let e = RecordBatch::new(schema.clone(), vec![int_array(&pool, &[None, Some(2)]), ids.clone()]).unwrap_err();
+    assert_eq!(&StatusCode::Invalid, e.code());
+
+    let empty = RecordBatch::new(Arc::new(Schema::new(Vec::new())), Vec::new()).unwrap();
+    assert_eq!(0, empty.num_rows());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_slice_record_batch_out_of_range() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = Arc::new(Schema::new(vec![Field::new(String::from("id"), Ty::Int32)]));
+    let batch = RecordBatch::new(schema, vec![int_array(&pool, &[Some(1), Some(2)])]).unwrap();
+    batch.slice(1, 2);
+  }
+}
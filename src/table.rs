@@ -0,0 +1,295 @@
+use chunked_array::ChunkedArray;
+use common::schema::Schema;
+use common::status::ArrowError;
+use record_batch::RecordBatch;
+
+use std::cmp;
+use std::sync::Arc;
+
+/// Record batches sharing a schema, which are viewed as a single table. Each column of the table is
+/// split into chunks by the batches. Batches are not copied when tables are created, sliced or
+/// concatenated.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Table {
+  schema: Arc<Schema>,
+  batches: Vec<RecordBatch>,
+  num_rows: i64
+}
+
+impl Table {
+  /// Creates a table of `batches`. This fails with `StatusCode::Invalid` if the schema of a batch
+  /// is different from `schema`. The metadata of the schema and its fields is ignored in the
+  /// comparison, but column types must be identical including the metadata of nested fields,
+  /// because the chunks of a column share its type.
+  pub fn new(schema: Arc<Schema>, batches: Vec<RecordBatch>) -> Result<Table, ArrowError> {
+    for (i, batch) in batches.iter().enumerate() {
+      let same_types = batch.schema().fields().iter().zip(schema.fields().iter())
+        .all(|(field, expected)| field.data_type() == expected.data_type());
+      if !batch.schema().equals(&schema, false) || !same_types {
+        return Err(ArrowError::invalid(
+          format!("schema of batch [{}] is {:?}, but {:?} is expected", i, batch.schema(), schema)
+        ));
+      }
+    }
+    let num_rows = batches.iter().map(|batch| batch.num_rows()).sum();
+    Ok(Table {
+      schema,
+      batches,
+      num_rows
+    })
+  }
+
+  /// Creates a table of `batches` with the schema of the first batch.
+  pub fn from_batches(batches: Vec<RecordBatch>) -> Result<Table, ArrowError> {
+    let schema = match batches.first() {
+      Some(batch) => batch.schema().clone(),
+      None => return Err(ArrowError::invalid(String::from("cannot infer the schema of a table without batches")))
+    };
+    Table::new(schema, batches)
+  }
+
+  /// Concatenates tables sharing a schema. The result has the schema of the first table.
+  pub fn concat(tables: &[Table]) -> Result<Table, ArrowError> {
+    let schema = match tables.first() {
+      Some(table) => table.schema.clone(),
+      None => return Err(ArrowError::invalid(String::from("cannot concatenate no tables")))
+    };
+    let batches = tables.iter().flat_map(|table| table.batches.iter().cloned()).collect();
+    Table::new(schema, batches)
+  }
+
+  #[inline]
+  pub fn schema(&self) -> &Arc<Schema> {
+    &self.schema
+  }
+
+  #[inline]
+  pub fn num_rows(&self) -> i64 {
+    self.num_rows
+  }
+
+  #[inline]
+  pub fn num_columns(&self) -> usize {
+    self.schema.num_fields()
+  }
+
+  #[inline]
+  pub fn num_batches(&self) -> usize {
+    self.batches.len()
+  }
+
+  #[inline]
+  pub fn batch(&self, i: usize) -> &RecordBatch {
+    &self.batches[i]
+  }
+
+  #[inline]
+  pub fn batches(&self) -> &Vec<RecordBatch> {
+    &self.batches
+  }
+
+  /// Returns the i-th column, which is split into chunks by the batches.
+  pub fn column(&self, i: usize) -> ChunkedArray {
+    let chunks = self.batches.iter().map(|batch| batch.column(i).clone()).collect();
+    match ChunkedArray::with_type(self.schema.field(i).data_type().clone(), chunks) {
+      Ok(column) => column,
+      // column types of the batches are checked when the table is created
+      Err(_) => unreachable!()
+    }
+  }
+
+  pub fn column_by_name(&self, name: &str) -> Result<ChunkedArray, ArrowError> {
+    match self.schema.index_of(name) {
+      Ok(i) => Ok(self.column(i)),
+      Err(e) => Err(e)
+    }
+  }
+
+  /// Returns a table of the columns at `indices` in the given order.
+  pub fn select(&self, indices: &[usize]) -> Result<Table, ArrowError> {
+    let schema = match self.schema.project(indices) {
+      Ok(schema) => Arc::new(schema),
+      Err(e) => return Err(e)
+    };
+    let mut batches = Vec::with_capacity(self.batches.len());
+    for batch in &self.batches {
+      match RecordBatch::new(schema.clone(), indices.iter().map(|&i| batch.column(i).clone()).collect()) {
+        Ok(batch) => batches.push(batch),
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(Table {
+      schema,
+      batches,
+      num_rows: self.num_rows
+    })
+  }
+
+  pub fn select_by_names(&self, names: &[&str]) -> Result<Table, ArrowError> {
+    let mut indices = Vec::with_capacity(names.len());
+    for name in names {
+      match self.schema.index_of(name) {
+        Ok(i) => indices.push(i),
+        Err(e) => return Err(e)
+      }
+    }
+    self.select(&indices)
+  }
+
+  /// Returns a zero-copy slice of `length` rows starting from `offset`. Batches are sliced at the
+  /// boundaries of the slice, and those out of the slice are dropped.
+  pub fn slice(&self, offset: i64, length: i64) -> Table {
+    if offset < 0 || length < 0 || offset + length > self.num_rows {
+      panic!("slice [{}, {}) is out of the table of [{}] rows", offset, offset + length, self.num_rows)
+    }
+
+    let end = offset + length;
+    let mut batches = Vec::new();
+    let mut batch_start = 0;
+    for batch in &self.batches {
+      let batch_end = batch_start + batch.num_rows();
+      let start = cmp::max(offset, batch_start);
+      let stop = cmp::min(end, batch_end);
+      if start < stop {
+        batches.push(batch.slice(start - batch_start, stop - start));
+      }
+      batch_start = batch_end;
+    }
+
+    Table {
+      schema: self.schema.clone(),
+      batches,
+      num_rows: length
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use array::{Array, ArrowSlice};
+  use buffer::PoolBuffer;
+  use builder::{ArrayBuilder, Append};
+  use common::field::Field;
+  use common::schema::Schema;
+  use common::status::StatusCode;
+  use common::ty::Ty;
+  use memory_pool::DefaultMemoryPool;
+  use record_batch::RecordBatch;
+  use table::Table;
+  use std::sync::Arc;
+
+  fn batch(pool: &Arc<DefaultMemoryPool>, schema: &Arc<Schema>, start: i64, num_rows: i64) -> RecordBatch {
+    let mut ids = ArrayBuilder::new_fixed_width(Ty::Int64, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap();
+    let mut names = ArrayBuilder::string(PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone()));
+    for i in start..start + num_rows {
+      ids.append(i).unwrap();
+      names.append(format!("name{}", i).as_str()).unwrap();
+    }
+    RecordBatch::new(schema.clone(), vec![Array::from(ids), Array::from(names)]).unwrap()
+  }
+
+  fn schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+      Field::new(String::from("id"), Ty::Int64),
+      Field::new(String::from("name"), Ty::String)
+    ]))
+  }
+
+  #[test]
+  fn test_table() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = schema();
+    let table = Table::new(schema.clone(), vec![batch(&pool, &schema, 0, 10), batch(&pool, &schema, 10, 5)]).unwrap();
+    assert_eq!(15, table.num_rows());
+    assert_eq!(2, table.num_columns());
+    assert_eq!(2, table.num_batches());
+
+    let ids = table.column(0);
+    assert_eq!(2, ids.num_chunks());
+    assert_eq!(15, ids.len());
+    assert_eq!(10, ids.value::<i64>(10));
+    let names = table.column_by_name("name").unwrap();
+    assert_eq!("name14", names.value::<&str>(14));
+
+    let selected = table.select_by_names(&["name"]).unwrap();
+    assert_eq!(1, selected.num_columns());
+    assert_eq!(15, selected.num_rows());
+    assert_eq!(names, selected.column(0));
+    assert_eq!(&StatusCode::KeyError, table.select_by_names(&["value"]).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, table.select(&[2]).unwrap_err().code());
+  }
+
+  #[test]
+  fn test_slice_table() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = schema();
+    let batches = vec![batch(&pool, &schema, 0, 10), batch(&pool, &schema, 10, 5), batch(&pool, &schema, 15, 10)];
+    let table = Table::from_batches(batches).unwrap();
+
+    // the slice crosses a batch boundary
+    let slice = table.slice(8, 4);
+    assert_eq!(4, slice.num_rows());
+    assert_eq!(2, slice.num_batches());
+    let ids = slice.column(0);
+    assert_eq!(&[8, 9], ArrowSlice::<i64>::values(ids.chunk(0)));
+    assert_eq!(&[10, 11], ArrowSlice::<i64>::values(ids.chunk(1)));
+    assert_eq!(ids, table.column(0).slice(8, 4));
+
+    let slice = table.slice(5, 15);
+    assert_eq!(3, slice.num_batches());
+    assert_eq!(15, slice.num_rows());
+    assert_eq!(5, slice.batch(2).num_rows());
+
+    let slice = table.slice(10, 5);
+    assert_eq!(1, slice.num_batches());
+
+    let empty = table.slice(25, 0);
+    assert_eq!(0, empty.num_rows());
+    assert_eq!(0, empty.num_batches());
+  }
+
+  #[test]
+  fn test_concat_tables() {
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let schema = schema();
+    let t1 = Table::from_batches(vec![batch(&pool, &schema, 0, 3)]).unwrap();
+    let t2 = Table::from_batches(vec![batch(&pool, &schema, 3, 4), batch(&pool, &schema, 7, 2)]).unwrap();
+    let table = Table::concat(&[t1, t2.clone()]).unwrap();
+    assert_eq!(9, table.num_rows());
+    assert_eq!(3, table.num_batches());
+    assert_eq!(t2.batch(0), table.batch(1));
+
+    let other_schema = Arc::new(Schema::new(vec![Field::new(String::from("id"), Ty::Int64)]));
+    let other = Table::from_batches(vec![batch(&pool, &schema, 0, 1).project(&[0]).unwrap()]).unwrap();
+    assert_eq!(&other_schema, other.schema());
+    assert_eq!(&StatusCode::Invalid, Table::concat(&[table, other]).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, Table::concat(&[]).unwrap_err().code());
+    assert_eq!(&StatusCode::Invalid, Table::from_batches(Vec::new()).unwrap_err().code());
+  }
+
+  #[test]
+  fn test_nested_metadata() {
+    use common::KeyValueMetadata;
+
+    let pool = Arc::new(DefaultMemoryPool::new());
+    let struct_batch = |field: Field| {
+      let field_builders = vec![ArrayBuilder::new_fixed_width(Ty::Int32, PoolBuffer::new(pool.clone()), PoolBuffer::new(pool.clone())).unwrap()];
+      let mut builder = ArrayBuilder::struct_type(PoolBuffer::new(pool.clone()), vec![field], field_builders).unwrap();
+      builder.field_builder_mut(0).append(1i32).unwrap();
+      builder.append_struct().unwrap();
+      let column = Array::from(builder);
+      let schema = Arc::new(Schema::new(vec![Field::new(String::from("s"), column.ty().clone())]));
+      RecordBatch::new(schema, vec![column]).unwrap()
+    };
+    let field = Field::new(String::from("c"), Ty::Int32);
+    let metadata = KeyValueMetadata::with_kvs(vec![String::from("origin")], vec![String::from("test")]);
+    let with_metadata = struct_batch(field.with_metadata(metadata));
+    let without_metadata = struct_batch(field);
+
+    // the chunks of a column can't have different types
+    let result = Table::from_batches(vec![with_metadata.clone(), without_metadata.clone()]);
+    assert_eq!(&StatusCode::Invalid, result.unwrap_err().code());
+    let table = Table::from_batches(vec![with_metadata.clone(), with_metadata]).unwrap();
+    assert_eq!(2, table.column(0).len());
+  }
+}